@@ -0,0 +1,211 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::header,
+    middleware::{self, Next},
+    response::Response,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clock,
+    config::{Config, ConfigView},
+    error::{Error, Result},
+    extract::{JsonBody, QueryParams},
+    id::SessionId,
+    AppContext, SharedState, Totals,
+};
+
+pub fn router(config: Arc<Config>) -> Router<AppContext> {
+    Router::new()
+        .route("/admin/finish-all", post(finish_all))
+        .route("/admin/focus-lock", post(set_focus_lock))
+        .route("/admin/maintenance", post(set_maintenance))
+        .route("/admin/reset-sessions", post(reset_sessions))
+        .route("/admin/sweep", post(sweep))
+        .route("/config", get(show_config))
+        .route_layer(middleware::from_fn_with_state(config, require_admin))
+}
+
+// Admin routes are open only when no token is configured.
+async fn require_admin(
+    State(config): State<Arc<Config>>,
+    req: Request,
+    next: Next,
+) -> Result<Response> {
+    if let Some(token) = &config.admin_token {
+        let provided = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+        if provided != Some(token.as_str()) {
+            return Err(Error::Unauthorized);
+        }
+    }
+    Ok(next.run(req).await)
+}
+
+// Every action that changes sessions takes `?dry_run=true`, which answers with
+// the ids it would affect and leaves state alone.
+#[derive(Deserialize)]
+struct DryRun {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Serialize)]
+struct FinishAll {
+    finished: usize,
+    ids: Vec<SessionId>,
+    dry_run: bool,
+}
+
+async fn finish_all(
+    State(state): State<SharedState>,
+    QueryParams(q): QueryParams<DryRun>,
+) -> Result<Json<FinishAll>> {
+    let mut guard = state.lock()?;
+    let mut ids = Vec::new();
+    for s in guard.sessions.values_mut() {
+        s.update_elapsed();
+        if s.is_unfinished() {
+            ids.push(s.id);
+        }
+    }
+    if !q.dry_run {
+        for &id in &ids {
+            if let Some(s) = guard.sessions.get_mut(&id) {
+                s.force_finish();
+            }
+            guard.touch(id);
+        }
+    }
+    Ok(Json(FinishAll {
+        finished: ids.len(),
+        ids,
+        dry_run: q.dry_run,
+    }))
+}
+
+#[derive(Serialize, Deserialize)]
+struct FocusLock {
+    enabled: bool,
+}
+
+async fn set_focus_lock(
+    State(state): State<SharedState>,
+    JsonBody(req): JsonBody<FocusLock>,
+) -> Result<Json<FocusLock>> {
+    let mut guard = state.lock()?;
+    guard.focus_lock = req.enabled;
+    guard.dirty = true;
+    Ok(Json(FocusLock {
+        enabled: guard.focus_lock,
+    }))
+}
+
+#[derive(Deserialize)]
+struct MaintenanceReq {
+    enabled: bool,
+}
+
+// `sessions` counts those frozen on entry, or resumed on exit. A dry run
+// reports `enabled` as it would end up.
+#[derive(Serialize)]
+struct Maintenance {
+    enabled: bool,
+    sessions: usize,
+    ids: Vec<SessionId>,
+    dry_run: bool,
+}
+
+async fn set_maintenance(
+    State(state): State<SharedState>,
+    QueryParams(q): QueryParams<DryRun>,
+    JsonBody(req): JsonBody<MaintenanceReq>,
+) -> Result<Json<Maintenance>> {
+    let mut guard = state.lock()?;
+    let (enabled, ids) = if q.dry_run {
+        (req.enabled, guard.maintenance_preview(req.enabled))
+    } else if req.enabled {
+        (true, guard.enter_maintenance())
+    } else {
+        (false, guard.exit_maintenance())
+    };
+    Ok(Json(Maintenance {
+        enabled,
+        sessions: ids.len(),
+        ids,
+        dry_run: q.dry_run,
+    }))
+}
+
+#[derive(Deserialize)]
+struct ResetQuery {
+    #[serde(default)]
+    keep_stats: bool,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Serialize)]
+struct Reset {
+    removed: usize,
+    ids: Vec<SessionId>,
+    totals: Totals,
+    dry_run: bool,
+}
+
+// Clears every session. `keep_stats=true` keeps `/stats` counting them.
+async fn reset_sessions(
+    State(state): State<SharedState>,
+    QueryParams(q): QueryParams<ResetQuery>,
+) -> Result<Json<Reset>> {
+    let mut guard = state.lock()?;
+    let (ids, totals) = if q.dry_run {
+        let totals = guard.totals_after_reset(q.keep_stats);
+        (guard.sessions.keys().copied().collect(), totals)
+    } else {
+        let ids = guard.reset_sessions(q.keep_stats);
+        (ids, guard.carried)
+    };
+    Ok(Json(Reset {
+        removed: ids.len(),
+        ids,
+        totals,
+        dry_run: q.dry_run,
+    }))
+}
+
+#[derive(Serialize)]
+struct Sweep {
+    swept: Vec<SessionId>,
+    dry_run: bool,
+}
+
+// Runs the sweeper's expiry pass now instead of at its next interval.
+async fn sweep(
+    State(state): State<SharedState>,
+    State(config): State<Arc<Config>>,
+    QueryParams(q): QueryParams<DryRun>,
+) -> Result<Json<Sweep>> {
+    let mut guard = state.lock()?;
+    let now = clock::utc();
+    let swept = if q.dry_run {
+        guard.expired(&config, now)
+    } else {
+        guard.sweep(&config, now)
+    };
+    Ok(Json(Sweep {
+        swept,
+        dry_run: q.dry_run,
+    }))
+}
+
+async fn show_config(State(config): State<Arc<Config>>) -> Json<ConfigView> {
+    Json(config.view())
+}
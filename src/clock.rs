@@ -0,0 +1,92 @@
+#[cfg(test)]
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local, TimeDelta, Utc};
+
+// Every reading that session timing depends on goes through `instant`, `utc`
+// and `local`. Under test they stand still, each thread's clocks fixed at
+// their first reading until the test moves them on with `advance`; a
+// current-thread test runtime keeps a request's handler on the test's thread.
+#[cfg(test)]
+thread_local! {
+    static NOW: Cell<(Instant, DateTime<Utc>)> = Cell::new((Instant::now(), Utc::now()));
+}
+
+#[cfg(test)]
+pub fn advance(by: Duration) {
+    let (instant, system) = NOW.get();
+    NOW.set((instant + by, system + TimeDelta::from_std(by).unwrap()));
+}
+
+#[cfg(test)]
+pub fn instant() -> Instant {
+    NOW.get().0
+}
+
+#[cfg(test)]
+pub fn utc() -> DateTime<Utc> {
+    NOW.get().1
+}
+
+#[cfg(test)]
+pub fn local() -> DateTime<Local> {
+    utc().with_timezone(&Local)
+}
+
+#[cfg(not(test))]
+pub fn instant() -> Instant {
+    Instant::now()
+}
+
+#[cfg(not(test))]
+pub fn utc() -> DateTime<Utc> {
+    Utc::now()
+}
+
+#[cfg(not(test))]
+pub fn local() -> DateTime<Local> {
+    Local::now()
+}
+
+// One reading of both clocks. Timers count on the monotonic `Instant`, while
+// dates that are stored or reported come from the wall clock; turning one into
+// the other through a single reading keeps both sides on the same moment
+// instead of two `now()` calls that drift apart (or straddle a clock step).
+#[derive(Clone, Copy)]
+pub struct Clock {
+    instant: Instant,
+    system: DateTime<Utc>,
+}
+
+impl Clock {
+    pub fn now() -> Self {
+        Self {
+            instant: instant(),
+            system: utc(),
+        }
+    }
+
+    pub fn now_instant(&self) -> Instant {
+        self.instant
+    }
+
+    pub fn now_system(&self) -> DateTime<Utc> {
+        self.system
+    }
+
+    // The wall-clock time of `at`, which may lie on either side of the reading.
+    pub fn system_at(&self, at: Instant) -> DateTime<Utc> {
+        let offset = |d: Duration| TimeDelta::from_std(d).unwrap_or_default();
+        match at.checked_duration_since(self.instant) {
+            Some(after) => self.system + offset(after),
+            None => self.system - offset(self.instant - at),
+        }
+    }
+
+    // When `ahead` of monotonic time from the reading will have passed; `None`
+    // only for spans too long for a date.
+    pub fn project(&self, ahead: Duration) -> Option<DateTime<Utc>> {
+        self.system.checked_add_signed(TimeDelta::from_std(ahead).ok()?)
+    }
+}
@@ -0,0 +1,458 @@
+use std::{env, path::PathBuf, time::Duration};
+
+use chrono::NaiveTime;
+use serde::Serialize;
+
+#[derive(Clone)]
+pub struct Config {
+    pub request_timeout: Duration,
+    pub state_file: Option<PathBuf>,
+    pub state_format: StateFormat,
+    pub flush_interval: Duration,
+    pub max_subscribers_per_session: usize,
+    pub sweep_interval: Duration,
+    pub idle_ttl: Option<Duration>,
+    pub finished_ttl: Option<Duration>,
+    pub admin_token: Option<String>,
+    pub uds: Option<PathBuf>,
+    pub webhook_url: Option<String>,
+    pub webhook_attempts: u32,
+    pub webhook_backoff: Duration,
+    pub list_snapshot: bool,
+    pub abandon_after: Duration,
+    pub envelope: bool,
+    pub summary_at: Option<NaiveTime>,
+    pub summary_webhook_url: Option<String>,
+    pub summary_skip_empty: bool,
+    pub quiet_hours: Option<QuietHours>,
+    pub max_concurrency: Option<usize>,
+    pub timestamp_format: TimestampFormat,
+    pub archive_capacity: usize,
+    pub archive_ttl: Duration,
+    pub history_max_entries: Option<usize>,
+    pub history_max_age: Option<Duration>,
+    pub notifiers: Vec<NotifierKind>,
+    pub shutdown_grace: Duration,
+    pub log_format: LogFormat,
+    pub enforce_breaks: bool,
+    pub id_seed: u64,
+    pub stale_read_bound: Option<Duration>,
+    pub finished_start: FinishedStart,
+    pub pulse_interval: Duration,
+    pub delete_tombstone_ttl: Option<Duration>,
+    pub nudges: Option<Nudges>,
+    pub rounding: Rounding,
+    pub inactivity_pause: Option<Duration>,
+    pub max_concurrent_running: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampFormat {
+    Rfc3339,
+    EpochMillis,
+}
+
+// How tracing output, including per-transition events, is written to stderr.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    Pretty,
+    Json,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifierKind {
+    Webhook,
+    Log,
+    Noop,
+}
+
+impl NotifierKind {
+    fn parse(name: &str) -> Option<Self> {
+        match name.trim() {
+            "webhook" => Some(Self::Webhook),
+            "log" => Some(Self::Log),
+            "noop" => Some(Self::Noop),
+            _ => None,
+        }
+    }
+}
+
+// What start (or play) does to a Finished session: refuse with 409 so its
+// record stays intact (`POST /sessions/:id/continue` makes a fresh copy), or
+// reset it and run it again in place. Patterned sessions always move on to
+// their next round.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FinishedStart {
+    Refuse,
+    Restart,
+}
+
+// How fractional seconds become the whole `elapsed_secs` and `remaining_secs`
+// in session responses. Floor matches how they were always reported; ceil
+// keeps a 0.2s remainder showing as 1s until time is really up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rounding {
+    Floor,
+    Ceil,
+    Round,
+}
+
+impl Rounding {
+    pub fn secs(self, d: Duration) -> u64 {
+        let secs = d.as_secs();
+        let frac = d.subsec_nanos();
+        match self {
+            Rounding::Floor => secs,
+            Rounding::Ceil if frac > 0 => secs.saturating_add(1),
+            Rounding::Round if frac >= 500_000_000 => secs.saturating_add(1),
+            Rounding::Ceil | Rounding::Round => secs,
+        }
+    }
+}
+
+// Chooses how the state file is written; loading detects either format.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StateFormat {
+    Json,
+    Binary,
+}
+
+// Milestones during a work phase at which a session's event stream sends a
+// `nudge`: every `every` of work done, and as each percentage of the work is
+// reached. Written as a list like "10m,90%" (units are s or m).
+#[derive(Clone, Serialize)]
+pub struct Nudges {
+    #[serde(rename = "every_secs", serialize_with = "as_secs")]
+    pub every: Option<Duration>,
+    pub percents: Vec<u8>,
+}
+
+impl Nudges {
+    fn parse(list: &str) -> Option<Self> {
+        let mut nudges = Self {
+            every: None,
+            percents: Vec::new(),
+        };
+        for item in list.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+            if let Some(percent) = item.strip_suffix('%') {
+                let percent: u8 = percent.parse().ok().filter(|p| (1..=100).contains(p))?;
+                nudges.percents.push(percent);
+                continue;
+            }
+            let secs = if let Some(n) = item.strip_suffix('s') {
+                n.parse::<u64>().ok()?
+            } else {
+                item.strip_suffix('m')?.parse::<u64>().ok()?.checked_mul(60)?
+            };
+            nudges.every = Some(Duration::from_secs(secs)).filter(|d| !d.is_zero());
+        }
+        nudges.percents.sort_unstable();
+        nudges.percents.dedup();
+        (nudges.every.is_some() || !nudges.percents.is_empty()).then_some(nudges)
+    }
+}
+
+fn as_secs<S: serde::Serializer>(every: &Option<Duration>, ser: S) -> Result<S::Ok, S::Error> {
+    every.map(|d| d.as_secs()).serialize(ser)
+}
+
+// Local-time window during which finish webhooks are held back. The window may
+// wrap past midnight (22:00-07:00). With `queue` set, held bodies go out when it
+// ends; otherwise they are dropped.
+#[derive(Clone, Copy, Serialize)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub queue: bool,
+}
+
+impl QuietHours {
+    fn parse(window: &str, queue: bool) -> Option<Self> {
+        let (start, end) = window.split_once('-')?;
+        Some(Self {
+            start: NaiveTime::parse_from_str(start.trim(), "%H:%M").ok()?,
+            end: NaiveTime::parse_from_str(end.trim(), "%H:%M").ok()?,
+            queue,
+        })
+    }
+
+    pub fn contains(&self, t: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= t && t < self.end
+        } else {
+            t >= self.start || t < self.end
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            request_timeout: Duration::from_secs(30),
+            state_file: None,
+            state_format: StateFormat::Json,
+            flush_interval: Duration::from_secs(1),
+            max_subscribers_per_session: 16,
+            sweep_interval: Duration::from_secs(60),
+            idle_ttl: None,
+            finished_ttl: None,
+            admin_token: None,
+            uds: None,
+            webhook_url: None,
+            webhook_attempts: 3,
+            webhook_backoff: Duration::from_secs(1),
+            list_snapshot: false,
+            abandon_after: Duration::from_secs(30 * 60),
+            envelope: false,
+            summary_at: None,
+            summary_webhook_url: None,
+            summary_skip_empty: false,
+            quiet_hours: None,
+            max_concurrency: None,
+            timestamp_format: TimestampFormat::Rfc3339,
+            archive_capacity: 128,
+            archive_ttl: Duration::from_secs(5 * 60),
+            history_max_entries: None,
+            history_max_age: None,
+            notifiers: vec![NotifierKind::Webhook],
+            shutdown_grace: Duration::from_secs(10),
+            log_format: LogFormat::Pretty,
+            enforce_breaks: false,
+            id_seed: 0,
+            stale_read_bound: None,
+            finished_start: FinishedStart::Refuse,
+            pulse_interval: Duration::from_secs(5),
+            delete_tombstone_ttl: None,
+            nudges: None,
+            rounding: Rounding::Floor,
+            inactivity_pause: None,
+            max_concurrent_running: None,
+        }
+    }
+}
+
+impl Config {
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Some(secs) = env_u64("POMODORO_REQUEST_TIMEOUT_SECS") {
+            config.request_timeout = Duration::from_secs(secs);
+        }
+        config.state_file = env::var_os("POMODORO_STATE_FILE").map(PathBuf::from);
+        if env::var("POMODORO_STATE_FORMAT").is_ok_and(|f| f == "binary") {
+            config.state_format = StateFormat::Binary;
+        }
+        if let Some(ms) = env_u64("POMODORO_FLUSH_INTERVAL_MS").filter(|&ms| ms > 0) {
+            config.flush_interval = Duration::from_millis(ms);
+        }
+        if let Some(max) = env_u64("POMODORO_MAX_SUBSCRIBERS_PER_SESSION") {
+            config.max_subscribers_per_session = max as usize;
+        }
+        if let Some(secs) = env_u64("POMODORO_SWEEP_INTERVAL_SECS").filter(|&secs| secs > 0) {
+            config.sweep_interval = Duration::from_secs(secs);
+        }
+        config.idle_ttl = env_u64("POMODORO_IDLE_TTL_SECS").map(Duration::from_secs);
+        config.finished_ttl = env_u64("POMODORO_FINISHED_TTL_SECS").map(Duration::from_secs);
+        config.admin_token = env::var("POMODORO_ADMIN_TOKEN").ok().filter(|t| !t.is_empty());
+        config.uds = flag_value("--uds").map(PathBuf::from);
+        config.webhook_url = env::var("POMODORO_WEBHOOK_URL").ok().filter(|u| !u.is_empty());
+        if let Some(attempts) = env_u64("POMODORO_WEBHOOK_ATTEMPTS") {
+            config.webhook_attempts = attempts as u32;
+        }
+        if let Some(ms) = env_u64("POMODORO_WEBHOOK_BACKOFF_MS") {
+            config.webhook_backoff = Duration::from_millis(ms);
+        }
+        config.list_snapshot = env_flag("POMODORO_LIST_SNAPSHOT");
+        if let Some(secs) = env_u64("POMODORO_ABANDON_AFTER_SECS") {
+            config.abandon_after = Duration::from_secs(secs);
+        }
+        config.envelope = env_flag("POMODORO_ENVELOPE");
+        // Local time of day as HH:MM; the summary goes to its own URL or the main webhook.
+        config.summary_at = env::var("POMODORO_SUMMARY_AT")
+            .ok()
+            .and_then(|v| NaiveTime::parse_from_str(&v, "%H:%M").ok());
+        config.summary_webhook_url = env::var("POMODORO_SUMMARY_WEBHOOK_URL")
+            .ok()
+            .filter(|u| !u.is_empty())
+            .or_else(|| config.webhook_url.clone());
+        config.summary_skip_empty = env_flag("POMODORO_SUMMARY_SKIP_EMPTY");
+        let queue = env::var("POMODORO_QUIET_MODE").is_ok_and(|m| m == "queue");
+        config.quiet_hours = env::var("POMODORO_QUIET_HOURS")
+            .ok()
+            .and_then(|w| QuietHours::parse(&w, queue));
+        config.max_concurrency = env_u64("POMODORO_MAX_CONCURRENCY")
+            .filter(|&n| n > 0)
+            .map(|n| n as usize);
+        if env::var("POMODORO_TIMESTAMP_FORMAT").is_ok_and(|f| f == "epoch_millis") {
+            config.timestamp_format = TimestampFormat::EpochMillis;
+        }
+        if let Some(capacity) = env_u64("POMODORO_ARCHIVE_CAPACITY") {
+            config.archive_capacity = capacity as usize;
+        }
+        if let Some(secs) = env_u64("POMODORO_ARCHIVE_TTL_SECS") {
+            config.archive_ttl = Duration::from_secs(secs);
+        }
+        // History beyond either limit is folded into per-event counts by the sweeper.
+        config.history_max_entries = env_u64("POMODORO_HISTORY_MAX_ENTRIES").map(|n| n as usize);
+        config.history_max_age = env_u64("POMODORO_HISTORY_MAX_AGE_SECS").map(Duration::from_secs);
+        // Comma-separated sinks for finish events, e.g. "webhook,log".
+        if let Ok(names) = env::var("POMODORO_NOTIFIERS") {
+            config.notifiers = names.split(',').filter_map(NotifierKind::parse).collect();
+        }
+        // How long shutdown waits for webhook deliveries that are still retrying.
+        if let Some(secs) = env_u64("POMODORO_SHUTDOWN_GRACE_SECS") {
+            config.shutdown_grace = Duration::from_secs(secs);
+        }
+        if env::var("POMODORO_LOG_FORMAT").is_ok_and(|f| f == "json") {
+            config.log_format = LogFormat::Json;
+        }
+        // Refuses to start work while any other session is still on its break.
+        config.enforce_breaks = env_flag("POMODORO_ENFORCE_BREAKS");
+        // Session ids start above this, so a server without a state file can be
+        // restarted without reissuing ids that outside systems still hold.
+        if let Some(seed) = env_u64("POMODORO_ID_SEED") {
+            config.id_seed = seed;
+        }
+        // When set, list and get reads that find the state lock busy serve the
+        // last list snapshot if it is at most this old, instead of waiting.
+        config.stale_read_bound = env_u64("POMODORO_STALE_READ_MS")
+            .filter(|&ms| ms > 0)
+            .map(Duration::from_millis);
+        if env::var("POMODORO_FINISHED_START").is_ok_and(|m| m == "restart") {
+            config.finished_start = FinishedStart::Restart;
+        }
+        if let Some(secs) = env_u64("POMODORO_PULSE_INTERVAL_SECS").filter(|&secs| secs > 0) {
+            config.pulse_interval = Duration::from_secs(secs);
+        }
+        // How long a repeated DELETE of the same session still gets 204.
+        config.delete_tombstone_ttl = env_u64("POMODORO_DELETE_TOMBSTONE_SECS")
+            .filter(|&secs| secs > 0)
+            .map(Duration::from_secs);
+        config.nudges = env::var("POMODORO_NUDGES").ok().and_then(|n| Nudges::parse(&n));
+        // Running sessions no request has touched for this long get paused, for
+        // clients that crash or lose the network mid-session.
+        config.inactivity_pause = env_u64("POMODORO_INACTIVITY_PAUSE_SECS")
+            .filter(|&secs| secs > 0)
+            .map(Duration::from_secs);
+        // 1 means single-tasking: starting or resuming a second session is refused.
+        config.max_concurrent_running = env_u64("POMODORO_MAX_CONCURRENT_RUNNING")
+            .filter(|&n| n > 0)
+            .map(|n| n as usize);
+        match env::var("POMODORO_ROUNDING").as_deref() {
+            Ok("ceil") => config.rounding = Rounding::Ceil,
+            Ok("round") => config.rounding = Rounding::Round,
+            _ => {}
+        }
+        config
+    }
+}
+
+// What `GET /config` shows. The admin token and webhook URLs (which often embed
+// credentials) are reduced to whether they are set.
+#[derive(Serialize)]
+pub struct ConfigView {
+    request_timeout_secs: u64,
+    state_file: Option<PathBuf>,
+    state_format: StateFormat,
+    flush_interval_ms: u64,
+    max_subscribers_per_session: usize,
+    sweep_interval_secs: u64,
+    idle_ttl_secs: Option<u64>,
+    finished_ttl_secs: Option<u64>,
+    auth_enabled: bool,
+    uds: Option<PathBuf>,
+    webhook_enabled: bool,
+    webhook_attempts: u32,
+    webhook_backoff_ms: u64,
+    list_snapshot: bool,
+    abandon_after_secs: u64,
+    envelope: bool,
+    summary_at: Option<NaiveTime>,
+    summary_enabled: bool,
+    summary_skip_empty: bool,
+    quiet_hours: Option<QuietHours>,
+    max_concurrency: Option<usize>,
+    timestamp_format: TimestampFormat,
+    archive_capacity: usize,
+    archive_ttl_secs: u64,
+    history_max_entries: Option<usize>,
+    history_max_age_secs: Option<u64>,
+    notifiers: Vec<NotifierKind>,
+    shutdown_grace_secs: u64,
+    log_format: LogFormat,
+    enforce_breaks: bool,
+    id_seed: u64,
+    stale_read_ms: Option<u64>,
+    finished_start: FinishedStart,
+    pulse_interval_secs: u64,
+    delete_tombstone_secs: Option<u64>,
+    nudges: Option<Nudges>,
+    rounding: Rounding,
+    inactivity_pause_secs: Option<u64>,
+    max_concurrent_running: Option<usize>,
+}
+
+impl Config {
+    pub fn view(&self) -> ConfigView {
+        ConfigView {
+            request_timeout_secs: self.request_timeout.as_secs(),
+            state_file: self.state_file.clone(),
+            state_format: self.state_format,
+            flush_interval_ms: self.flush_interval.as_millis() as u64,
+            max_subscribers_per_session: self.max_subscribers_per_session,
+            sweep_interval_secs: self.sweep_interval.as_secs(),
+            idle_ttl_secs: self.idle_ttl.map(|ttl| ttl.as_secs()),
+            finished_ttl_secs: self.finished_ttl.map(|ttl| ttl.as_secs()),
+            auth_enabled: self.admin_token.is_some(),
+            uds: self.uds.clone(),
+            webhook_enabled: self.webhook_url.is_some(),
+            webhook_attempts: self.webhook_attempts,
+            webhook_backoff_ms: self.webhook_backoff.as_millis() as u64,
+            list_snapshot: self.list_snapshot,
+            abandon_after_secs: self.abandon_after.as_secs(),
+            envelope: self.envelope,
+            summary_at: self.summary_at,
+            summary_enabled: self.summary_at.is_some() && self.summary_webhook_url.is_some(),
+            summary_skip_empty: self.summary_skip_empty,
+            quiet_hours: self.quiet_hours,
+            max_concurrency: self.max_concurrency,
+            timestamp_format: self.timestamp_format,
+            archive_capacity: self.archive_capacity,
+            archive_ttl_secs: self.archive_ttl.as_secs(),
+            history_max_entries: self.history_max_entries,
+            history_max_age_secs: self.history_max_age.map(|age| age.as_secs()),
+            notifiers: self.notifiers.clone(),
+            shutdown_grace_secs: self.shutdown_grace.as_secs(),
+            log_format: self.log_format,
+            enforce_breaks: self.enforce_breaks,
+            id_seed: self.id_seed,
+            stale_read_ms: self.stale_read_bound.map(|b| b.as_millis() as u64),
+            finished_start: self.finished_start,
+            pulse_interval_secs: self.pulse_interval.as_secs(),
+            delete_tombstone_secs: self.delete_tombstone_ttl.map(|ttl| ttl.as_secs()),
+            nudges: self.nudges.clone(),
+            rounding: self.rounding,
+            inactivity_pause_secs: self.inactivity_pause.map(|window| window.as_secs()),
+            max_concurrent_running: self.max_concurrent_running,
+        }
+    }
+}
+
+fn flag_value(flag: &str) -> Option<String> {
+    let mut args = env::args().skip(1);
+    args.find(|arg| arg == flag)?;
+    args.next()
+}
+
+fn env_flag(key: &str) -> bool {
+    env::var(key).is_ok_and(|v| matches!(v.as_str(), "1" | "true"))
+}
+
+fn env_u64(key: &str) -> Option<u64> {
+    env::var(key).ok()?.parse().ok()
+}
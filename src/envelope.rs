@@ -0,0 +1,42 @@
+use std::sync::Arc;
+
+use axum::{
+    body::{self, Body},
+    extract::{Request, State},
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::{config::Config, error::Error};
+
+const ENVELOPE_HEADER: &str = "x-envelope";
+
+// `X-Envelope: data` or `X-Envelope: bare` overrides the configured default per
+// request. Errors already have the `{"error": ...}` shape, so only successful
+// JSON bodies are wrapped.
+pub async fn wrap(State(config): State<Arc<Config>>, req: Request, next: Next) -> Response {
+    let wanted = match req.headers().get(ENVELOPE_HEADER).map(HeaderValue::as_bytes) {
+        Some(b"data") => true,
+        Some(b"bare") => false,
+        _ => config.envelope,
+    };
+    let res = next.run(req).await;
+    let is_json = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes().starts_with(b"application/json"));
+    if !wanted || !res.status().is_success() || !is_json {
+        return res;
+    }
+    let (mut parts, body) = res.into_parts();
+    let Ok(bytes) = body::to_bytes(body, usize::MAX).await else {
+        return Error::Unavailable("body_unreadable").into_response();
+    };
+    let mut wrapped = Vec::with_capacity(bytes.len() + 9);
+    wrapped.extend_from_slice(b"{\"data\":");
+    wrapped.extend_from_slice(&bytes);
+    wrapped.push(b'}');
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(wrapped))
+}
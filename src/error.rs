@@ -0,0 +1,148 @@
+use std::{fmt, io, sync::PoisonError};
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use validator::{ValidationError, ValidationErrors};
+
+#[derive(Debug)]
+pub enum Error {
+    Unauthorized,
+    InvalidId,
+    NotFound,
+    Conflict(&'static str),
+    Locked(&'static str),
+    // Another session's break still has this many seconds to run.
+    BreakNotFinished(u64),
+    // The body couldn't be read as the expected shape at all (400).
+    Malformed(String),
+    // Well-formed input that breaks a rule (422).
+    Validation(String),
+    // Every violation found in a request body, keyed by field.
+    Invalid(ValidationErrors),
+    // The same, for query parameters, which are all checked before answering (400).
+    BadQuery(ValidationErrors),
+    Unavailable(&'static str),
+    Io(io::Error),
+    // A handler panicked; the details only go to the log.
+    Internal,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    // A `BadQuery` naming the one parameter at fault.
+    pub fn bad_query(field: &'static str, message: String) -> Self {
+        let mut errors = ValidationErrors::new();
+        errors.add(field, ValidationError::new(field).with_message(message.into()));
+        Error::BadQuery(errors)
+    }
+
+    fn status(&self) -> StatusCode {
+        match self {
+            Error::Io(_) | Error::Internal => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            Error::Unauthorized => StatusCode::UNAUTHORIZED,
+            Error::InvalidId => StatusCode::BAD_REQUEST,
+            Error::NotFound => StatusCode::NOT_FOUND,
+            Error::Conflict(_) | Error::BreakNotFinished(_) => StatusCode::CONFLICT,
+            Error::Locked(_) => StatusCode::LOCKED,
+            Error::Malformed(_) | Error::BadQuery(_) => StatusCode::BAD_REQUEST,
+            Error::Validation(_) | Error::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            Error::Unauthorized => "unauthorized",
+            Error::InvalidId => "invalid_id",
+            Error::NotFound => "not_found",
+            Error::Conflict(code) | Error::Locked(code) | Error::Unavailable(code) => code,
+            Error::BreakNotFinished(_) => "break_not_finished",
+            Error::Malformed(_) => "malformed",
+            Error::BadQuery(_) => "invalid_query",
+            Error::Validation(_) | Error::Invalid(_) => "validation",
+            Error::Io(_) => "io",
+            Error::Internal => "internal",
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Unauthorized => f.write_str("unauthorized"),
+            Error::InvalidId => f.write_str("invalid id"),
+            Error::NotFound => f.write_str("not found"),
+            Error::Conflict(code) => write!(f, "conflict: {code}"),
+            Error::Locked(code) => write!(f, "locked: {code}"),
+            Error::BreakNotFinished(secs) => write!(f, "break not finished: {secs}s left"),
+            Error::Malformed(msg) => write!(f, "malformed request: {msg}"),
+            Error::Validation(msg) => write!(f, "validation failed: {msg}"),
+            Error::Invalid(errors) => write!(f, "validation failed: {errors}"),
+            Error::BadQuery(errors) => write!(f, "invalid query: {errors}"),
+            Error::Unavailable(code) => write!(f, "unavailable: {code}"),
+            Error::Io(err) => write!(f, "io error: {err}"),
+            Error::Internal => f.write_str("internal error"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// A poisoned state lock stays poisoned: whatever panicked may have left the
+// state half-changed, so every request that needs it fails from then on.
+impl<T> From<PoisonError<T>> for Error {
+    fn from(_: PoisonError<T>) -> Self {
+        Error::Internal
+    }
+}
+
+impl From<ValidationError> for Error {
+    fn from(err: ValidationError) -> Self {
+        Error::Validation(err.to_string())
+    }
+}
+
+impl From<ValidationErrors> for Error {
+    fn from(errors: ValidationErrors) -> Self {
+        Error::Invalid(errors)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let body = match &self {
+            Error::Malformed(msg) | Error::Validation(msg) => {
+                json!({ "error": self.code(), "message": msg })
+            }
+            Error::BreakNotFinished(secs) => {
+                json!({ "error": self.code(), "remaining_secs": secs })
+            }
+            Error::Invalid(errors) | Error::BadQuery(errors) => {
+                let fields: serde_json::Map<_, _> = errors
+                    .field_errors()
+                    .into_iter()
+                    .map(|(field, errs)| {
+                        let messages: Vec<String> = errs.iter().map(ToString::to_string).collect();
+                        (field.to_string(), json!(messages))
+                    })
+                    .collect();
+                json!({ "error": self.code(), "fields": fields })
+            }
+            _ => json!({ "error": self.code() }),
+        };
+        (self.status(), Json(body)).into_response()
+    }
+}
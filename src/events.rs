@@ -0,0 +1,426 @@
+use std::{
+    collections::VecDeque,
+    convert::Infallible,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use chrono::{DateTime, Utc};
+use futures_util::{stream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, watch};
+
+use crate::{
+    clock::{self, Clock},
+    config::{Config, Nudges, TimestampFormat},
+    error::{Error, Result},
+    extract::{IdPath, QueryParams},
+    hub::Hub,
+    id::SessionId,
+    timestamp::Stamp,
+    to_response, Phase, PomodoroSession, PomodoroState, RemainingStats, SharedState,
+};
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum EventMode {
+    #[default]
+    Full,
+    Progress,
+}
+
+#[derive(Deserialize)]
+pub struct EventsQuery {
+    #[serde(default)]
+    mode: EventMode,
+}
+
+// Compact payload for bandwidth-sensitive progress bars.
+#[derive(Serialize)]
+struct Progress {
+    percent: u64,
+    phase: Phase,
+}
+
+fn session_event(s: &PomodoroSession, mode: EventMode) -> Option<Event> {
+    let event = Event::default();
+    match mode {
+        EventMode::Full => event.json_data(to_response(s)),
+        EventMode::Progress => event.json_data(Progress {
+            percent: s.phase_percent(),
+            phase: s.phase(),
+        }),
+    }
+    .ok()
+}
+
+#[derive(Serialize)]
+struct Nudge {
+    milestone: &'static str,
+    elapsed_secs: u64,
+    remaining_secs: u64,
+    percent: u64,
+    message: String,
+}
+
+fn rough(d: Duration) -> String {
+    match d.as_secs() {
+        secs if secs < 60 => format!("{secs} seconds"),
+        60..120 => "1 minute".to_string(),
+        secs => format!("{} minutes", secs / 60),
+    }
+}
+
+// The milestones a running session passed since the stream last saw it at
+// `last` worked. If an adjust skips several intervals at once only the latest
+// one is sent, but every percentage passed gets its own nudge.
+fn nudges(config: &Nudges, s: &PomodoroSession, last: Duration) -> Vec<Event> {
+    let now = s.elapsed;
+    if s.state != PomodoroState::Running || now <= last {
+        return Vec::new();
+    }
+    let total = s.total_work();
+    let mut passed: Vec<(Duration, Option<u8>)> = config
+        .percents
+        .iter()
+        .map(|&p| (total.mul_f64(f64::from(p) / 100.0), Some(p)))
+        .filter(|&(at, _)| last < at && at <= now)
+        .collect();
+    if let Some(every) = config.every {
+        let reached = (now.as_millis() / every.as_millis()) as u32;
+        let before = (last.as_millis() / every.as_millis()) as u32;
+        if reached > before {
+            passed.push((every * reached, None));
+        }
+    }
+    passed.sort_unstable_by_key(|&(at, _)| at);
+    let left = total.saturating_sub(now);
+    passed
+        .into_iter()
+        .filter_map(|(at, percent)| {
+            let nudge = match percent {
+                Some(p) => Nudge {
+                    milestone: "percent",
+                    elapsed_secs: at.as_secs(),
+                    remaining_secs: left.as_secs(),
+                    percent: p.into(),
+                    message: format!("{p}% done, {} left", rough(left)),
+                },
+                None => Nudge {
+                    milestone: "interval",
+                    elapsed_secs: at.as_secs(),
+                    remaining_secs: left.as_secs(),
+                    percent: s.phase_percent(),
+                    message: format!("{} in, keep going", rough(at)),
+                },
+            };
+            Event::default().event("nudge").json_data(nudge).ok()
+        })
+        .collect()
+}
+
+// Ends `events` once shutdown begins (see `EventLog::close_streams`), with a
+// final `closing` event so clients can tell a deliberate close from a dropped
+// connection. A stream that ends for its own reasons (the session was
+// removed) gets no closing event.
+fn until_closing(
+    mut rx: watch::Receiver<bool>,
+    events: impl Stream<Item = std::result::Result<Event, Infallible>>,
+) -> impl Stream<Item = std::result::Result<Event, Infallible>> {
+    let farewell_rx = rx.clone();
+    let closed = async move {
+        let _ = rx.wait_for(|closing| *closing).await;
+    };
+    let farewell = stream::once(async move {
+        let closing = *farewell_rx.borrow();
+        closing.then(|| Ok(Event::default().event("closing").data("server closing")))
+    })
+    .filter_map(|event| async move { event });
+    events.take_until(closed).chain(farewell)
+}
+
+// Held by each open stream; dropping it (client disconnect) frees the slot.
+struct SubscriberSlot {
+    state: SharedState,
+    id: SessionId,
+}
+
+impl SubscriberSlot {
+    fn acquire(state: &SharedState, id: SessionId, max: usize) -> Result<Self> {
+        let mut guard = state.lock()?;
+        if !guard.sessions.contains_key(&id) {
+            return Err(Error::NotFound);
+        }
+        let count = guard.subscribers.entry(id).or_default();
+        if *count >= max {
+            return Err(Error::Unavailable("too_many_subscribers"));
+        }
+        *count += 1;
+        Ok(Self {
+            state: state.clone(),
+            id,
+        })
+    }
+}
+
+impl Drop for SubscriberSlot {
+    fn drop(&mut self) {
+        let Ok(mut guard) = self.state.lock() else {
+            return;
+        };
+        if let Some(count) = guard.subscribers.get_mut(&self.id) {
+            *count -= 1;
+            if *count == 0 {
+                guard.subscribers.remove(&self.id);
+            }
+        }
+    }
+}
+
+// How often a session stream looks at its session. Tests move the clock
+// themselves, so they only need the stream to keep up.
+#[cfg(not(test))]
+const SESSION_TICK: Duration = Duration::from_secs(1);
+#[cfg(test)]
+const SESSION_TICK: Duration = Duration::from_millis(10);
+
+pub async fn session_events(
+    State(state): State<SharedState>,
+    State(config): State<Arc<Config>>,
+    State(hub): State<Arc<Hub>>,
+    IdPath(id): IdPath,
+    QueryParams(q): QueryParams<EventsQuery>,
+) -> Result<Sse<impl Stream<Item = std::result::Result<Event, Infallible>>>> {
+    let slot = SubscriberSlot::acquire(&state, id, config.max_subscribers_per_session)?;
+    let mode = q.mode;
+    let interval = tokio::time::interval(SESSION_TICK);
+    // Nudges count from where the session was when the stream opened.
+    let seen = None;
+    let events = stream::unfold((slot, interval, seen), move |(slot, mut interval, seen)| {
+        let config = config.clone();
+        async move {
+            interval.tick().await;
+            let mut guard = slot.state.lock().ok()?;
+            let s = guard.sessions.get_mut(&slot.id)?;
+            s.update_elapsed();
+            let mut events = match (&config.nudges, seen) {
+                (Some(milestones), Some(last)) => nudges(milestones, s, last),
+                _ => Vec::new(),
+            };
+            events.push(session_event(s, mode)?);
+            let seen = Some(s.elapsed);
+            drop(guard);
+            Some((events, (slot, interval, seen)))
+        }
+    })
+    .flat_map(|events| stream::iter(events.into_iter().map(Ok)));
+    let events = until_closing(hub.events.closing.subscribe(), events);
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+#[derive(Serialize)]
+struct Pulse {
+    #[serde(flatten)]
+    remaining: RemainingStats,
+    soonest_finish: Option<Stamp>,
+    at: Stamp,
+}
+
+// Server-wide aggregates for ambient displays, one `pulse` event per interval.
+pub async fn pulse(
+    State(state): State<SharedState>,
+    State(config): State<Arc<Config>>,
+    State(hub): State<Arc<Hub>>,
+) -> Sse<impl Stream<Item = std::result::Result<Event, Infallible>>> {
+    let interval = tokio::time::interval(config.pulse_interval);
+    let format = hub.timestamps;
+    let events = stream::unfold((state, interval), move |(state, mut interval)| async move {
+        interval.tick().await;
+        let pulse = {
+            let guard = state.lock().ok()?;
+            let clock = Clock::now();
+            let remaining = guard.remaining_stats_at(clock.now_instant());
+            let at = clock.now_system();
+            let soonest_finish = remaining
+                .min_remaining_secs
+                .map(|secs| at + chrono::TimeDelta::seconds(secs as i64));
+            Pulse {
+                remaining,
+                soonest_finish: soonest_finish.map(|at| Stamp::new(at, format)),
+                at: Stamp::new(at, format),
+            }
+        };
+        let event = Event::default().event("pulse").json_data(pulse).ok()?;
+        Some((Ok(event), (state, interval)))
+    });
+    let events = until_closing(hub.events.closing.subscribe(), events);
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Created,
+    Started,
+    Paused,
+    Interrupted,
+    Resumed,
+    BreakStarted,
+    Finished,
+    Reset,
+    Adjusted,
+    Removed,
+    Cancelled,
+    Merged,
+}
+
+impl EventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventKind::Created => "created",
+            EventKind::Started => "started",
+            EventKind::Paused => "paused",
+            EventKind::Interrupted => "interrupted",
+            EventKind::Resumed => "resumed",
+            EventKind::BreakStarted => "break_started",
+            EventKind::Finished => "finished",
+            EventKind::Reset => "reset",
+            EventKind::Adjusted => "adjusted",
+            EventKind::Removed => "removed",
+            EventKind::Cancelled => "cancelled",
+            EventKind::Merged => "merged",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerEvent {
+    seq: u64,
+    session_id: SessionId,
+    kind: EventKind,
+    at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+struct ServerEventBody {
+    seq: u64,
+    session_id: SessionId,
+    #[serde(rename = "type")]
+    kind: EventKind,
+    at: Stamp,
+}
+
+const LOG_CAP: usize = 256;
+
+// Recent events are kept so a reconnecting client can pass `Last-Event-ID` and
+// replay what it missed. Publishing appends and broadcasts under one lock, and
+// subscribing snapshots the log under the same lock, so nothing falls between
+// the replay and the live feed. `closing` flips once when shutdown begins.
+pub struct EventLog {
+    recent: Mutex<(u64, VecDeque<ServerEvent>)>,
+    tx: broadcast::Sender<ServerEvent>,
+    closing: watch::Sender<bool>,
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self {
+            recent: Mutex::new((0, VecDeque::with_capacity(LOG_CAP))),
+            tx: broadcast::channel(LOG_CAP).0,
+            closing: watch::Sender::new(false),
+        }
+    }
+}
+
+impl EventLog {
+    pub fn publish(&self, session_id: SessionId, kind: EventKind) {
+        let Ok(mut recent) = self.recent.lock() else {
+            return;
+        };
+        let (seq, events) = &mut *recent;
+        *seq += 1;
+        let event = ServerEvent {
+            seq: *seq,
+            session_id,
+            kind,
+            at: clock::utc(),
+        };
+        if events.len() == LOG_CAP {
+            events.pop_front();
+        }
+        events.push_back(event.clone());
+        // No receivers is fine; the event still lands in the log.
+        let _ = self.tx.send(event);
+    }
+
+    // Tells every open stream the server is going away. Graceful shutdown waits on
+    // open connections, so streams have to end themselves for it to complete.
+    pub fn close_streams(&self) {
+        self.closing.send_replace(true);
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ServerEventsQuery {
+    types: Option<String>,
+}
+
+fn server_event(event: &ServerEvent, format: TimestampFormat) -> Option<Event> {
+    Event::default()
+        .id(event.seq.to_string())
+        .event(event.kind.as_str())
+        .json_data(ServerEventBody {
+            seq: event.seq,
+            session_id: event.session_id,
+            kind: event.kind,
+            at: Stamp::new(event.at, format),
+        })
+        .ok()
+}
+
+pub async fn server_events(
+    State(hub): State<Arc<Hub>>,
+    QueryParams(q): QueryParams<ServerEventsQuery>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = std::result::Result<Event, Infallible>>>> {
+    let types: Option<Vec<String>> = q
+        .types
+        .map(|t| t.split(',').map(|t| t.trim().to_string()).collect());
+    let last_seen: u64 = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(u64::MAX);
+    let format = hub.timestamps;
+    let (backlog, rx) = {
+        let log = &hub.events;
+        let recent = log.recent.lock()?;
+        let backlog: Vec<ServerEvent> =
+            recent.1.iter().filter(|e| e.seq > last_seen).cloned().collect();
+        (backlog, log.tx.subscribe())
+    };
+    // A lagging receiver skips what it missed rather than ending the stream.
+    let live = stream::unfold(rx, |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(event) => return Some((event, rx)),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+    let events = stream::iter(backlog)
+        .chain(live)
+        .filter(move |e| {
+            let keep = types.as_ref().is_none_or(|t| t.iter().any(|t| t == e.kind.as_str()));
+            async move { keep }
+        })
+        .filter_map(move |e| async move { server_event(&e, format).map(Ok) });
+    let events = until_closing(hub.events.closing.subscribe(), events);
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
@@ -0,0 +1,123 @@
+use std::io;
+
+use axum::{
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+use crate::{timestamp::Stamp, SessionResponse};
+
+const CSV_HEADER: [&str; 16] = [
+    "id",
+    "state",
+    "work_minutes",
+    "break_minutes",
+    "elapsed_secs",
+    "remaining_secs",
+    "rounds_completed",
+    "pauses",
+    "interruptions",
+    "pinned",
+    "external_id",
+    "created_at",
+    "finished_at",
+    "metadata",
+    "title",
+    "tags",
+];
+
+// Map-valued fields are flattened to `key=value` pairs joined by `;`, sorted by
+// key; tags are joined by `;` too, in their stored (sorted) order.
+pub fn sessions_csv(sessions: &[SessionResponse]) -> io::Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(CSV_HEADER)?;
+    for s in sessions {
+        let mut metadata: Vec<_> = s.metadata.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        metadata.sort();
+        writer.write_record([
+            s.id.to_string(),
+            s.state.to_string(),
+            s.work_minutes.to_string(),
+            s.break_minutes.to_string(),
+            s.elapsed_secs.to_string(),
+            s.remaining_secs.to_string(),
+            s.rounds_completed.to_string(),
+            s.pauses.to_string(),
+            s.interruptions.to_string(),
+            s.pinned.to_string(),
+            s.external_id.clone().unwrap_or_default(),
+            s.created_at.render(),
+            s.finished_at.as_ref().map(Stamp::render).unwrap_or_default(),
+            metadata.join(";"),
+            s.title.clone().unwrap_or_default(),
+            s.tags.join(";"),
+        ])?;
+    }
+    writer.into_inner().map_err(|err| err.into_error())
+}
+
+// Resolves a single `bytes=` range against a body of `len` bytes, as an
+// inclusive (first, last). Multiple ranges and syntax we don't understand come
+// back as `None`, and the caller serves the whole body as RFC 9110 allows;
+// `Err` means the range is well-formed but lies outside the body.
+fn byte_range(spec: &str, len: u64) -> Option<Result<(u64, u64), ()>> {
+    let spec = spec.strip_prefix("bytes=")?.trim();
+    if spec.contains(',') {
+        return None;
+    }
+    let (first, last) = spec.split_once('-')?;
+    let range = match (first.trim(), last.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            if suffix == 0 || len == 0 {
+                return Some(Err(()));
+            }
+            (len.saturating_sub(suffix), len - 1)
+        }
+        (first, last) => {
+            let first: u64 = first.parse().ok()?;
+            let last = match last {
+                "" => u64::MAX,
+                last => last.parse().ok()?,
+            };
+            if first > last {
+                return None;
+            }
+            if first >= len {
+                return Some(Err(()));
+            }
+            (first, last.min(len - 1))
+        }
+    };
+    Some(Ok(range))
+}
+
+// Serves a fully buffered export, honouring a `Range` header. Exports of
+// running sessions change from one request to the next, so a resumed
+// download only lines up with its first part when nothing was active.
+pub fn ranged(headers: &HeaderMap, content_type: &'static str, body: Vec<u8>) -> Response {
+    let len = body.len() as u64;
+    let range = headers
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|spec| byte_range(spec, len));
+    let base = [
+        (header::CONTENT_TYPE, content_type),
+        (header::ACCEPT_RANGES, "bytes"),
+    ];
+    match range {
+        None => (base, body).into_response(),
+        Some(Ok((first, last))) => (
+            StatusCode::PARTIAL_CONTENT,
+            base,
+            [(header::CONTENT_RANGE, format!("bytes {first}-{last}/{len}"))],
+            body[first as usize..=last as usize].to_vec(),
+        )
+            .into_response(),
+        Some(Err(())) => (
+            StatusCode::RANGE_NOT_SATISFIABLE,
+            [(header::CONTENT_RANGE, format!("bytes */{len}"))],
+        )
+            .into_response(),
+    }
+}
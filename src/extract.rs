@@ -0,0 +1,61 @@
+use axum::{
+    async_trait,
+    extract::{FromRequest, FromRequestParts, Path, Query, Request},
+    http::request::Parts,
+    Json,
+};
+use serde::de::DeserializeOwned;
+
+use crate::{error::Error, id::SessionId};
+
+// `Path<T>` whose rejection is our `invalid_id` error rather than axum's
+// plain-text 400, so a malformed id is distinguishable from a missing one.
+// Session routes use the default; templates keep plain numeric ids.
+pub struct IdPath<T = SessionId>(pub T);
+
+#[async_trait]
+impl<T: DeserializeOwned + Send, S: Send + Sync> FromRequestParts<S> for IdPath<T> {
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        Path::<T>::from_request_parts(parts, state)
+            .await
+            .map(|Path(id)| IdPath(id))
+            .map_err(|_| Error::InvalidId)
+    }
+}
+
+// `Query<T>` with the rejection as our 400 `invalid_query`, the same error
+// handlers give for values that parse but are out of range. A parameter that
+// can't be read at all is reported under `query`, since the deserializer
+// doesn't say which one it was.
+pub struct QueryParams<T>(pub T);
+
+#[async_trait]
+impl<T: DeserializeOwned, S: Send + Sync> FromRequestParts<S> for QueryParams<T> {
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        Query::<T>::from_request_parts(parts, state)
+            .await
+            .map(|Query(q)| QueryParams(q))
+            .map_err(|rejection| Error::bad_query("query", rejection.body_text()))
+    }
+}
+
+// `Json<T>` for request bodies. Anything that can't be read as a `T` (bad
+// syntax, wrong types, missing fields, no JSON content type) is a 400
+// `malformed` error; 422 is reserved for bodies that parse but fail validation.
+pub struct JsonBody<T>(pub T);
+
+#[async_trait]
+impl<T: DeserializeOwned, S: Send + Sync> FromRequest<S> for JsonBody<T> {
+    type Rejection = Error;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        Json::<T>::from_request(req, state)
+            .await
+            .map(|Json(body)| JsonBody(body))
+            .map_err(|rejection| Error::Malformed(rejection.body_text()))
+    }
+}
@@ -0,0 +1,118 @@
+use std::sync::Arc;
+
+use crate::{
+    config::{Config, FinishedStart},
+    error::{Error, Result},
+    events::EventKind,
+    hub::Hub,
+    id::SessionId,
+    AppState, PomodoroSession, PomodoroState,
+};
+
+// Custom logic around state transitions, built into the hub at startup.
+//
+// `before` runs under the state lock for every transition a client asks for
+// (start, pause, interrupt, resume, cancel), ahead of the session's own state
+// checks, and vetoes it by returning an error; `Error::Conflict` becomes a 409.
+// Starts the server makes on a client's behalf (`start_immediately`, continue,
+// `auto_start`) are checked the same way.
+// `after` observes every transition that happened, including the ones the
+// ticker makes when time runs out, so it must not block.
+pub trait TransitionHook: Send + Sync {
+    fn before(&self, _state: &AppState, _session: &PomodoroSession, _event: EventKind) -> Result<()> {
+        Ok(())
+    }
+
+    fn after(&self, _session: &PomodoroSession, _event: EventKind, _from: PomodoroState) {}
+}
+
+// The first veto wins. A missing session is left for the handler to report.
+pub fn before(state: &AppState, id: SessionId, event: EventKind) -> Result<()> {
+    let Some(session) = state.sessions.get(&id) else {
+        return Ok(());
+    };
+    check(state, session, event)
+}
+
+// Like `before`, for a session that needn't be stored yet, such as one about to
+// be created already running.
+pub fn check(state: &AppState, session: &PomodoroSession, event: EventKind) -> Result<()> {
+    state
+        .hub
+        .hooks
+        .iter()
+        .try_for_each(|hook| hook.before(state, session, event))
+}
+
+pub fn after(hub: &Hub, session: &PomodoroSession, event: EventKind, from: PomodoroState) {
+    for hook in &hub.hooks {
+        hook.after(session, event, from);
+    }
+}
+
+// Refuses to start work while another session is still on its break.
+pub struct EnforceBreaks;
+
+impl TransitionHook for EnforceBreaks {
+    fn before(&self, state: &AppState, session: &PomodoroSession, event: EventKind) -> Result<()> {
+        let startable = matches!(session.state, PomodoroState::Idle | PomodoroState::Finished);
+        if event == EventKind::Started && startable {
+            state.check_break_finished(session.id)?;
+        }
+        Ok(())
+    }
+}
+
+// Keeps Finished sessions as they are instead of restarting them in place.
+// A patterned session finishes after every round and is started again for the
+// next one, so it is let through.
+pub struct KeepFinished;
+
+impl TransitionHook for KeepFinished {
+    fn before(&self, _: &AppState, session: &PomodoroSession, event: EventKind) -> Result<()> {
+        if event == EventKind::Started
+            && session.state == PomodoroState::Finished
+            && session.pattern.is_empty()
+        {
+            return Err(Error::Conflict("already_finished"));
+        }
+        Ok(())
+    }
+}
+
+// Caps how many sessions can be in their work phase at once. Sessions on a
+// break don't count; they aren't being worked on.
+pub struct MaxRunning(pub usize);
+
+impl TransitionHook for MaxRunning {
+    fn before(&self, state: &AppState, session: &PomodoroSession, event: EventKind) -> Result<()> {
+        if !matches!(event, EventKind::Started | EventKind::Resumed)
+            || session.state == PomodoroState::Running
+        {
+            return Ok(());
+        }
+        let running = state
+            .sessions
+            .values()
+            .filter(|s| s.id != session.id && s.state == PomodoroState::Running)
+            .count();
+        if running >= self.0 {
+            return Err(Error::Conflict("too_many_running"));
+        }
+        Ok(())
+    }
+}
+
+pub fn from_config(config: &Config) -> Vec<Arc<dyn TransitionHook>> {
+    let mut hooks: Vec<Arc<dyn TransitionHook>> = Vec::new();
+    if config.finished_start == FinishedStart::Refuse {
+        hooks.push(Arc::new(KeepFinished));
+    }
+    if config.enforce_breaks {
+        hooks.push(Arc::new(EnforceBreaks));
+    }
+    if let Some(max) = config.max_concurrent_running {
+        hooks.push(Arc::new(MaxRunning(max)));
+    }
+    hooks
+}
@@ -0,0 +1,59 @@
+use std::{fmt, sync::Arc};
+
+use crate::{
+    config::{Config, Rounding, TimestampFormat},
+    events::EventLog,
+    hooks::{self, TransitionHook},
+    metrics::Metrics,
+    supervise::Tasks,
+    webhook::Deliveries,
+};
+
+// Everything a server shares across requests besides the sessions: the event
+// feed, metrics, hooks, background task health, webhook deliveries, and the
+// output settings responses are rendered with. Built once per server; handlers
+// reach it through `AppContext`, and the state and its sessions hold it too so
+// transitions can publish wherever they happen.
+pub(crate) struct Hub {
+    pub events: EventLog,
+    pub metrics: Metrics,
+    pub hooks: Vec<Arc<dyn TransitionHook>>,
+    pub tasks: Tasks,
+    pub deliveries: Deliveries,
+    pub rounding: Rounding,
+    pub timestamps: TimestampFormat,
+}
+
+impl Hub {
+    pub fn new(config: &Config) -> Self {
+        Self::with_hooks(config, hooks::from_config(config))
+    }
+
+    pub fn with_hooks(config: &Config, hooks: Vec<Arc<dyn TransitionHook>>) -> Self {
+        Self {
+            events: EventLog::default(),
+            metrics: Metrics::default(),
+            hooks,
+            tasks: Tasks::default(),
+            deliveries: Deliveries::default(),
+            rounding: config.rounding,
+            timestamps: config.timestamp_format,
+        }
+    }
+}
+
+impl Default for Hub {
+    fn default() -> Self {
+        Self::new(&Config::default())
+    }
+}
+
+impl fmt::Debug for Hub {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hub")
+            .field("hooks", &self.hooks.len())
+            .field("rounding", &self.rounding)
+            .field("timestamps", &self.timestamps)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,146 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::{
+    clock,
+    error::{Error, Result},
+    extract::QueryParams,
+    id::SessionId,
+    timestamp, CreateSessionReq, PomodoroSession, PomodoroState, SharedState,
+};
+
+// Columns are matched by header name, so order doesn't matter and extra
+// columns are ignored. A row without `finished_at` is imported as Idle.
+#[derive(Deserialize)]
+struct Row {
+    title: Option<String>,
+    user: Option<String>,
+    work_minutes: u64,
+    break_minutes: Option<u64>,
+    finished_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ImportQuery {
+    // Import nothing if any row is invalid.
+    #[serde(default)]
+    strict: bool,
+}
+
+#[derive(Serialize)]
+pub struct ImportReport {
+    imported: Vec<SessionId>,
+    errors: Vec<RowError>,
+}
+
+#[derive(Serialize)]
+struct RowError {
+    line: u64,
+    message: String,
+}
+
+struct Planned {
+    req: CreateSessionReq,
+    finished_at: Option<DateTime<Utc>>,
+}
+
+// Rows are held to the same rules as `POST /sessions`.
+fn plan(row: Row, now: DateTime<Utc>) -> std::result::Result<Planned, String> {
+    let mut req = CreateSessionReq {
+        title: row.title,
+        user: row.user,
+        work_minutes: row.work_minutes,
+        break_minutes: row.break_minutes.unwrap_or_default(),
+        ..CreateSessionReq::default()
+    };
+    req.validate().map_err(|errors| errors.to_string())?;
+    req.resolve_end_at(now).map_err(|err| err.to_string())?;
+    let finished_at = match row.finished_at {
+        Some(v) => Some(timestamp::parse(&v).ok_or(format!("invalid finished_at {v:?}"))?),
+        None => None,
+    };
+    if finished_at.is_some_and(|at| at > now) {
+        return Err("finished_at is in the future".into());
+    }
+    Ok(Planned { req, finished_at })
+}
+
+// A finished row becomes a completed round that ended at `finished_at`, with
+// `created_at` backdated by its work and break time.
+fn build(id: SessionId, planned: Planned) -> PomodoroSession {
+    let Planned { req, finished_at } = planned;
+    let mut s = PomodoroSession::new(id, req.work_minutes, req.break_minutes);
+    s.title = req.title;
+    s.user = req.user;
+    if let Some(at) = finished_at {
+        let total = s.total_work() + s.total_break();
+        s.created_at = at - TimeDelta::from_std(total).unwrap_or_default();
+        s.was_started = true;
+        s.elapsed = s.total_work();
+        s.break_elapsed = s.total_break();
+        s.rounds_completed = 1;
+        s.finished_at = Some(at);
+        s.state = PomodoroState::Finished;
+    }
+    s
+}
+
+pub async fn import_csv(
+    State(state): State<SharedState>,
+    QueryParams(q): QueryParams<ImportQuery>,
+    body: String,
+) -> Result<(StatusCode, Json<ImportReport>)> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(body.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|err| Error::Malformed(format!("unreadable CSV header: {err}")))?
+        .clone();
+    let now = clock::utc();
+    let mut planned = Vec::new();
+    let mut errors = Vec::new();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(err) => {
+                let line = err.position().map_or(0, |p| p.line());
+                errors.push(RowError {
+                    line,
+                    message: err.to_string(),
+                });
+                continue;
+            }
+        };
+        let line = record.position().map_or(0, |p| p.line());
+        let result = record
+            .deserialize::<Row>(Some(&headers))
+            .map_err(|err| err.to_string())
+            .and_then(|row| plan(row, now));
+        match result {
+            Ok(p) => planned.push(p),
+            Err(message) => errors.push(RowError { line, message }),
+        }
+    }
+    let mut report = ImportReport {
+        imported: Vec::new(),
+        errors,
+    };
+    if q.strict && !report.errors.is_empty() {
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, Json(report)));
+    }
+    let mut guard = state.lock()?;
+    guard.check_can_create()?;
+    for p in planned {
+        let id = guard.allocate_id();
+        guard.insert_session(build(id, p));
+        report.imported.push(id);
+    }
+    Ok((StatusCode::OK, Json(report)))
+}
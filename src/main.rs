@@ -1,214 +1,3503 @@
+mod admin;
+mod clock;
+mod config;
+mod envelope;
+mod error;
+mod events;
+mod export;
+mod extract;
+mod hooks;
+mod hub;
+mod id;
+mod import;
+mod meta;
+mod metrics;
+mod notify;
+mod persist;
+mod query;
+mod request_id;
+mod summary;
+mod supervise;
+mod templates;
+mod timestamp;
+#[cfg(test)]
+mod tests;
+#[cfg(unix)]
+mod uds;
+mod webhook;
 
 use std::{
-    collections::HashMap,
-    sync::{Arc, Mutex},
+    any::Any,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    fmt, io,
+    ops::Bound,
+    sync::{Arc, Mutex, MutexGuard, TryLockError},
     time::{Duration, Instant},
 };
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    routing::{get, post},
+    body::Body,
+    error_handling::HandleErrorLayer,
+    extract::{FromRef, Path, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware,
+    response::{Html, IntoResponse, Response},
+    routing::{get, post, put},
     Json, Router,
 };
+use arc_swap::ArcSwap;
+use chrono::{DateTime, FixedOffset, NaiveDate, Offset, TimeDelta, Timelike, Utc};
+use futures_util::{stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use tokio::net::TcpListener;
+use tower::{limit::GlobalConcurrencyLimitLayer, BoxError, ServiceBuilder};
+use validator::{Validate, ValidationError, ValidationErrors};
+use tower_http::{catch_panic::CatchPanicLayer, timeout::TimeoutLayer};
+use tracing_subscriber::{fmt::MakeWriter, util::SubscriberInitExt};
+use uuid::Uuid;
 
-#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+use crate::{
+    clock::Clock,
+    id::SessionId,
+    config::{Config, LogFormat, QuietHours, Rounding, TimestampFormat},
+    events::EventKind,
+    error::{Error, Result},
+    extract::{IdPath, JsonBody, QueryParams},
+    hub::Hub,
+    query::{Format, SessionQuery},
+    timestamp::Stamp,
+};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 enum PomodoroState {
     Idle,
     Running,
+    Break,
     Paused,
+    Interrupted,
     Finished,
+    // Aborted but kept for the record; no further changes are accepted.
+    Cancelled,
 }
 
-#[derive(Clone)]
+impl fmt::Display for PomodoroState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PomodoroState::Idle => "idle",
+            PomodoroState::Running => "running",
+            PomodoroState::Break => "on break",
+            PomodoroState::Paused => "paused",
+            PomodoroState::Interrupted => "interrupted",
+            PomodoroState::Finished => "finished",
+            PomodoroState::Cancelled => "cancelled",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Phase {
+    Work,
+    Break,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct PomodoroSession {
-    id: u64,
+    id: SessionId,
     work_minutes: u64,
     break_minutes: u64,
     state: PomodoroState,
+    created_at: DateTime<Utc>,
+    was_started: bool,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    notes: Option<String>,
+    // Who the session belongs to, for team views such as the leaderboard.
+    #[serde(default)]
+    user: Option<String>,
+    external_id: Option<String>,
+    // The calendar event (meeting) the session belongs to. Unlike `external_id`
+    // it needn't be unique; several sessions can share a meeting.
+    #[serde(default)]
+    calendar_event_id: Option<String>,
+    #[serde(default)]
+    client_id: Option<Uuid>,
+    pinned: bool,
+    #[serde(default)]
+    metadata: HashMap<String, String>,
+    color: Option<String>,
+    #[serde(default)]
+    notify_profile: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    split_from: Option<SessionId>,
+    next_session: Option<SessionId>,
+    #[serde(default)]
+    depends_on: Option<SessionId>,
+    #[serde(default)]
+    auto_start: bool,
+    // Pauses allowed per round; the round counter is `round_pauses`.
+    #[serde(default)]
+    max_pauses: Option<u64>,
+    #[serde(default)]
+    version: u64,
+    #[serde(skip)]
     started_at: Option<Instant>,
+    #[serde(skip)]
+    last_started_at: Option<Instant>,
+    #[serde(skip)]
     paused_at: Option<Instant>,
-    elapsed_secs: u64,
+    // Latest request naming this session; only kept with POMODORO_INACTIVITY_PAUSE_SECS.
+    #[serde(skip)]
+    last_interaction: Option<Instant>,
+    finished_at: Option<DateTime<Utc>>,
+    elapsed: Duration,
+    break_elapsed: Duration,
+    auto_reset_after: Option<u64>,
+    // Counters saturate at u64::MAX instead of wrapping or panicking.
+    rounds_completed: u64,
+    pauses: u64,
+    interruptions: u64,
+    // Per-round counterparts of the above, cleared by `reset`; they feed the focus score.
+    #[serde(default)]
+    round_pauses: u64,
+    #[serde(default)]
+    round_interruptions: u64,
+    #[serde(default)]
+    round_suspended: Duration,
+    focus_score: Option<u64>,
+    #[serde(default)]
+    recent_actions: VecDeque<String>,
+    #[serde(default)]
+    history: Vec<HistoryEntry>,
+    // How many entries of each kind compaction has dropped from `history`.
+    #[serde(default)]
+    history_compacted: BTreeMap<EventKind, u64>,
+    // Durations to cycle through, one entry per round; `pattern_index` is the
+    // round the next reset loads.
+    #[serde(default)]
+    pattern: Vec<Round>,
+    #[serde(default)]
+    pattern_index: usize,
+    // Created to finish at a wall-clock time. The first start sets `work_target`
+    // to exactly the time left; `work_minutes` is that rounded up, for display.
+    #[serde(default)]
+    end_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    work_target: Option<Duration>,
+    #[serde(skip)]
+    finish_unsent: bool,
+    // Set when time alone moves the state on; the ticker bumps the version.
+    #[serde(skip)]
+    transitioned: bool,
+    // Where transitions are published; set once the session is in a state.
+    #[serde(skip)]
+    hub: Option<Arc<Hub>>,
 }
 
 const PORT: u32 = 3000;
+const RECENT_ACTIONS_CAP: usize = 16;
+const MAX_METADATA_KEYS: usize = 32;
+const MAX_METADATA_BYTES: usize = 4096;
+const MAX_MINUTES: u64 = 24 * 60;
+const MAX_TAGS: u64 = 32;
+const MAX_NOTES_BYTES: u64 = 4096;
+// Sounds clients know how to play when a session finishes.
+const NOTIFY_PROFILES: [&str; 3] = ["bell", "chime", "silent"];
+const MAX_PATTERN_ROUNDS: usize = 16;
 
 impl PomodoroSession {
-    fn new(id: u64, work_minutes: u64, break_minutes: u64) -> Self {
+    fn new(id: SessionId, work_minutes: u64, break_minutes: u64) -> Self {
         Self {
             id,
             work_minutes,
             break_minutes,
             state: PomodoroState::Idle,
+            created_at: clock::utc(),
+            was_started: false,
+            title: None,
+            notes: None,
+            user: None,
+            external_id: None,
+            calendar_event_id: None,
+            client_id: None,
+            pinned: false,
+            metadata: HashMap::new(),
+            color: None,
+            notify_profile: None,
+            tags: Vec::new(),
+            split_from: None,
+            next_session: None,
+            depends_on: None,
+            auto_start: false,
+            max_pauses: None,
+            version: 0,
             started_at: None,
+            last_started_at: None,
             paused_at: None,
-            elapsed_secs: 0,
+            last_interaction: None,
+            finished_at: None,
+            elapsed: Duration::ZERO,
+            break_elapsed: Duration::ZERO,
+            auto_reset_after: None,
+            rounds_completed: 0,
+            pauses: 0,
+            interruptions: 0,
+            round_pauses: 0,
+            round_interruptions: 0,
+            round_suspended: Duration::ZERO,
+            focus_score: None,
+            recent_actions: VecDeque::new(),
+            history: Vec::new(),
+            history_compacted: BTreeMap::new(),
+            pattern: Vec::new(),
+            pattern_index: 0,
+            end_at: None,
+            work_target: None,
+            finish_unsent: false,
+            transitioned: false,
+            hub: None,
         }
     }
 
-    fn total_work_secs(&self) -> u64 {
-        self.work_minutes * 60
+    fn total_work(&self) -> Duration {
+        self.work_target
+            .unwrap_or(Duration::from_secs(self.work_minutes * 60))
+    }
+
+    fn total_break(&self) -> Duration {
+        Duration::from_secs(self.break_minutes * 60)
+    }
+
+    fn work_done(&self) -> bool {
+        self.elapsed >= self.total_work()
     }
 
+    // Accumulates the full (sub-second) span since the last accumulation point,
+    // so frequent polling never drops fractions of a second. Time past the end
+    // of work carries over into the break; with `break_minutes == 0` there is
+    // no break phase and the session goes straight to Finished.
+    //
+    // A single call resolves any gap, however long: a session left unpolled for
+    // hours goes Running -> Break -> Finished here, with `finished_at` backdated
+    // by the overshoot. Together with the ticker calling this every second, the
+    // stored state is eventually consistent within one tick.
     fn update_elapsed(&mut self) {
-        if let (PomodoroState::Running, Some(start)) = (self.state, self.started_at) {
-            let now = Instant::now();
-            self.elapsed_secs += now.saturating_duration_since(start).as_secs();
-            self.started_at = Some(now);
-            if self.elapsed_secs >= self.total_work_secs() {
-                self.state = PomodoroState::Finished;
+        self.update_elapsed_at(clock::instant());
+    }
+
+    // As `update_elapsed`, against a caller-supplied clock reading so a batch of
+    // sessions can be brought up to the same instant.
+    fn update_elapsed_at(&mut self, now: Instant) {
+        let Some(start) = self.started_at else {
+            return;
+        };
+        if !self.is_active() {
+            return;
+        }
+        let mut delta = now.saturating_duration_since(start);
+        self.started_at = Some(now);
+        if self.state == PomodoroState::Running {
+            self.elapsed = self.elapsed.saturating_add(delta);
+            if !self.work_done() {
+                return;
             }
+            delta = self.elapsed - self.total_work();
+            self.elapsed = self.total_work();
+            if self.break_minutes == 0 {
+                self.finish(now, delta);
+                return;
+            }
+            self.state = PomodoroState::Break;
+            self.transitioned = true;
+            self.record(EventKind::BreakStarted, PomodoroState::Running, None);
+        }
+        self.break_elapsed = self.break_elapsed.saturating_add(delta);
+        if self.break_elapsed >= self.total_break() {
+            let overshoot = self.break_elapsed - self.total_break();
+            self.break_elapsed = self.total_break();
+            self.finish(now, overshoot);
+        }
+    }
+
+    // `overshoot` is how long before `now` the session actually ended.
+    fn finish(&mut self, now: Instant, overshoot: Duration) {
+        self.end_round(now, overshoot);
+        if let Some(hub) = &self.hub {
+            hub.metrics.session_duration.observe(self.elapsed.as_secs());
+        }
+    }
+
+    // Moves the session to Finished; `finish` also counts it in the metrics.
+    fn end_round(&mut self, now: Instant, overshoot: Duration) {
+        self.started_at = None;
+        if let Some(at) = self.paused_at.take() {
+            self.round_suspended += now.saturating_duration_since(at);
         }
+        self.focus_score = Some(self.compute_focus_score());
+        let ended = now.checked_sub(overshoot).unwrap_or(now);
+        self.finished_at = Some(Clock::now().system_at(ended));
+        let from = self.state;
+        self.state = PomodoroState::Finished;
+        self.rounds_completed = self.rounds_completed.saturating_add(1);
+        if !self.pattern.is_empty() {
+            self.pattern_index = (self.pattern_index + 1) % self.pattern.len();
+        }
+        self.finish_unsent = true;
+        self.transitioned = true;
+        self.record(EventKind::Finished, from, None);
+    }
+
+    // Returns the session to Idle; `rounds_completed` is kept across resets.
+    // A patterned session also takes on the durations of its upcoming round,
+    // except on its first start: until then it keeps the lengths it was given,
+    // which for a split part are its share of the round.
+    fn reset(&mut self) {
+        if self.was_started
+            && let Some(round) = self.pattern.get(self.pattern_index)
+        {
+            self.work_minutes = round.work_minutes;
+            self.break_minutes = round.break_minutes;
+        }
+        self.elapsed = Duration::ZERO;
+        self.break_elapsed = Duration::ZERO;
+        self.started_at = None;
+        self.paused_at = None;
+        self.finished_at = None;
+        self.round_pauses = 0;
+        self.round_interruptions = 0;
+        self.round_suspended = Duration::ZERO;
+        self.focus_score = None;
+        self.work_target = None;
+        self.state = PomodoroState::Idle;
     }
 
-    fn remaining_secs(&mut self) -> u64 {
+    // 100 minus 5 per pause, 15 per interruption, and up to 40 for the share of
+    // the round spent suspended; the result is then scaled by how much of the
+    // planned work was actually done, so a forced early finish scores low.
+    fn compute_focus_score(&self) -> u64 {
+        let work = self.total_work().as_secs_f64();
+        let suspended = self.round_suspended.as_secs_f64();
+        let suspended_share = if work + suspended > 0.0 {
+            suspended / (work + suspended)
+        } else {
+            0.0
+        };
+        let penalty = 5.0 * self.round_pauses as f64
+            + 15.0 * self.round_interruptions as f64
+            + 40.0 * suspended_share;
+        let completion = if work > 0.0 {
+            (self.elapsed.as_secs_f64() / work).min(1.0)
+        } else {
+            1.0
+        };
+        ((100.0 - penalty).max(0.0) * completion).round() as u64
+    }
+
+    fn tick(&mut self) {
         self.update_elapsed();
-        self.total_work_secs().saturating_sub(self.elapsed_secs)
+        if let (PomodoroState::Finished, Some(grace), Some(finished)) =
+            (self.state, self.auto_reset_after, self.finished_at)
+            && clock::utc() - finished >= TimeDelta::seconds(grace as i64)
+        {
+            self.reset();
+            self.transitioned = true;
+            self.record(EventKind::Reset, PomodoroState::Finished, None);
+        }
     }
-}
 
-#[derive(Default, Clone)]
-struct AppState {
-    next_id: u64,
-    sessions: HashMap<u64, PomodoroSession>,
-}
+    fn is_unfinished(&self) -> bool {
+        matches!(
+            self.state,
+            PomodoroState::Running
+                | PomodoroState::Break
+                | PomodoroState::Paused
+                | PomodoroState::Interrupted
+        )
+    }
 
-type SharedState = Arc<Mutex<AppState>>;
+    // Ends any started, unfinished session now with its elapsed time as-is.
+    // That time is wherever the session happened to be, not a length it ran
+    // to, so it stays out of the session duration histogram.
+    fn force_finish(&mut self) -> bool {
+        self.update_elapsed();
+        if !self.is_unfinished() {
+            return false;
+        }
+        self.end_round(clock::instant(), Duration::ZERO);
+        true
+    }
 
-#[derive(Deserialize)]
-struct CreateSessionReq {
-    work_minutes: u64,
-    break_minutes: u64,
+    fn phase(&self) -> Phase {
+        if self.work_done() && self.break_minutes > 0 {
+            Phase::Break
+        } else {
+            Phase::Work
+        }
+    }
+
+    fn phase_remaining(&self) -> Duration {
+        match self.phase() {
+            Phase::Work => self.remaining(),
+            Phase::Break => self.total_break().saturating_sub(self.break_elapsed),
+        }
+    }
+
+    // Completion of the current phase, 0..=100.
+    fn phase_percent(&self) -> u64 {
+        let (done, total) = match self.phase() {
+            Phase::Work => (self.elapsed, self.total_work()),
+            Phase::Break => (self.break_elapsed, self.total_break()),
+        };
+        if total.is_zero() {
+            return 100;
+        }
+        (done.as_millis() * 100 / total.as_millis()).min(100) as u64
+    }
+
+    // Paused (or interrupted) and not resumed for at least `threshold`.
+    fn is_abandoned(&self, now: Instant, threshold: Duration) -> bool {
+        matches!(self.state, PomodoroState::Paused | PomodoroState::Interrupted)
+            && self
+                .paused_at
+                .is_some_and(|at| now.saturating_duration_since(at) >= threshold)
+    }
+
+    fn is_active(&self) -> bool {
+        matches!(self.state, PomodoroState::Running | PomodoroState::Break)
+    }
+
+    // Time left until Finished (work plus break) as of `now`, without mutating.
+    fn remaining_total_at(&self, now: Instant) -> Duration {
+        let pending = match self.started_at {
+            Some(start) if self.is_active() => now.saturating_duration_since(start),
+            _ => Duration::ZERO,
+        };
+        (self.total_work() + self.total_break())
+            .saturating_sub(self.elapsed + self.break_elapsed + pending)
+    }
+
+    // Work time done as of `now`, without mutating.
+    fn work_elapsed_at(&self, now: Instant) -> Duration {
+        match self.started_at {
+            Some(start) if self.state == PomodoroState::Running => {
+                (self.elapsed + now.saturating_duration_since(start)).min(self.total_work())
+            }
+            _ => self.elapsed,
+        }
+    }
+
+    fn remaining(&self) -> Duration {
+        self.total_work().saturating_sub(self.elapsed)
+    }
+
+    // Records a client-supplied action id; false means it was already applied
+    // and the retried request should be a no-op.
+    fn first_seen(&mut self, action_id: Option<String>) -> bool {
+        let Some(action_id) = action_id else {
+            return true;
+        };
+        if self.recent_actions.contains(&action_id) {
+            return false;
+        }
+        if self.recent_actions.len() == RECENT_ACTIONS_CAP {
+            self.recent_actions.pop_front();
+        }
+        self.recent_actions.push_back(action_id);
+        true
+    }
+
+    // The state check and the switch to Running happen in one step, so when
+    // two starts race only the first sees Idle/Finished and gets `true`.
+    fn start(&mut self) -> bool {
+        if self.state != PomodoroState::Idle && self.state != PomodoroState::Finished {
+            return false;
+        }
+        let from = self.state;
+        let first_start = !self.was_started;
+        self.reset();
+        let clock = Clock::now();
+        if first_start && let Some(end_at) = self.end_at {
+            // Already past the deadline means nothing is left to do.
+            let left = (end_at - clock.now_system()).to_std().unwrap_or_default();
+            self.work_minutes = left.as_secs().div_ceil(60);
+            self.work_target = Some(left);
+        }
+        let now = clock.now_instant();
+        self.started_at = Some(now);
+        self.last_started_at = Some(now);
+        self.was_started = true;
+        self.state = PomodoroState::Running;
+        self.record(EventKind::Started, from, None);
+        true
+    }
+
+    // Only a pause that would take effect counts against `max_pauses`.
+    fn check_pause_limit(&mut self) -> Result<()> {
+        self.update_elapsed();
+        if self.is_active() && self.max_pauses.is_some_and(|max| self.round_pauses >= max) {
+            return Err(Error::Conflict("pause_limit_reached"));
+        }
+        Ok(())
+    }
+
+    // Like `resume` below, returns whether anything happened: a session that
+    // isn't counting down is left as it is.
+    fn pause(&mut self) -> bool {
+        let Some(from) = self.suspend(PomodoroState::Paused) else {
+            return false;
+        };
+        self.pauses = self.pauses.saturating_add(1);
+        self.round_pauses = self.round_pauses.saturating_add(1);
+        self.record(EventKind::Paused, from, None);
+        true
+    }
+
+    fn interrupt(&mut self) -> bool {
+        let Some(from) = self.suspend(PomodoroState::Interrupted) else {
+            return false;
+        };
+        self.interruptions = self.interruptions.saturating_add(1);
+        self.round_interruptions = self.round_interruptions.saturating_add(1);
+        self.record(EventKind::Interrupted, from, None);
+        true
+    }
+
+    // Clears `started_at` so nothing can accumulate while suspended. Returns
+    // the state that was suspended, if any.
+    fn suspend(&mut self, to: PomodoroState) -> Option<PomodoroState> {
+        self.update_elapsed();
+        if !self.is_active() {
+            return None;
+        }
+        self.started_at = None;
+        self.paused_at = Some(clock::instant());
+        Some(std::mem::replace(&mut self.state, to))
+    }
+
+    // Counting restarts from the moment of resume; the paused span is never added.
+    fn resume(&mut self) -> bool {
+        if !matches!(self.state, PomodoroState::Paused | PomodoroState::Interrupted) {
+            return false;
+        }
+        let from = self.state;
+        let now = clock::instant();
+        self.started_at = Some(now);
+        self.last_started_at = Some(now);
+        if let Some(at) = self.paused_at.take() {
+            self.round_suspended += now.saturating_duration_since(at);
+        }
+        self.state = if self.work_done() {
+            PomodoroState::Break
+        } else {
+            PomodoroState::Running
+        };
+        self.record(EventKind::Resumed, from, None);
+        true
+    }
+
+    // Maintenance pauses aren't the user's doing, so unlike `pause` they leave
+    // the pause counters and the focus score's suspended time alone.
+    fn freeze(&mut self) -> bool {
+        let Some(from) = self.suspend(PomodoroState::Paused) else {
+            return false;
+        };
+        self.record(EventKind::Paused, from, None);
+        true
+    }
+
+    fn thaw(&mut self) -> bool {
+        if self.state != PomodoroState::Paused {
+            return false;
+        }
+        let suspended = self.round_suspended;
+        self.resume();
+        self.round_suspended = suspended;
+        true
+    }
+
+    // Freezes the session where it stands. Only sessions that were started and
+    // haven't ended yet can be cancelled.
+    fn cancel(&mut self) -> bool {
+        self.update_elapsed();
+        if !matches!(
+            self.state,
+            PomodoroState::Running
+                | PomodoroState::Break
+                | PomodoroState::Paused
+                | PomodoroState::Interrupted
+        ) {
+            return false;
+        }
+        self.started_at = None;
+        if let Some(at) = self.paused_at.take() {
+            self.round_suspended += clock::instant().saturating_duration_since(at);
+        }
+        let from = std::mem::replace(&mut self.state, PomodoroState::Cancelled);
+        self.record(EventKind::Cancelled, from, None);
+        if let Some(hub) = &self.hub {
+            hub.metrics.early_stop_duration.observe(self.elapsed.as_secs());
+        }
+        true
+    }
+
+    // Folds `source` into this session: its work, pause and interruption
+    // counts, suspended time and history. The caller combines the notes.
+    fn absorb(&mut self, source: PomodoroSession) {
+        let from = self.state;
+        self.elapsed = self.elapsed.saturating_add(source.elapsed);
+        self.rounds_completed = self.rounds_completed.saturating_add(source.rounds_completed);
+        self.pauses = self.pauses.saturating_add(source.pauses);
+        self.interruptions = self.interruptions.saturating_add(source.interruptions);
+        self.round_pauses = self.round_pauses.saturating_add(source.round_pauses);
+        self.round_interruptions = self
+            .round_interruptions
+            .saturating_add(source.round_interruptions);
+        self.round_suspended = self.round_suspended.saturating_add(source.round_suspended);
+        self.history.extend(source.history);
+        self.history.sort_by_key(|entry| entry.at);
+        for (kind, count) in source.history_compacted {
+            *self.history_compacted.entry(kind).or_default() += count;
+        }
+        if self.state == PomodoroState::Finished {
+            self.focus_score = Some(self.compute_focus_score());
+        }
+        self.record(EventKind::Merged, from, Some(source.elapsed.as_secs() as i64));
+    }
+
+    // Shifts work time by `delta_secs` (clamped to the work phase) to correct
+    // a forgotten pause or a late start. Only Running and Paused sessions can be
+    // adjusted; a Running one pushed past its work time moves on to Break or
+    // Finished, and a Paused one does so when resumed.
+    fn adjust(&mut self, delta_secs: i64) -> bool {
+        self.update_elapsed();
+        if !matches!(self.state, PomodoroState::Running | PomodoroState::Paused) {
+            return false;
+        }
+        let from = self.state;
+        let delta = Duration::from_secs(delta_secs.unsigned_abs());
+        self.elapsed = if delta_secs >= 0 {
+            self.elapsed.saturating_add(delta)
+        } else {
+            self.elapsed.saturating_sub(delta)
+        }
+        .min(self.total_work());
+        self.record(EventKind::Adjusted, from, Some(delta_secs));
+        self.update_elapsed();
+        true
+    }
+
+    // Changes the work length in place. Elapsed time is kept and measured
+    // against the new total: a started session already past it finishes now,
+    // and one on break whose work is no longer done goes back to Running.
+    fn set_work_minutes(&mut self, work_minutes: u64) {
+        self.update_elapsed();
+        self.work_minutes = work_minutes;
+        self.work_target = None;
+        self.end_at = None;
+        match self.state {
+            PomodoroState::Running | PomodoroState::Paused | PomodoroState::Interrupted
+                if self.work_done() =>
+            {
+                let overshoot = self.elapsed - self.total_work();
+                self.elapsed = self.total_work();
+                self.finish(clock::instant(), overshoot);
+            }
+            PomodoroState::Break if !self.work_done() => {
+                self.break_elapsed = Duration::ZERO;
+                self.state = PomodoroState::Running;
+                self.record(EventKind::Adjusted, PomodoroState::Break, None);
+            }
+            PomodoroState::Break => self.elapsed = self.total_work(),
+            _ => {}
+        }
+        self.update_elapsed();
+    }
+
+    // Drops the oldest entries past `max_entries` and any older than `cutoff`,
+    // keeping only their per-kind counts. Returns whether anything was dropped.
+    fn compact_history(
+        &mut self,
+        max_entries: Option<usize>,
+        cutoff: Option<DateTime<Utc>>,
+    ) -> bool {
+        let stale = cutoff.map_or(0, |cutoff| self.history.partition_point(|e| e.at < cutoff));
+        let excess = max_entries.map_or(0, |max| self.history.len().saturating_sub(max));
+        let drop = stale.max(excess);
+        for entry in self.history.drain(..drop) {
+            let count = self.history_compacted.entry(entry.event).or_default();
+            *count = count.saturating_add(1);
+        }
+        drop > 0
+    }
+
+    fn record(&mut self, event: EventKind, from: PomodoroState, delta_secs: Option<i64>) {
+        let at = clock::utc();
+        tracing::info!(
+            target: "transition",
+            session_id = self.id.0,
+            event = event.as_str(),
+            from = ?from,
+            to = ?self.state,
+            elapsed_secs = self.elapsed.as_secs(),
+            at = %at.to_rfc3339(),
+        );
+        self.history.push(HistoryEntry {
+            at,
+            event,
+            from,
+            to: self.state,
+            delta_secs,
+        });
+        if let Some(hub) = self.hub.clone() {
+            hub.events.publish(self.id, event);
+            hooks::after(&hub, self, event, from);
+        }
+    }
 }
 
-#[derive(Serialize)]
-struct SessionResponse {
-    id: u64,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Round {
     work_minutes: u64,
+    #[serde(default)]
     break_minutes: u64,
-    state: PomodoroState,
-    elapsed_secs: u64,
-    remaining_secs: u64,
 }
 
-fn to_response(mut s: PomodoroSession) -> SessionResponse {
-    let remaining = s.remaining_secs();
-    SessionResponse {
-        id: s.id,
-        work_minutes: s.work_minutes,
-        break_minutes: s.break_minutes,
-        state: s.state,
-        elapsed_secs: s.elapsed_secs,
-        remaining_secs: remaining,
-    }
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HistoryEntry {
+    #[serde(with = "timestamp")]
+    at: DateTime<Utc>,
+    event: EventKind,
+    from: PomodoroState,
+    to: PomodoroState,
+    delta_secs: Option<i64>,
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    let state: SharedState = Arc::new(Mutex::new(AppState::default()));
-
-    let app = Router::new()
-        .route("/sessions", post(create_session).get(list_sessions))
-        .route(
-            "/sessions/:id/start",
-            post(start_session),
-        )
-        .route(
-            "/sessions/:id/pause",
-            post(pause_session),
-        )
-        .route(
-            "/sessions/:id/resume",
-            post(resume_session),
-        )
-        .route(
-            "/sessions/:id",
-            get(get_session),
-        )
-        .with_state(state);
-    
-    let listener = TcpListener::bind(format!("0.0.0.0:{}", PORT)).await?;
-    axum::serve(listener, app).await?;
-    Ok(())
+// A history entry as `/history` and `/activity` show it.
+#[derive(Serialize)]
+struct HistoryView {
+    at: Stamp,
+    event: EventKind,
+    from: PomodoroState,
+    to: PomodoroState,
+    delta_secs: Option<i64>,
 }
 
-async fn create_session(
-    State(state): State<SharedState>,
-    Json(req): Json<CreateSessionReq>,
-) -> (StatusCode, Json<SessionResponse>) {
-    let mut guard = state.lock().unwrap();
-    guard.next_id += 1;
-    let id = guard.next_id;
-    let session = PomodoroSession::new(id, req.work_minutes, req.break_minutes);
-    guard.sessions.insert(id, session.clone());
-    (
-        StatusCode::CREATED,
-        Json(to_response(session)),
-    )
+impl HistoryEntry {
+    fn view(&self, format: TimestampFormat) -> HistoryView {
+        HistoryView {
+            at: Stamp::new(self.at, format),
+            event: self.event,
+            from: self.from,
+            to: self.to,
+            delta_secs: self.delta_secs,
+        }
+    }
 }
 
-async fn list_sessions(
-    State(state): State<SharedState>,
-) -> Json<Vec<SessionResponse>> {
-    let mut guard = state.lock().unwrap();
-    let res = guard
-        .sessions
-        .values()
-        .cloned()
-        .map(to_response)
-        .collect();
-    Json(res)
+#[derive(Default, Clone, Serialize, Deserialize)]
+struct AppState {
+    next_id: u64,
+    // Ordered by id so every listing is stable across requests.
+    sessions: BTreeMap<SessionId, PomodoroSession>,
+    #[serde(skip)]
+    dirty: bool,
+    #[serde(skip)]
+    subscribers: HashMap<SessionId, usize>,
+    #[serde(skip)]
+    external_ids: HashMap<String, SessionId>,
+    #[serde(skip)]
+    client_ids: HashMap<Uuid, SessionId>,
+    #[serde(skip)]
+    calendar_events: HashMap<String, BTreeSet<SessionId>>,
+    // Highest version handed out; `changes` maps each session's current version to its id.
+    // Like the per-session counters it saturates at u64::MAX rather than wrapping.
+    #[serde(default)]
+    version: u64,
+    #[serde(skip)]
+    changes: BTreeMap<u64, SessionId>,
+    // Final responses of swept Finished sessions, most recently used last.
+    #[serde(skip)]
+    archive: VecDeque<(Instant, SessionResponse)>,
+    // Ids deleted through the API, and when; see `delete_session`.
+    #[serde(skip)]
+    tombstones: HashMap<SessionId, Instant>,
+    #[serde(default)]
+    next_template_id: u64,
+    #[serde(default)]
+    templates: HashMap<u64, templates::TemplateReq>,
+    // Set through /admin/focus-lock; see `check_focus_lock`.
+    #[serde(default)]
+    focus_lock: bool,
+    // Some while in maintenance, holding the sessions it froze. Persisted so a
+    // restart mid-deploy still resumes them when maintenance ends.
+    #[serde(default)]
+    maintenance: Option<Vec<SessionId>>,
+    // What sessions cleared by a `keep_stats` reset had counted up.
+    #[serde(default)]
+    carried: Totals,
+    #[serde(skip)]
+    hub: Arc<Hub>,
 }
 
-async fn get_session(
-    State(state): State<SharedState>,
-    Path(id): Path<u64>,
-) -> Result<Json<SessionResponse>, StatusCode> {
-    let mut guard = state.lock().unwrap();
-    let session = guard.sessions.get(&id).cloned().ok_or(StatusCode::NOT_FOUND)?;
-    Ok(Json(to_response(session)))
+// The cumulative counters `/stats` reports, kept apart from the sessions so a
+// reset can clear those without losing the history behind the numbers.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+struct Totals {
+    sessions_completed: u64,
+    focus_secs: u64,
+    rounds_completed: u64,
+    pauses: u64,
+    interruptions: u64,
 }
 
-async fn start_session(
-    State(state): State<SharedState>,
-    Path(id): Path<u64>,
-) -> Result<Json<SessionResponse>, StatusCode> {
-    let mut guard = state.lock().unwrap();
-    let s = guard.sessions.get_mut(&id).ok_or(StatusCode::NOT_FOUND)?;
-    if s.state == PomodoroState::Idle || s.state == PomodoroState::Finished {
-        s.elapsed_secs = 0;
-        s.started_at = Some(Instant::now());
-        s.paused_at = None;
-        s.state = PomodoroState::Running;
+impl Totals {
+    fn add(&mut self, s: &PomodoroSession) {
+        if s.state == PomodoroState::Finished {
+            self.sessions_completed = self.sessions_completed.saturating_add(1);
+        }
+        self.focus_secs = self.focus_secs.saturating_add(s.elapsed.as_secs());
+        self.rounds_completed = self.rounds_completed.saturating_add(s.rounds_completed);
+        self.pauses = self.pauses.saturating_add(s.pauses);
+        self.interruptions = self.interruptions.saturating_add(s.interruptions);
     }
-    Ok(Json(to_response(s.clone())))
 }
 
-async fn pause_session(
-    State(state): State<SharedState>,
-    Path(id): Path<u64>,
-) -> Result<Json<SessionResponse>, StatusCode> {
-    let mut guard = state.lock().unwrap();
-    let s = guard.sessions.get_mut(&id).ok_or(StatusCode::NOT_FOUND)?;
-    if s.state == PomodoroState::Running {
-        s.update_elapsed();
-        s.state = PomodoroState::Paused;
+impl AppState {
+    // Hands the state, and every session already in it, the server's hub.
+    fn adopt(&mut self, hub: Arc<Hub>) {
+        for s in self.sessions.values_mut() {
+            s.hub = Some(hub.clone());
+        }
+        self.hub = hub;
     }
-    Ok(Json(to_response(s.clone())))
-}
 
-async fn resume_session(
-    State(state): State<SharedState>,
-    Path(id): Path<u64>,
-) -> Result<Json<SessionResponse>, StatusCode> {
-    let mut guard = state.lock().unwrap();
-    let s = guard.sessions.get_mut(&id).ok_or(StatusCode::NOT_FOUND)?;
-    if s.state == PomodoroState::Paused {
-        s.started_at = Some(Instant::now());
-        s.paused_at = None;
-        s.state = PomodoroState::Running;
+    // Expects `req` to be validated already. A session created running has to
+    // get past the start hooks first; it's built under the id it will get, but
+    // the id is only taken once they agree, so a veto leaves no gap.
+    fn create_session(&mut self, req: CreateSessionReq) -> Result<SessionResponse> {
+        let id = SessionId(self.next_id + 1);
+        let mut session = PomodoroSession::new(id, req.work_minutes, req.break_minutes);
+        session.auto_reset_after = req.auto_reset_after;
+        session.title = req.title;
+        session.notes = req.notes;
+        session.user = req.user;
+        session.end_at = req.end_at;
+        session.external_id = req.external_id;
+        session.calendar_event_id = req.calendar_event_id;
+        session.client_id = req.client_id;
+        session.pinned = req.pinned;
+        session.metadata = req.metadata;
+        session.color = req.color;
+        session.notify_profile = req.notify_profile;
+        session.tags = normalize_tags(req.tags);
+        session.depends_on = req.depends_on;
+        session.auto_start = req.auto_start;
+        session.max_pauses = req.max_pauses;
+        if let Some(first) = req.pattern.first() {
+            session.work_minutes = first.work_minutes;
+            session.break_minutes = first.break_minutes;
+        }
+        session.pattern = req.pattern;
+        if req.start_immediately {
+            hooks::check(self, &session, EventKind::Started)?;
+        }
+        self.allocate_id();
+        self.insert_session(session);
+        // Started only once inserted so `created` precedes `started` on /events.
+        if req.start_immediately
+            && let Some(s) = self.sessions.get_mut(&id)
+        {
+            s.start();
+            s.update_elapsed();
+        }
+        Ok(to_response(&self.sessions[&id]))
+    }
+
+    // The only place ids are handed out. Allocation and the matching insert
+    // happen under the same lock guard, so concurrent creates can neither
+    // share an id nor leave a gap.
+    fn allocate_id(&mut self) -> SessionId {
+        self.next_id += 1;
+        SessionId(self.next_id)
+    }
+
+    fn insert_session(&mut self, mut session: PomodoroSession) {
+        session.hub = Some(self.hub.clone());
+        if let Some(external_id) = &session.external_id {
+            self.external_ids.insert(external_id.clone(), session.id);
+        }
+        if let Some(client_id) = session.client_id {
+            self.client_ids.insert(client_id, session.id);
+        }
+        if let Some(event) = &session.calendar_event_id {
+            self.link_calendar_event(event.clone(), session.id);
+        }
+        let id = session.id;
+        self.sessions.insert(id, session);
+        self.touch(id);
+        self.hub.events.publish(id, EventKind::Created);
+    }
+
+    fn remove_session(&mut self, id: SessionId) -> Option<PomodoroSession> {
+        let session = self.sessions.remove(&id)?;
+        if let Some(external_id) = &session.external_id {
+            self.external_ids.remove(external_id);
+        }
+        if let Some(client_id) = &session.client_id {
+            self.client_ids.remove(client_id);
+        }
+        if let Some(event) = &session.calendar_event_id {
+            self.unlink_calendar_event(event, id);
+        }
+        self.changes.remove(&session.version);
+        self.dirty = true;
+        self.hub.events.publish(id, EventKind::Removed);
+        Some(session)
+    }
+
+    fn link_calendar_event(&mut self, event: String, id: SessionId) {
+        self.calendar_events.entry(event).or_default().insert(id);
+    }
+
+    fn unlink_calendar_event(&mut self, event: &str, id: SessionId) {
+        if let Some(ids) = self.calendar_events.get_mut(event) {
+            ids.remove(&id);
+            if ids.is_empty() {
+                self.calendar_events.remove(event);
+            }
+        }
+    }
+
+    // What `carried` becomes once every session is cleared: their counters
+    // folded in with `keep_stats`, nothing at all without it.
+    fn totals_after_reset(&mut self, keep_stats: bool) -> Totals {
+        if !keep_stats {
+            return Totals::default();
+        }
+        let mut totals = self.carried;
+        for s in self.sessions.values_mut() {
+            s.update_elapsed();
+            totals.add(s);
+        }
+        totals
+    }
+
+    fn reset_sessions(&mut self, keep_stats: bool) -> Vec<SessionId> {
+        self.carried = self.totals_after_reset(keep_stats);
+        let ids: Vec<SessionId> = self.sessions.keys().copied().collect();
+        for &id in &ids {
+            self.remove_session(id);
+        }
+        self.dirty = true;
+        ids
+    }
+
+    // Secondary indexes aren't persisted; rebuild them after loading.
+    fn reindex(&mut self) {
+        self.external_ids = self
+            .sessions
+            .values()
+            .filter_map(|s| Some((s.external_id.clone()?, s.id)))
+            .collect();
+        self.client_ids = self
+            .sessions
+            .values()
+            .filter_map(|s| Some((s.client_id?, s.id)))
+            .collect();
+        self.calendar_events.clear();
+        let linked: Vec<(String, SessionId)> = self
+            .sessions
+            .values()
+            .filter_map(|s| Some((s.calendar_event_id.clone()?, s.id)))
+            .collect();
+        for (event, id) in linked {
+            self.link_calendar_event(event, id);
+        }
+        // Files written before versions existed load with every session at 0.
+        let mut unversioned: Vec<SessionId> =
+            self.sessions.values().filter(|s| s.version == 0).map(|s| s.id).collect();
+        unversioned.sort_unstable();
+        for id in unversioned {
+            self.touch(id);
+        }
+        self.changes = self.sessions.values().map(|s| (s.version, s.id)).collect();
+    }
+
+    // Gives the session a fresh version so `changed_since` readers pick it up.
+    fn touch(&mut self, id: SessionId) {
+        let Some(s) = self.sessions.get_mut(&id) else {
+            return;
+        };
+        self.version = self.version.saturating_add(1);
+        self.changes.remove(&s.version);
+        s.version = self.version;
+        self.changes.insert(s.version, id);
+        self.dirty = true;
+    }
+
+    fn changed_since(
+        &mut self,
+        version: u64,
+        keep: impl Fn(&PomodoroSession) -> bool,
+    ) -> Vec<SessionResponse> {
+        let ids: Vec<SessionId> = self
+            .changes
+            .range((Bound::Excluded(version), Bound::Unbounded))
+            .map(|(_, &id)| id)
+            .collect();
+        ids.into_iter()
+            .filter_map(|id| {
+                let s = self.sessions.get_mut(&id)?;
+                s.update_elapsed();
+                keep(s).then(|| to_response(s))
+            })
+            .collect()
+    }
+
+    // Removes Idle sessions that were never started within `idle_ttl` (a session
+    // reset back to Idle after running is kept) and Finished sessions older than
+    // `finished_ttl`. Pinned sessions are never swept. The histories of those
+    // left are compacted in the same pass.
+    fn sweep(&mut self, config: &Config, now: DateTime<Utc>) -> Vec<SessionId> {
+        let expired = self.expired(config, now);
+        for id in &expired {
+            if let Some(mut s) = self.remove_session(*id)
+                && s.state == PomodoroState::Finished
+                && config.archive_capacity > 0
+            {
+                s.update_elapsed();
+                let mut response = to_response(&s);
+                response.archived = true;
+                if self.archive.len() >= config.archive_capacity {
+                    self.archive.pop_front();
+                }
+                self.archive.push_back((clock::instant(), response));
+            }
+        }
+        self.compact_histories(config, now);
+        expired
+    }
+
+    // The sessions `sweep` would remove at `now`.
+    fn expired(&self, config: &Config, now: DateTime<Utc>) -> Vec<SessionId> {
+        let idle_ttl = config.idle_ttl.and_then(|ttl| TimeDelta::from_std(ttl).ok());
+        let finished_ttl = config.finished_ttl.and_then(|ttl| TimeDelta::from_std(ttl).ok());
+        self.sessions
+            .values()
+            .filter(|s| !s.pinned)
+            .filter(|s| match s.state {
+                PomodoroState::Idle => {
+                    !s.was_started && idle_ttl.is_some_and(|ttl| now - s.created_at >= ttl)
+                }
+                PomodoroState::Finished => finished_ttl
+                    .zip(s.finished_at)
+                    .is_some_and(|(ttl, at)| now - at >= ttl),
+                _ => false,
+            })
+            .map(|s| s.id)
+            .collect()
+    }
+
+    fn compact_histories(&mut self, config: &Config, now: DateTime<Utc>) {
+        if config.history_max_entries.is_none() && config.history_max_age.is_none() {
+            return;
+        }
+        let cutoff = config
+            .history_max_age
+            .and_then(|age| TimeDelta::from_std(age).ok())
+            .map(|age| now - age);
+        for s in self.sessions.values_mut() {
+            self.dirty |= s.compact_history(config.history_max_entries, cutoff);
+        }
+    }
+
+    // Drops entries older than `ttl`, then moves a hit to the back of the LRU.
+    fn archived(&mut self, id: SessionId, ttl: Duration) -> Option<SessionResponse> {
+        let now = clock::instant();
+        self.archive.retain(|(at, _)| now.saturating_duration_since(*at) < ttl);
+        let pos = self.archive.iter().position(|(_, r)| r.id == id)?;
+        let entry = self.archive.remove(pos)?;
+        let response = entry.1.clone();
+        self.archive.push_back(entry);
+        Some(response)
+    }
+
+    // A prerequisite that has since been swept counts as done.
+    fn prerequisite_pending(&mut self, depends_on: Option<SessionId>) -> bool {
+        let Some(dep) = depends_on.and_then(|dep| self.sessions.get_mut(&dep)) else {
+            return false;
+        };
+        dep.update_elapsed();
+        dep.state != PomodoroState::Finished
+    }
+
+    // Freezes every running or on-break session; returns how many. A no-op
+    // when maintenance is already on.
+    fn enter_maintenance(&mut self) -> Vec<SessionId> {
+        if self.maintenance.is_some() {
+            return Vec::new();
+        }
+        let frozen: Vec<SessionId> = self
+            .sessions
+            .values_mut()
+            .filter_map(|s| s.freeze().then_some(s.id))
+            .collect();
+        for &id in &frozen {
+            self.touch(id);
+        }
+        self.maintenance = Some(frozen.clone());
+        self.dirty = true;
+        frozen
+    }
+
+    // The sessions entering (`enable`) or leaving maintenance would freeze or
+    // resume, without doing either.
+    fn maintenance_preview(&mut self, enable: bool) -> Vec<SessionId> {
+        match (&self.maintenance, enable) {
+            (None, true) => self
+                .sessions
+                .values_mut()
+                .filter_map(|s| {
+                    s.update_elapsed();
+                    s.is_active().then_some(s.id)
+                })
+                .collect(),
+            (Some(frozen), false) => frozen
+                .iter()
+                .copied()
+                .filter(|id| {
+                    self.sessions.get(id).is_some_and(|s| s.state == PomodoroState::Paused)
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    // Resumes what `enter_maintenance` froze, skipping sessions that were
+    // removed or changed state in the meantime.
+    fn exit_maintenance(&mut self) -> Vec<SessionId> {
+        let Some(frozen) = self.maintenance.take() else {
+            return Vec::new();
+        };
+        let mut resumed = Vec::new();
+        for id in frozen {
+            if let Some(s) = self.sessions.get_mut(&id)
+                && s.thaw()
+            {
+                resumed.push(id);
+                self.touch(id);
+            }
+        }
+        self.dirty = true;
+        resumed
+    }
+
+    // Brings every session up to `now`, so anything summing finished sessions
+    // also sees the ones that ran out since the last tick.
+    fn update_elapsed_at(&mut self, now: Instant) {
+        for s in self.sessions.values_mut() {
+            s.update_elapsed_at(now);
+        }
+    }
+
+    // Nothing may change during maintenance.
+    fn check_maintenance(&self) -> Result<()> {
+        match self.maintenance {
+            Some(_) => Err(Error::Unavailable("maintenance")),
+            None => Ok(()),
+        }
+    }
+
+    // While the focus lock is on, new sessions may only be added alongside one
+    // that is already running.
+    fn check_focus_lock(&self) -> Result<()> {
+        let running = self
+            .sessions
+            .values()
+            .any(|s| s.state == PomodoroState::Running);
+        if self.focus_lock && !running {
+            return Err(Error::Locked("focus_locked"));
+        }
+        Ok(())
+    }
+
+    // Every path that adds sessions checks this: nothing is added during
+    // maintenance, and the focus lock holds new ones back.
+    fn check_can_create(&self) -> Result<()> {
+        self.check_maintenance()?;
+        self.check_focus_lock()
+    }
+
+    // Fails while a session other than `id` is on its break, reporting the
+    // longest one left, rounded up so a nearly-over break never reads as zero.
+    fn check_break_finished(&self, id: SessionId) -> Result<()> {
+        let now = clock::instant();
+        let remaining = self
+            .sessions
+            .values()
+            .filter(|s| s.id != id && s.state == PomodoroState::Break)
+            .map(|s| s.remaining_total_at(now))
+            .max()
+            .unwrap_or_default();
+        if remaining.is_zero() {
+            return Ok(());
+        }
+        Err(Error::BreakNotFinished(remaining.as_millis().div_ceil(1000) as u64))
+    }
+
+    fn is_blocked(&mut self, id: SessionId) -> bool {
+        let depends_on = self.sessions.get(&id).and_then(|s| s.depends_on);
+        self.prerequisite_pending(depends_on)
     }
-    Ok(Json(to_response(s.clone())))
+
+    // Rejects unknown prerequisites and any chain that leads back to `id`.
+    fn check_dependency(&self, id: SessionId, dep: SessionId) -> Result<()> {
+        if !self.sessions.contains_key(&dep) {
+            return Err(Error::Validation(format!("depends_on: no session {dep}")));
+        }
+        let mut seen = HashSet::new();
+        let mut next = Some(dep);
+        while let Some(current) = next {
+            if current == id || !seen.insert(current) {
+                return Err(Error::Validation("depends_on would create a cycle".into()));
+            }
+            next = self.sessions.get(&current).and_then(|s| s.depends_on);
+        }
+        Ok(())
+    }
+
+    // Never-started auto_start sessions whose prerequisite has finished.
+    fn start_unblocked(&mut self) {
+        let waiting: Vec<SessionId> = self
+            .sessions
+            .values()
+            .filter(|s| s.auto_start && !s.was_started && s.depends_on.is_some())
+            .filter(|s| s.state == PomodoroState::Idle)
+            .map(|s| s.id)
+            .collect();
+        // A vetoed start is left waiting and tried again on the next tick.
+        for id in waiting {
+            if !self.is_blocked(id)
+                && hooks::before(self, id, EventKind::Started).is_ok()
+                && let Some(s) = self.sessions.get_mut(&id)
+                && s.start()
+            {
+                self.touch(id);
+            }
+        }
+    }
+
+    // Cancelled sessions are read-only, and nothing may change during maintenance.
+    fn check_mutable(&self, id: SessionId) -> Result<()> {
+        self.check_maintenance()?;
+        match self.sessions.get(&id) {
+            None => Err(Error::NotFound),
+            Some(s) if s.state == PomodoroState::Cancelled => Err(Error::Conflict("cancelled")),
+            Some(_) => Ok(()),
+        }
+    }
+
+    // For handlers that mutate a session. `change` returns whether it changed
+    // anything; only then does the session get a new version and the state get
+    // marked for the next flush, so neither a refused request nor a no-op (a
+    // pause of a paused session, a replayed action) shows up for
+    // `changed_since` readers.
+    fn modify(
+        &mut self,
+        id: SessionId,
+        change: impl FnOnce(&mut PomodoroSession) -> Result<bool>,
+    ) -> Result<&mut PomodoroSession> {
+        self.check_mutable(id)?;
+        if change(self.sessions.get_mut(&id).ok_or(Error::NotFound)?)? {
+            self.touch(id);
+        }
+        self.sessions.get_mut(&id).ok_or(Error::NotFound)
+    }
+}
+
+type SharedState = Arc<Mutex<AppState>>;
+
+// Lock-free copy of the list response, refreshed by the ticker. Reads through it
+// may be up to one tick (one second) stale, including missing just-created sessions.
+// With stale reads enabled, locked list reads refresh it as well. The ignored
+// `listing_under_contention` test measures what it saves.
+type ListSnapshot = Arc<ArcSwap<Snapshot>>;
+
+#[derive(Default)]
+struct Snapshot {
+    taken: Option<Instant>,
+    items: Vec<SessionResponse>,
+}
+
+enum Read<'a> {
+    Locked(MutexGuard<'a, AppState>),
+    Stale(Arc<Snapshot>),
+}
+
+// Takes the lock, unless `bound` is set, the lock is busy right now and the
+// snapshot is no older than `bound`; then the snapshot is served instead of
+// waiting. Callers mark those responses with `X-Stale: true`.
+fn read_or_stale<'a>(
+    state: &'a SharedState,
+    snapshot: &ListSnapshot,
+    bound: Option<Duration>,
+) -> Result<Read<'a>> {
+    let Some(bound) = bound else {
+        return Ok(Read::Locked(state.lock()?));
+    };
+    match state.try_lock() {
+        Ok(guard) => Ok(Read::Locked(guard)),
+        Err(TryLockError::Poisoned(err)) => Err(err.into()),
+        Err(TryLockError::WouldBlock) => {
+            let cached = snapshot.load_full();
+            if cached
+                .taken
+                .is_some_and(|taken| clock::instant().saturating_duration_since(taken) <= bound) {
+                Ok(Read::Stale(cached))
+            } else {
+                Ok(Read::Locked(state.lock()?))
+            }
+        }
+    }
+}
+
+const X_STALE: &str = "x-stale";
+
+#[derive(Clone)]
+struct AppContext {
+    state: SharedState,
+    config: Arc<Config>,
+    snapshot: ListSnapshot,
+    hub: Arc<Hub>,
+}
+
+impl FromRef<AppContext> for SharedState {
+    fn from_ref(ctx: &AppContext) -> Self {
+        ctx.state.clone()
+    }
+}
+
+impl FromRef<AppContext> for Arc<Config> {
+    fn from_ref(ctx: &AppContext) -> Self {
+        ctx.config.clone()
+    }
+}
+
+impl FromRef<AppContext> for ListSnapshot {
+    fn from_ref(ctx: &AppContext) -> Self {
+        ctx.snapshot.clone()
+    }
+}
+
+impl FromRef<AppContext> for Arc<Hub> {
+    fn from_ref(ctx: &AppContext) -> Self {
+        ctx.hub.clone()
+    }
+}
+
+#[derive(Deserialize, Default, Validate)]
+struct CreateSessionReq {
+    // Required unless `end_at` is given instead.
+    #[serde(default)]
+    #[validate(range(max = MAX_MINUTES, message = "work_minutes may be at most 1440"))]
+    work_minutes: u64,
+    // Finish work by this time rather than after a fixed length; see `resolve_end_at`.
+    #[serde(default, with = "timestamp::option")]
+    end_at: Option<DateTime<Utc>>,
+    // Zero (the default) means no break: work ends straight in Finished.
+    #[serde(default)]
+    #[validate(range(max = MAX_MINUTES, message = "break_minutes may be at most 1440"))]
+    break_minutes: u64,
+    auto_reset_after: Option<u64>,
+    #[serde(default)]
+    start_immediately: bool,
+    #[validate(length(min = 1, max = 200, message = "title must be 1 to 200 bytes"))]
+    title: Option<String>,
+    #[validate(length(max = MAX_NOTES_BYTES, message = "notes may be at most 4096 bytes"))]
+    notes: Option<String>,
+    #[validate(length(min = 1, max = 64, message = "user must be 1 to 64 bytes"))]
+    user: Option<String>,
+    #[validate(length(min = 1, max = 128, message = "external_id must be 1 to 128 bytes"))]
+    external_id: Option<String>,
+    #[validate(length(min = 1, max = 256, message = "calendar_event_id must be 1 to 256 bytes"))]
+    calendar_event_id: Option<String>,
+    // A UUID the client generated for the session (offline-first sync). The
+    // server still keys sessions by its own numeric `id`; this is a unique alias.
+    #[serde(rename = "id")]
+    client_id: Option<Uuid>,
+    #[serde(default)]
+    pinned: bool,
+    #[serde(default)]
+    #[validate(custom(function = "validate_metadata"))]
+    metadata: HashMap<String, String>,
+    #[validate(custom(function = "validate_color"))]
+    color: Option<String>,
+    #[validate(custom(function = "validate_notify_profile"))]
+    notify_profile: Option<String>,
+    #[serde(default)]
+    #[validate(length(max = MAX_TAGS, message = "at most 32 tags are allowed"))]
+    tags: Vec<String>,
+    depends_on: Option<SessionId>,
+    // Start by itself once `depends_on` finishes.
+    #[serde(default)]
+    auto_start: bool,
+    // Once a round has been paused this often, further pauses get a 409 until
+    // the session is reset.
+    max_pauses: Option<u64>,
+    // Per-round durations, cycled; when set, the first round overrides
+    // `work_minutes` and `break_minutes`.
+    #[serde(default)]
+    #[validate(custom(function = "validate_pattern"))]
+    pattern: Vec<Round>,
+}
+
+impl CreateSessionReq {
+    // `end_at` replaces the work length. Until the session starts, `work_minutes`
+    // shows the time left as of creation; the first start measures it again. A
+    // pattern's first round sets the length too, so it can go without either.
+    fn resolve_end_at(&mut self, now: DateTime<Utc>) -> Result<()> {
+        let Some(end_at) = self.end_at else {
+            if self.work_minutes == 0 && self.pattern.is_empty() {
+                return Err(Error::Validation(
+                    "work_minutes, pattern or end_at is required".into(),
+                ));
+            }
+            return Ok(());
+        };
+        if self.work_minutes != 0 || !self.pattern.is_empty() {
+            return Err(Error::Validation(
+                "end_at can't be combined with work_minutes or pattern".into(),
+            ));
+        }
+        let left = (end_at - now).num_seconds();
+        if left <= 0 {
+            return Err(Error::Validation("end_at must be in the future".into()));
+        }
+        self.work_minutes = (left as u64).div_ceil(60);
+        if self.work_minutes > MAX_MINUTES {
+            return Err(Error::Validation("end_at may be at most 24 hours away".into()));
+        }
+        Ok(())
+    }
+}
+
+// `metadata` is merged key by key; a null value removes that key. A null
+// `color` clears it, while an absent one leaves it unchanged. Fields are held
+// to the same rules as on create; the merged metadata is checked once merged.
+#[derive(Deserialize, Validate)]
+struct UpdateSessionReq {
+    // Unlike on create there's no `end_at` to stand in for it, so zero is refused.
+    #[validate(range(min = 1, max = MAX_MINUTES, message = "work_minutes must be 1 to 1440"))]
+    work_minutes: Option<u64>,
+    #[serde(default, deserialize_with = "present")]
+    #[validate(length(min = 1, max = 200, message = "title must be 1 to 200 bytes"))]
+    title: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    #[validate(length(max = MAX_NOTES_BYTES, message = "notes may be at most 4096 bytes"))]
+    notes: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    #[validate(length(min = 1, max = 64, message = "user must be 1 to 64 bytes"))]
+    user: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    #[validate(length(min = 1, max = 256, message = "calendar_event_id must be 1 to 256 bytes"))]
+    calendar_event_id: Option<Option<String>>,
+    pinned: Option<bool>,
+    metadata: Option<HashMap<String, Option<String>>>,
+    #[serde(default, deserialize_with = "present")]
+    #[validate(custom(function = "validate_color"))]
+    color: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    #[validate(custom(function = "validate_notify_profile"))]
+    notify_profile: Option<Option<String>>,
+    #[validate(length(max = MAX_TAGS, message = "at most 32 tags are allowed"))]
+    tags: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "present")]
+    depends_on: Option<Option<SessionId>>,
+    auto_start: Option<bool>,
+}
+
+// Distinguishes an explicit `null` (Some(None)) from a missing field (None).
+fn present<'de, D, T>(de: D) -> std::result::Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(de).map(Some)
+}
+
+fn invalid(code: &'static str, message: String) -> ValidationError {
+    ValidationError::new(code).with_message(message.into())
+}
+
+fn validate_color(color: &str) -> std::result::Result<(), ValidationError> {
+    match color.strip_prefix('#') {
+        Some(hex) if hex.len() == 6 && hex.bytes().all(|b| b.is_ascii_hexdigit()) => Ok(()),
+        _ => Err(invalid("color", "color must be a hex color like #RRGGBB".into())),
+    }
+}
+
+fn validate_notify_profile(profile: &str) -> std::result::Result<(), ValidationError> {
+    if NOTIFY_PROFILES.contains(&profile) {
+        return Ok(());
+    }
+    Err(invalid(
+        "notify_profile",
+        format!("notify_profile must be one of {}", NOTIFY_PROFILES.join(", ")),
+    ))
+}
+
+fn validate_pattern(pattern: &[Round]) -> std::result::Result<(), ValidationError> {
+    if pattern.len() > MAX_PATTERN_ROUNDS {
+        let message = format!("at most {MAX_PATTERN_ROUNDS} rounds are allowed");
+        return Err(invalid("pattern", message));
+    }
+    let in_range =
+        |r: &Round| (1..=MAX_MINUTES).contains(&r.work_minutes) && r.break_minutes <= MAX_MINUTES;
+    if !pattern.iter().all(in_range) {
+        return Err(invalid(
+            "pattern",
+            "each round needs 1 to 1440 work minutes and at most 1440 break minutes".into(),
+        ));
+    }
+    Ok(())
+}
+
+// Tags are matched exactly, so blanks and duplicates are dropped up front.
+fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut tags: Vec<String> = tags
+        .into_iter()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+fn validate_metadata(
+    metadata: &HashMap<String, String>,
+) -> std::result::Result<(), ValidationError> {
+    if metadata.len() > MAX_METADATA_KEYS {
+        return Err(invalid(
+            "metadata_keys",
+            format!("metadata may have at most {MAX_METADATA_KEYS} keys"),
+        ));
+    }
+    let bytes: usize = metadata.iter().map(|(k, v)| k.len() + v.len()).sum();
+    if bytes > MAX_METADATA_BYTES {
+        return Err(invalid(
+            "metadata_bytes",
+            format!("metadata may total at most {MAX_METADATA_BYTES} bytes"),
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct CreateQuery {
+    #[serde(default)]
+    get_or_create: bool,
+}
+
+#[derive(Clone, Serialize)]
+struct SessionResponse {
+    id: SessionId,
+    work_minutes: u64,
+    break_minutes: u64,
+    state: PomodoroState,
+    created_at: Stamp,
+    title: Option<String>,
+    notes: Option<String>,
+    user: Option<String>,
+    external_id: Option<String>,
+    calendar_event_id: Option<String>,
+    client_id: Option<Uuid>,
+    pinned: bool,
+    metadata: HashMap<String, String>,
+    color: Option<String>,
+    notify_profile: Option<String>,
+    tags: Vec<String>,
+    split_from: Option<SessionId>,
+    next_session: Option<SessionId>,
+    depends_on: Option<SessionId>,
+    auto_start: bool,
+    max_pauses: Option<u64>,
+    version: u64,
+    elapsed_secs: u64,
+    // Work time left; `phase_remaining_secs` is the countdown for whichever
+    // phase the session is in.
+    remaining_secs: u64,
+    phase: Phase,
+    phase_remaining_secs: u64,
+    rounds_completed: u64,
+    pauses: u64,
+    interruptions: u64,
+    finished_at: Option<Stamp>,
+    focus_score: Option<u64>,
+    history_compacted: BTreeMap<EventKind, u64>,
+    pattern: Vec<Round>,
+    pattern_index: usize,
+    end_at: Option<Stamp>,
+    // How long the current pause (or interruption) has lasted; null otherwise.
+    paused_for_secs: Option<u64>,
+    // Set only on the copy served from the archive after the session was swept.
+    archived: bool,
+}
+
+// Callers bring the session up to date with `update_elapsed` first; this only reads.
+fn to_response(s: &PomodoroSession) -> SessionResponse {
+    to_response_at(s, clock::instant())
+}
+
+// A session not yet in a state is shown with the default settings.
+fn to_response_at(s: &PomodoroSession, now: Instant) -> SessionResponse {
+    let (rounding, format) = s
+        .hub
+        .as_ref()
+        .map_or((Rounding::Floor, TimestampFormat::Rfc3339), |hub| {
+            (hub.rounding, hub.timestamps)
+        });
+    let stamp = |at| Stamp::new(at, format);
+    SessionResponse {
+        id: s.id,
+        work_minutes: s.work_minutes,
+        break_minutes: s.break_minutes,
+        state: s.state,
+        created_at: stamp(s.created_at),
+        title: s.title.clone(),
+        notes: s.notes.clone(),
+        user: s.user.clone(),
+        external_id: s.external_id.clone(),
+        calendar_event_id: s.calendar_event_id.clone(),
+        client_id: s.client_id,
+        pinned: s.pinned,
+        metadata: s.metadata.clone(),
+        color: s.color.clone(),
+        notify_profile: s.notify_profile.clone(),
+        tags: s.tags.clone(),
+        split_from: s.split_from,
+        next_session: s.next_session,
+        depends_on: s.depends_on,
+        auto_start: s.auto_start,
+        max_pauses: s.max_pauses,
+        version: s.version,
+        elapsed_secs: rounding.secs(s.elapsed),
+        remaining_secs: rounding.secs(s.remaining()),
+        phase: s.phase(),
+        phase_remaining_secs: rounding.secs(s.phase_remaining()),
+        rounds_completed: s.rounds_completed,
+        pauses: s.pauses,
+        interruptions: s.interruptions,
+        finished_at: s.finished_at.map(stamp),
+        focus_score: s.focus_score,
+        history_compacted: s.history_compacted.clone(),
+        pattern: s.pattern.clone(),
+        pattern_index: s.pattern_index,
+        end_at: s.end_at.map(stamp),
+        paused_for_secs: s
+            .paused_at
+            .filter(|_| matches!(s.state, PomodoroState::Paused | PomodoroState::Interrupted))
+            .map(|at| now.saturating_duration_since(at).as_secs()),
+        archived: false,
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let config = Arc::new(Config::from_env());
+    logger(config.log_format, io::stderr).init();
+    let hub = Arc::new(Hub::new(&config));
+    let mut initial = match &config.state_file {
+        Some(path) => persist::load(path)?.unwrap_or_default(),
+        None => AppState::default(),
+    };
+    initial.adopt(hub.clone());
+    initial.next_id = initial.next_id.max(config.id_seed);
+    let state: SharedState = Arc::new(Mutex::new(initial));
+    let ctx = AppContext {
+        state: state.clone(),
+        config: config.clone(),
+        snapshot: ListSnapshot::default(),
+        hub: hub.clone(),
+    };
+    let tasks = &hub.tasks;
+
+    {
+        let ctx = ctx.clone();
+        supervise::spawn(tasks.clone(), "ticker", move || {
+            run_ticker(Ticker::new(&ctx))
+        });
+    }
+    {
+        let (state, config) = (state.clone(), config.clone());
+        supervise::spawn(tasks.clone(), "sweeper", move || {
+            run_sweeper(state.clone(), config.clone())
+        });
+    }
+    if let (Some(at), Some(url)) = (config.summary_at, config.summary_webhook_url.clone()) {
+        let webhook = webhook::Webhook::new(&config, &hub.deliveries, url);
+        let (state, skip_empty) = (state.clone(), config.summary_skip_empty);
+        supervise::spawn(tasks.clone(), "summary", move || {
+            let push = summary::SummaryPush::new(state.clone(), webhook.clone(), at, skip_empty);
+            summary::run_summary(push)
+        });
+    }
+    if let Some(path) = config.state_file.clone() {
+        let (state, interval, format) = (state.clone(), config.flush_interval, config.state_format);
+        supervise::spawn(tasks.clone(), "flusher", move || {
+            persist::run_flusher(state.clone(), path.clone(), interval, format)
+        });
+    }
+
+    let shutdown = shutdown_signal(hub.clone());
+    serve(&ctx, app(ctx.clone()), shutdown).await?;
+    if let Some(path) = &config.state_file {
+        persist::flush(&state, path, config.state_format)?;
+    }
+    Ok(())
+}
+
+fn logger<W>(format: LogFormat, writer: W) -> Box<dyn tracing::Subscriber + Send + Sync>
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let fmt = tracing_subscriber::fmt().with_writer(writer);
+    match format {
+        LogFormat::Pretty => Box::new(fmt.finish()),
+        LogFormat::Json => Box::new(fmt.json().finish()),
+    }
+}
+
+// Serves until `shutdown` resolves, then gives webhook deliveries still in
+// flight up to the shutdown grace to finish.
+async fn serve(
+    ctx: &AppContext,
+    app: Router,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<()> {
+    match &ctx.config.uds {
+        #[cfg(unix)]
+        Some(path) => uds::serve(path, app, shutdown).await?,
+        _ => {
+            let listener = TcpListener::bind(format!("0.0.0.0:{}", PORT)).await?;
+            axum::serve(listener, app).with_graceful_shutdown(shutdown).await?;
+        }
+    }
+    ctx.hub.deliveries.drain(ctx.config.shutdown_grace).await;
+    Ok(())
+}
+
+async fn shutdown_signal(hub: Arc<Hub>) {
+    let _ = tokio::signal::ctrl_c().await;
+    hub.events.close_streams();
+}
+
+// Long-lived streaming routes must be merged after the timeout layer so they are not cut off.
+fn app(ctx: AppContext) -> Router {
+    let config = ctx.config.clone();
+    let streams = Router::new()
+        .route("/events", get(events::server_events))
+        .route("/sessions/:id/events", get(events::session_events))
+        .route("/pulse", get(events::pulse));
+    let api = Router::new()
+        .route("/", get(index))
+        .route("/sessions", post(create_session).get(list_sessions))
+        .route("/sessions/validate", post(validate_session))
+        .route("/sessions/completed", get(list_completed))
+        .route("/sessions/current", get(current_session))
+        .route("/sessions/by-client-id/:uuid", get(session_by_client_id))
+        .route("/sessions/compare", get(compare_sessions))
+        .route("/export.ndjson", get(export_ndjson))
+        .route("/import/csv", post(import::import_csv))
+        .route("/stats", get(stats))
+        .route("/stats/remaining", get(remaining_stats))
+        .route("/stats/daily", get(daily_stats))
+        .route("/stats/hourly", get(hourly_stats))
+        .route("/stats/busy-until", get(busy_until))
+        .route("/leaderboard", get(leaderboard))
+        .route("/activity", get(activity))
+        .route("/metrics", get(metrics::metrics))
+        .route("/healthz", get(supervise::healthz))
+        .route("/meta", get(meta::meta))
+        .route(
+            "/sessions/:id/start",
+            post(start_session),
+        )
+        .route(
+            "/sessions/actions/pause-by-tag",
+            post(pause_by_tag),
+        )
+        .route(
+            "/sessions/:id/pause",
+            post(pause_session),
+        )
+        .route(
+            "/sessions/:id/interrupt",
+            post(interrupt_session),
+        )
+        .route(
+            "/sessions/:id/resume",
+            post(resume_session),
+        )
+        .route(
+            "/sessions/:id/play",
+            post(play_session),
+        )
+        .route(
+            "/sessions/:id",
+            get(get_session).patch(update_session).delete(delete_session),
+        )
+        .route(
+            "/sessions/:id/tags",
+            put(replace_tags).post(add_tag).delete(remove_tag),
+        )
+        .route(
+            "/sessions/:id/cancel",
+            post(cancel_session),
+        )
+        .route(
+            "/sessions/:id/adjust",
+            post(adjust_session),
+        )
+        .route(
+            "/sessions/:id/suggest",
+            get(suggest_action),
+        )
+        .route(
+            "/sessions/:id/qr",
+            get(session_qr),
+        )
+        .route(
+            "/sessions/:id/history",
+            get(session_history),
+        )
+        .route(
+            "/sessions/:id/continue",
+            post(continue_session),
+        )
+        .route(
+            "/sessions/:id/merge",
+            post(merge_session),
+        )
+        .route(
+            "/sessions/:id/split",
+            post(split_session),
+        )
+        .route(
+            "/sessions/:id/projected-completion",
+            get(projected_completion),
+        )
+        .merge(templates::router())
+        .merge(admin::router(config.clone()));
+    #[cfg(test)]
+    let api = api.merge(tests::debug_router());
+    // A zero timeout would fail any request that has to wait on anything.
+    let timeout = match config.request_timeout {
+        Duration::ZERO => {
+            let default = Config::default().request_timeout;
+            tracing::warn!(
+                default_secs = default.as_secs(),
+                "request timeout of zero ignored; using the default"
+            );
+            default
+        }
+        timeout => timeout,
+    };
+    let api = api
+        .layer(middleware::from_fn_with_state(config.clone(), envelope::wrap))
+        .layer(TimeoutLayer::new(timeout));
+    // Streams are exempt: each holds its permit for as long as the client stays connected.
+    // `Router::layer` wraps each route separately, so the semaphore has to be a
+    // shared one for the limit to cover the whole API rather than each route.
+    let api = match config.max_concurrency {
+        Some(permits) => api.layer(
+            ServiceBuilder::new()
+                .layer(HandleErrorLayer::new(|_: BoxError| async {
+                    Error::Unavailable("overloaded")
+                }))
+                .load_shed()
+                .layer(GlobalConcurrencyLimitLayer::new(permits)),
+        ),
+        None => api,
+    };
+    let app = api.merge(streams);
+    let app = if config.inactivity_pause.is_some() {
+        app.layer(middleware::from_fn_with_state(ctx.clone(), track_interaction))
+    } else {
+        app
+    };
+    app.layer(CatchPanicLayer::custom(recover))
+        .layer(middleware::from_fn(request_id::propagate))
+        .with_state(ctx)
+}
+
+// Turns a handler panic into a JSON 500 instead of a dropped connection. A
+// panic while holding the state lock leaves it poisoned (see `Error`'s
+// `From<PoisonError>`), so later requests get the same 500.
+fn recover(panic: Box<dyn Any + Send>) -> Response {
+    let message = panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("non-string payload");
+    tracing::error!(panic = message, "handler panicked");
+    Error::Internal.into_response()
+}
+
+// Any request under `/sessions/:id` counts as interaction with that session,
+// whatever its outcome, for the ticker's inactivity pause.
+async fn track_interaction(
+    State(state): State<SharedState>,
+    req: axum::extract::Request,
+    next: middleware::Next,
+) -> Response {
+    let id = req
+        .uri()
+        .path()
+        .strip_prefix("/sessions/")
+        .and_then(|rest| rest.split('/').next())
+        .and_then(|id| id.parse::<SessionId>().ok());
+    if let Some(id) = id
+        && let Ok(mut guard) = state.lock()
+        && let Some(s) = guard.sessions.get_mut(&id)
+    {
+        s.last_interaction = Some(clock::instant());
+    }
+    next.run(req).await
+}
+
+async fn index() -> Html<&'static str> {
+    Html(include_str!("index.html"))
+}
+
+// Moves every session along with the clock once a second: finishes, breaks,
+// auto resets and starts, and inactivity pauses. Finishes can happen on any
+// access, so each tick also collects unsent ones and hands them to the
+// notifiers outside the lock.
+struct Ticker {
+    state: SharedState,
+    notifiers: notify::Notifiers,
+    snapshot: Option<ListSnapshot>,
+    quiet_hours: Option<QuietHours>,
+    inactivity: Option<Duration>,
+    // Finish notifications queued during quiet hours.
+    held: Vec<notify::SessionEvent>,
+}
+
+impl Ticker {
+    fn new(ctx: &AppContext) -> Self {
+        let config = &ctx.config;
+        Self {
+            state: ctx.state.clone(),
+            notifiers: notify::from_config(config, &ctx.hub.deliveries),
+            snapshot: config.list_snapshot.then(|| ctx.snapshot.clone()),
+            quiet_hours: config.quiet_hours,
+            inactivity: config.inactivity_pause,
+            held: Vec::new(),
+        }
+    }
+
+    // Only fails once the state lock is poisoned.
+    fn tick(&mut self) -> Result<()> {
+        let mut guard = self.state.lock()?;
+        let mut changed = false;
+        let mut finished = Vec::new();
+        let mut transitioned = Vec::new();
+        let now = clock::instant();
+        for s in guard.sessions.values_mut() {
+            let before = s.state;
+            s.tick();
+            if let Some(window) = self.inactivity
+                && s.state == PomodoroState::Running
+            {
+                // A session running before anyone asked about it (a restart,
+                // an auto start) gets a full window from here.
+                let seen = *s.last_interaction.get_or_insert(now);
+                if now.saturating_duration_since(seen) >= window {
+                    s.pause();
+                    s.transitioned = true;
+                }
+            }
+            changed |= s.state != before || s.is_active();
+            if std::mem::take(&mut s.transitioned) {
+                transitioned.push(s.id);
+            }
+            if std::mem::take(&mut s.finish_unsent) && !self.notifiers.is_empty() {
+                finished.push(notify::SessionEvent {
+                    kind: EventKind::Finished,
+                    session: to_response(s),
+                });
+            }
+        }
+        for id in transitioned {
+            guard.touch(id);
+        }
+        if guard.maintenance.is_none() {
+            guard.start_unblocked();
+        }
+        guard.dirty |= changed;
+        if let Some(snapshot) = &self.snapshot {
+            let now = clock::instant();
+            let responses = guard.sessions.values().map(|s| to_response_at(s, now));
+            snapshot.store(Arc::new(Snapshot {
+                taken: Some(now),
+                items: responses.collect(),
+            }));
+        }
+        drop(guard);
+        // The finish itself is already recorded; quiet hours only hold back notifications.
+        if let Some(quiet) = self.quiet_hours {
+            if quiet.contains(clock::local().time()) {
+                if quiet.queue {
+                    self.held.append(&mut finished);
+                }
+                finished.clear();
+            } else {
+                finished.splice(0..0, self.held.drain(..));
+            }
+        }
+        for event in finished {
+            for notifier in self.notifiers.iter() {
+                notifier.notify(event.clone());
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn run_ticker(mut ticker: Ticker) {
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        interval.tick().await;
+        if ticker.tick().is_err() {
+            return;
+        }
+    }
+}
+
+async fn run_sweeper(state: SharedState, config: Arc<Config>) {
+    let mut interval = tokio::time::interval(config.sweep_interval);
+    loop {
+        interval.tick().await;
+        let Ok(mut guard) = state.lock() else {
+            return;
+        };
+        guard.sweep(&config, clock::utc());
+    }
+}
+
+async fn create_session(
+    State(state): State<SharedState>,
+    QueryParams(q): QueryParams<CreateQuery>,
+    JsonBody(mut req): JsonBody<CreateSessionReq>,
+) -> Result<(StatusCode, Json<SessionResponse>)> {
+    req.validate()?;
+    req.resolve_end_at(clock::utc())?;
+    let mut guard = state.lock()?;
+    if let Some(&existing) = req.external_id.as_ref().and_then(|e| guard.external_ids.get(e)) {
+        if !q.get_or_create {
+            return Err(Error::Conflict("duplicate_external_id"));
+        }
+        let s = guard.sessions.get_mut(&existing).ok_or(Error::NotFound)?;
+        s.update_elapsed();
+        return Ok((StatusCode::OK, Json(to_response(s))));
+    }
+    if req.client_id.is_some_and(|id| guard.client_ids.contains_key(&id)) {
+        return Err(Error::Conflict("duplicate_id"));
+    }
+    guard.check_can_create()?;
+    if let Some(dep) = req.depends_on {
+        // The new id can't be referenced yet, so this only checks `dep` exists.
+        let id = SessionId(guard.next_id + 1);
+        guard.check_dependency(id, dep)?;
+        if req.start_immediately && guard.prerequisite_pending(Some(dep)) {
+            return Err(Error::Conflict("blocked"));
+        }
+    }
+    let response = guard.create_session(req)?;
+    Ok((
+        StatusCode::CREATED,
+        Json(response),
+    ))
+}
+
+#[derive(Serialize)]
+struct Valid {
+    valid: bool,
+}
+
+// Runs create's checks on the body alone, for form validation. Conflicts that
+// depend on other sessions (duplicate ids, a missing dependency) are left to
+// the create call itself.
+async fn validate_session(JsonBody(mut req): JsonBody<CreateSessionReq>) -> Result<Json<Valid>> {
+    req.validate()?;
+    req.resolve_end_at(clock::utc())?;
+    Ok(Json(Valid { valid: true }))
+}
+
+#[derive(Serialize)]
+struct Changes {
+    items: Vec<SessionResponse>,
+    cursor: u64,
+}
+
+// JSON by default; CSV via `?format=csv` or `Accept: text/csv`.
+async fn list_sessions(
+    State(state): State<SharedState>,
+    State(config): State<Arc<Config>>,
+    State(snapshot): State<ListSnapshot>,
+    QueryParams(q): QueryParams<SessionQuery>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let q = q.parse()?;
+    let wants_csv = match q.format {
+        Some(format) => format == Format::Csv,
+        None => headers
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|accept| accept.contains("text/csv")),
+    };
+    // Sync clients pass back `cursor` as `changed_since` on their next call. When
+    // `limit` cuts the batch short, the cursor is the last item's version, so the
+    // next call picks up the rest.
+    if let Some(since) = q.changed_since {
+        let mut guard = state.lock()?;
+        let changed = guard.changed_since(since, |s| q.matches(s));
+        let more = changed.len() > q.offset + q.limit;
+        let items: Vec<SessionResponse> =
+            changed.into_iter().skip(q.offset).take(q.limit).collect();
+        let cursor = match items.last() {
+            Some(last) if more => last.version,
+            _ => guard.version,
+        };
+        drop(guard);
+        if wants_csv {
+            return list_csv(&headers, &items);
+        }
+        return Ok(Json(Changes { items, cursor }).into_response());
+    }
+    if q.is_search() {
+        let limit = q.limit;
+        let now = clock::instant();
+        let mut guard = state.lock()?;
+        // A calendar event id narrows the scan to the sessions linked to it.
+        let candidates: Vec<SessionId> = match q.calendar_event_id() {
+            Some(event) => guard
+                .calendar_events
+                .get(event)
+                .map(|ids| ids.iter().copied().collect())
+                .unwrap_or_default(),
+            None => guard.sessions.keys().copied().collect(),
+        };
+        let matched: Vec<SessionId> = candidates
+            .into_iter()
+            .filter(|id| {
+                guard.sessions.get_mut(id).is_some_and(|s| {
+                    s.update_elapsed_at(now);
+                    q.matches(s)
+                })
+            })
+            .collect();
+        let total = matched.len();
+        let items: Vec<SessionResponse> = matched
+            .iter()
+            .skip(q.offset)
+            .take(limit)
+            .filter_map(|id| guard.sessions.get(id))
+            .map(|s| to_response_at(s, now))
+            .collect();
+        drop(guard);
+        if wants_csv {
+            return list_csv(&headers, &items);
+        }
+        return Ok(Json(Page {
+            items,
+            total,
+            offset: q.offset,
+            limit,
+        })
+        .into_response());
+    }
+    let mut stale = false;
+    // Until the ticker's first pass there's no snapshot to serve.
+    let cached = config
+        .list_snapshot
+        .then(|| snapshot.load_full())
+        .filter(|cached| cached.taken.is_some());
+    let res = if let Some(cached) = cached {
+        cached
+    } else {
+        match read_or_stale(&state, &snapshot, config.stale_read_bound)? {
+            Read::Stale(cached) => {
+                stale = true;
+                cached
+            }
+            Read::Locked(mut guard) => {
+                // One clock reading for the whole page keeps every entry on the same basis.
+                let now = clock::instant();
+                let res = Arc::new(Snapshot {
+                    taken: Some(now),
+                    items: guard
+                        .sessions
+                        .values_mut()
+                        .map(|s| {
+                            s.update_elapsed_at(now);
+                            to_response_at(s, now)
+                        })
+                        .collect(),
+                });
+                drop(guard);
+                if config.stale_read_bound.is_some() {
+                    snapshot.store(res.clone());
+                }
+                res
+            }
+        }
+    };
+    let mut response = if wants_csv {
+        list_csv(&headers, &res.items)?
+    } else {
+        Json(&res.items).into_response()
+    };
+    if stale {
+        response.headers_mut().insert(X_STALE, HeaderValue::from_static("true"));
+    }
+    Ok(response)
+}
+
+// A CSV listing carries only the rows; paging and cursors are JSON-only.
+fn list_csv(headers: &HeaderMap, items: &[SessionResponse]) -> Result<Response> {
+    let body = export::sessions_csv(items)?;
+    Ok(export::ranged(headers, "text/csv", body))
+}
+
+#[derive(Deserialize)]
+struct CompletedQuery {
+    #[serde(default, with = "timestamp::option")]
+    from: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp::option")]
+    to: Option<DateTime<Utc>>,
+    #[serde(default)]
+    offset: usize,
+    #[serde(default = "default_limit")]
+    limit: usize,
+}
+
+fn default_limit() -> usize {
+    50
+}
+
+const MAX_LIMIT: usize = 500;
+
+#[derive(Serialize)]
+struct Page<T> {
+    items: Vec<T>,
+    total: usize,
+    offset: usize,
+    limit: usize,
+}
+
+async fn list_completed(
+    State(state): State<SharedState>,
+    QueryParams(q): QueryParams<CompletedQuery>,
+) -> Result<Json<Page<SessionResponse>>> {
+    if let (Some(from), Some(to)) = (q.from, q.to)
+        && from > to
+    {
+        return Err(Error::bad_query("to", "`from` must not be after `to`".into()));
+    }
+    let limit = q.limit.min(MAX_LIMIT);
+    let mut guard = state.lock()?;
+    guard.sessions.values_mut().for_each(PomodoroSession::update_elapsed);
+    let mut finished: Vec<_> = guard
+        .sessions
+        .values()
+        .filter(|s| s.state == PomodoroState::Finished)
+        .filter_map(|s| s.finished_at.map(|at| (at, s)))
+        .filter(|(at, _)| q.from.is_none_or(|from| *at >= from) && q.to.is_none_or(|to| *at <= to))
+        .collect();
+    finished.sort_by_key(|(at, s)| (*at, s.id));
+    let total = finished.len();
+    let items = finished
+        .into_iter()
+        .skip(q.offset)
+        .take(limit)
+        .map(|(_, s)| to_response(s))
+        .collect();
+    Ok(Json(Page {
+        items,
+        total,
+        offset: q.offset,
+        limit,
+    }))
+}
+
+async fn current_session(State(state): State<SharedState>) -> Result<Response> {
+    let mut guard = state.lock()?;
+    guard.sessions.values_mut().for_each(PomodoroSession::update_elapsed);
+    let current = guard
+        .sessions
+        .values()
+        .filter(|s| s.state == PomodoroState::Running)
+        .max_by_key(|s| (s.last_started_at, s.id));
+    Ok(match current {
+        Some(s) => Json(to_response(s)).into_response(),
+        None => StatusCode::NO_CONTENT.into_response(),
+    })
+}
+
+#[derive(Serialize)]
+struct Stats {
+    sessions: usize,
+    by_state: HashMap<PomodoroState, usize>,
+    abandoned: usize,
+    cancelled: usize,
+    #[serde(flatten)]
+    totals: Totals,
+}
+
+// Counters include whatever a `keep_stats` reset carried over.
+async fn stats(
+    State(state): State<SharedState>,
+    State(config): State<Arc<Config>>,
+) -> Result<Json<Stats>> {
+    let mut guard = state.lock()?;
+    guard.sessions.values_mut().for_each(PomodoroSession::update_elapsed);
+    let now = clock::instant();
+    let mut stats = Stats {
+        sessions: guard.sessions.len(),
+        by_state: HashMap::new(),
+        abandoned: 0,
+        cancelled: 0,
+        totals: guard.carried,
+    };
+    for s in guard.sessions.values() {
+        *stats.by_state.entry(s.state).or_default() += 1;
+        if s.is_abandoned(now, config.abandon_after) {
+            stats.abandoned += 1;
+        }
+        if s.state == PomodoroState::Cancelled {
+            stats.cancelled += 1;
+        }
+        stats.totals.add(s);
+    }
+    Ok(Json(stats))
+}
+
+#[derive(Serialize)]
+struct RemainingStats {
+    active_sessions: usize,
+    total_remaining_secs: u64,
+    min_remaining_secs: Option<u64>,
+}
+
+impl AppState {
+    fn remaining_stats_at(&self, now: Instant) -> RemainingStats {
+        let remaining: Vec<u64> = self
+            .sessions
+            .values()
+            .filter(|s| s.is_active())
+            .map(|s| s.remaining_total_at(now).as_secs())
+            .collect();
+        RemainingStats {
+            active_sessions: remaining.len(),
+            total_remaining_secs: remaining.iter().sum(),
+            min_remaining_secs: remaining.iter().copied().min(),
+        }
+    }
+}
+
+async fn remaining_stats(State(state): State<SharedState>) -> Result<Json<RemainingStats>> {
+    let guard = state.lock()?;
+    Ok(Json(guard.remaining_stats_at(clock::instant())))
+}
+
+const MAX_DAILY_DAYS: u32 = 366;
+
+#[derive(Deserialize)]
+struct DailyQuery {
+    days: Option<u32>,
+    // A fixed UTC offset such as `+02:00` (URL-encoded as `%2B02:00`); UTC if absent.
+    tz: Option<String>,
+}
+
+#[derive(Serialize)]
+struct DailyBucket {
+    date: NaiveDate,
+    focus_secs: u64,
+    sessions_completed: usize,
+}
+
+// One bucket per day, oldest first and ending today in `tz`, with empty days
+// included. Like the summary push, a session counts once, on its latest finish.
+async fn daily_stats(
+    State(state): State<SharedState>,
+    QueryParams(q): QueryParams<DailyQuery>,
+) -> Result<Json<Vec<DailyBucket>>> {
+    let days = q.days.unwrap_or(30);
+    if !(1..=MAX_DAILY_DAYS).contains(&days) {
+        return Err(Error::bad_query("days", format!("days must be 1 to {MAX_DAILY_DAYS}")));
+    }
+    let tz = parse_tz(q.tz.as_deref())?;
+    let today = clock::utc().with_timezone(&tz).date_naive();
+    let first = today - chrono::Days::new(u64::from(days - 1));
+    let mut buckets: Vec<DailyBucket> = first
+        .iter_days()
+        .take(days as usize)
+        .map(|date| DailyBucket {
+            date,
+            focus_secs: 0,
+            sessions_completed: 0,
+        })
+        .collect();
+    let mut guard = state.lock()?;
+    guard.update_elapsed_at(clock::instant());
+    let finished = guard
+        .sessions
+        .values()
+        .filter(|s| s.state == PomodoroState::Finished)
+        .filter_map(|s| Some((s.finished_at?.with_timezone(&tz).date_naive(), s)));
+    for (date, s) in finished {
+        if let Some(bucket) = (date - first)
+            .num_days()
+            .try_into()
+            .ok()
+            .and_then(|i: usize| buckets.get_mut(i))
+        {
+            bucket.focus_secs += s.elapsed.as_secs();
+            bucket.sessions_completed += 1;
+        }
+    }
+    Ok(Json(buckets))
+}
+
+#[derive(Deserialize)]
+struct HourlyQuery {
+    tz: Option<String>,
+}
+
+#[derive(Serialize)]
+struct HourlyBucket {
+    hour: u32,
+    focus_secs: u64,
+    sessions_completed: usize,
+}
+
+// All 24 hours of the day in `tz`, empty ones included. A session's focus is
+// credited to the hour it finished in, the same as the daily buckets.
+async fn hourly_stats(
+    State(state): State<SharedState>,
+    QueryParams(q): QueryParams<HourlyQuery>,
+) -> Result<Json<Vec<HourlyBucket>>> {
+    let tz = parse_tz(q.tz.as_deref())?;
+    let mut buckets: Vec<HourlyBucket> = (0..24)
+        .map(|hour| HourlyBucket {
+            hour,
+            focus_secs: 0,
+            sessions_completed: 0,
+        })
+        .collect();
+    let mut guard = state.lock()?;
+    guard.update_elapsed_at(clock::instant());
+    let finished = guard
+        .sessions
+        .values()
+        .filter(|s| s.state == PomodoroState::Finished)
+        .filter_map(|s| Some((s.finished_at?.with_timezone(&tz).hour(), s)));
+    for (hour, s) in finished {
+        let bucket = &mut buckets[hour as usize];
+        bucket.focus_secs += s.elapsed.as_secs();
+        bucket.sessions_completed += 1;
+    }
+    Ok(Json(buckets))
+}
+
+fn parse_tz(tz: Option<&str>) -> Result<FixedOffset> {
+    match tz {
+        None | Some("Z" | "UTC") => Ok(Utc.fix()),
+        Some(tz) => tz
+            .parse()
+            .map_err(|_| Error::bad_query("tz", "tz must be a UTC offset like +02:00".into())),
+    }
+}
+
+#[derive(Serialize)]
+struct BusyUntil {
+    busy_until: Option<Stamp>,
+}
+
+// The furthest projected completion among sessions that are counting down,
+// breaks included. Paused and interrupted sessions aren't progressing, so they
+// have no projection and don't count.
+async fn busy_until(State(state): State<SharedState>) -> Result<Json<BusyUntil>> {
+    let guard = state.lock()?;
+    let clock = Clock::now();
+    let busy_until = guard
+        .sessions
+        .values()
+        .filter(|s| s.is_active())
+        .map(|s| s.remaining_total_at(clock.now_instant()))
+        .max()
+        .and_then(|remaining| clock.project(remaining));
+    let format = guard.hub.timestamps;
+    Ok(Json(BusyUntil {
+        busy_until: busy_until.map(|at| Stamp::new(at, format)),
+    }))
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+enum Period {
+    Today,
+    Week,
+    #[default]
+    All,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+enum RankBy {
+    #[default]
+    Sessions,
+    FocusMinutes,
+}
+
+#[derive(Deserialize)]
+struct LeaderboardQuery {
+    period: Option<String>,
+    by: Option<String>,
+}
+
+#[derive(Serialize)]
+struct LeaderboardEntry {
+    rank: usize,
+    user: String,
+    sessions_completed: usize,
+    focus_minutes: u64,
+}
+
+#[derive(Serialize)]
+struct Leaderboard {
+    period: Period,
+    by: RankBy,
+    entries: Vec<LeaderboardEntry>,
+}
+
+// Parsed by hand so an unknown value is reported under its own name, with the
+// choices, rather than as an unreadable query.
+fn parse_choice<T: serde::de::DeserializeOwned + Default>(
+    value: Option<&str>,
+    field: &'static str,
+    choices: &str,
+) -> Result<T> {
+    let Some(value) = value else {
+        return Ok(T::default());
+    };
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|_| Error::bad_query(field, format!("{field} must be one of {choices}")))
+}
+
+// Finished sessions with a `user`, grouped per user. `today` starts at UTC
+// midnight and `week` at Monday 00:00 UTC. Ties keep alphabetical order.
+async fn leaderboard(
+    State(state): State<SharedState>,
+    QueryParams(q): QueryParams<LeaderboardQuery>,
+) -> Result<Json<Leaderboard>> {
+    let period: Period = parse_choice(q.period.as_deref(), "period", "today, week, all")?;
+    let by: RankBy = parse_choice(q.by.as_deref(), "by", "sessions, focus_minutes")?;
+    let today = clock::utc().date_naive();
+    let since = match period {
+        Period::Today => Some(today),
+        Period::Week => Some(today.week(chrono::Weekday::Mon).first_day()),
+        Period::All => None,
+    }
+    .and_then(|day| day.and_hms_opt(0, 0, 0))
+    .map(|midnight| midnight.and_utc());
+    let mut totals: BTreeMap<String, (usize, Duration)> = BTreeMap::new();
+    {
+        let guard = state.lock()?;
+        let finished = guard.sessions.values().filter(|s| {
+            s.state == PomodoroState::Finished
+                && s.finished_at.is_some_and(|at| since.is_none_or(|since| at >= since))
+        });
+        for s in finished {
+            if let Some(user) = &s.user {
+                let total = totals.entry(user.clone()).or_default();
+                total.0 += 1;
+                total.1 += s.elapsed;
+            }
+        }
+    }
+    let mut entries: Vec<LeaderboardEntry> = totals
+        .into_iter()
+        .map(|(user, (sessions, focus))| LeaderboardEntry {
+            rank: 0,
+            user,
+            sessions_completed: sessions,
+            focus_minutes: focus.as_secs() / 60,
+        })
+        .collect();
+    match by {
+        RankBy::Sessions => entries.sort_by_key(|e| std::cmp::Reverse(e.sessions_completed)),
+        RankBy::FocusMinutes => entries.sort_by_key(|e| std::cmp::Reverse(e.focus_minutes)),
+    }
+    for (i, entry) in entries.iter_mut().enumerate() {
+        entry.rank = i + 1;
+    }
+    Ok(Json(Leaderboard {
+        period,
+        by,
+        entries,
+    }))
+}
+
+const EXPORT_CHUNK: usize = 100;
+
+fn ndjson_chunk(state: &SharedState, chunk: &[SessionId]) -> Result<Vec<u8>> {
+    let mut guard = state.lock()?;
+    let mut buf = Vec::new();
+    for id in chunk {
+        if let Some(s) = guard.sessions.get_mut(id) {
+            s.update_elapsed();
+            serde_json::to_writer(&mut buf, &to_response(s)).map_err(io::Error::from)?;
+            buf.push(b'\n');
+        }
+    }
+    Ok(buf)
+}
+
+// Streams one session per line, re-acquiring the lock per chunk of ids so
+// neither the lock nor the whole document is held for the full export. A
+// `Range` request needs the total length up front, so it is buffered instead.
+async fn export_ndjson(
+    State(state): State<SharedState>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let mut ids: Vec<SessionId> = state.lock()?.sessions.keys().copied().collect();
+    ids.sort_unstable();
+    let chunks: Vec<Vec<SessionId>> = ids.chunks(EXPORT_CHUNK).map(<[SessionId]>::to_vec).collect();
+    if headers.contains_key(header::RANGE) {
+        let mut body = Vec::new();
+        for chunk in &chunks {
+            body.extend(ndjson_chunk(&state, chunk)?);
+        }
+        return Ok(export::ranged(&headers, "application/x-ndjson", body));
+    }
+    let body = stream::iter(chunks).map(move |chunk| ndjson_chunk(&state, &chunk));
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/x-ndjson"),
+            (header::ACCEPT_RANGES, "bytes"),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response())
+}
+
+async fn get_session(
+    State(state): State<SharedState>,
+    State(config): State<Arc<Config>>,
+    State(snapshot): State<ListSnapshot>,
+    IdPath(id): IdPath,
+) -> Result<Response> {
+    let mut guard = match read_or_stale(&state, &snapshot, config.stale_read_bound)? {
+        Read::Locked(guard) => guard,
+        Read::Stale(cached) => match cached.items.iter().find(|s| s.id == id) {
+            Some(s) => return Ok(([(X_STALE, "true")], Json(s)).into_response()),
+            // Possibly created since the snapshot; only the live state can say.
+            None => state.lock()?,
+        },
+    };
+    let Some(s) = guard.sessions.get_mut(&id) else {
+        let archived = guard.archived(id, config.archive_ttl);
+        return archived.map(|s| Json(s).into_response()).ok_or(Error::NotFound);
+    };
+    s.update_elapsed();
+    Ok(Json(to_response(s)).into_response())
+}
+
+// With a tombstone window configured, deleting an id again within it still
+// answers 204, so a client can retry a DELETE whose response it never saw.
+async fn delete_session(
+    State(state): State<SharedState>,
+    State(config): State<Arc<Config>>,
+    IdPath(id): IdPath,
+) -> Result<StatusCode> {
+    let mut guard = state.lock()?;
+    guard.check_maintenance()?;
+    let now = clock::instant();
+    let window = config.delete_tombstone_ttl;
+    guard
+        .tombstones
+        .retain(|_, at| window.is_some_and(|ttl| now.saturating_duration_since(*at) < ttl));
+    if guard.remove_session(id).is_some() {
+        if window.is_some() {
+            guard.tombstones.insert(id, now);
+        }
+        return Ok(StatusCode::NO_CONTENT);
+    }
+    if guard.tombstones.contains_key(&id) {
+        return Ok(StatusCode::NO_CONTENT);
+    }
+    Err(Error::NotFound)
+}
+
+#[derive(Deserialize)]
+struct SplitReq {
+    parts: u64,
+}
+
+// Replaces an Idle session with `parts` sessions chained via `next_session`;
+// the last part absorbs any remainder minutes. The first part waits on the
+// source's prerequisite, and an `auto_start` source has each later part wait on
+// the one before, so the chain still runs by itself. Sessions that waited on
+// the source wait on the last part instead.
+async fn split_session(
+    State(state): State<SharedState>,
+    IdPath(id): IdPath,
+    JsonBody(req): JsonBody<SplitReq>,
+) -> Result<(StatusCode, Json<Vec<SessionResponse>>)> {
+    let mut guard = state.lock()?;
+    // Not a create: it only replaces a session, so the focus lock doesn't apply.
+    guard.check_maintenance()?;
+    let source = guard.sessions.get(&id).ok_or(Error::NotFound)?;
+    if source.state != PomodoroState::Idle {
+        return Err(Error::Conflict("not_idle"));
+    }
+    if req.parts < 2 || req.parts > source.work_minutes {
+        return Err(Error::Validation(
+            "parts must be at least 2 and at most work_minutes".into(),
+        ));
+    }
+    let source = guard.remove_session(id).ok_or(Error::NotFound)?;
+    let base = source.work_minutes / req.parts;
+    let mut created = Vec::new();
+    for i in 0..req.parts {
+        let part_id = guard.allocate_id();
+        let work_minutes = if i + 1 == req.parts {
+            base + source.work_minutes % req.parts
+        } else {
+            base
+        };
+        let mut part = PomodoroSession::new(part_id, work_minutes, source.break_minutes);
+        part.auto_reset_after = source.auto_reset_after;
+        part.title = source.title.clone();
+        part.notes = source.notes.clone();
+        part.user = source.user.clone();
+        part.max_pauses = source.max_pauses;
+        part.calendar_event_id = source.calendar_event_id.clone();
+        part.pinned = source.pinned;
+        part.metadata = source.metadata.clone();
+        part.color = source.color.clone();
+        part.notify_profile = source.notify_profile.clone();
+        part.tags = source.tags.clone();
+        part.pattern = source.pattern.clone();
+        part.pattern_index = source.pattern_index;
+        (part.depends_on, part.auto_start) = match i {
+            0 => (source.depends_on, source.auto_start),
+            _ if source.auto_start => (Some(SessionId(part_id.0 - 1)), true),
+            _ => (None, false),
+        };
+        part.split_from = Some(id);
+        part.next_session = (i + 1 < req.parts).then_some(SessionId(part_id.0 + 1));
+        guard.insert_session(part);
+        created.push(to_response(&guard.sessions[&part_id]));
+    }
+    let last = SessionId(guard.next_id);
+    let dependents: Vec<SessionId> = guard
+        .sessions
+        .values()
+        .filter(|s| s.depends_on == Some(id))
+        .map(|s| s.id)
+        .collect();
+    for dependent in dependents {
+        if let Some(s) = guard.sessions.get_mut(&dependent) {
+            s.depends_on = Some(last);
+        }
+        guard.touch(dependent);
+    }
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+#[derive(Deserialize)]
+struct MergeReq {
+    from: SessionId,
+}
+
+// Moves `from`'s tracked time into this session and deletes `from`. Neither
+// may be counting down, so no time is accruing on either while they combine.
+async fn merge_session(
+    State(state): State<SharedState>,
+    IdPath(id): IdPath,
+    JsonBody(req): JsonBody<MergeReq>,
+) -> Result<Json<SessionResponse>> {
+    if req.from == id {
+        return Err(Error::Validation("a session can't be merged into itself".into()));
+    }
+    let mut guard = state.lock()?;
+    guard.check_mutable(id)?;
+    let target = &guard.sessions[&id];
+    let target_active = target.is_active();
+    let target_notes = target.notes.clone();
+    let source = guard.sessions.get(&req.from).ok_or(Error::NotFound)?;
+    if source.state == PomodoroState::Cancelled {
+        return Err(Error::Conflict("cancelled"));
+    }
+    if target_active || source.is_active() {
+        return Err(Error::Conflict("session_running"));
+    }
+    let notes = match (target_notes, &source.notes) {
+        (Some(ours), Some(theirs)) => Some(format!("{ours}\n{theirs}")),
+        (ours, theirs) => ours.or_else(|| theirs.clone()),
+    };
+    if notes.as_ref().is_some_and(|n| n.len() > MAX_NOTES_BYTES as usize) {
+        return Err(Error::Validation("merged notes would exceed 4096 bytes".into()));
+    }
+    let source = guard.remove_session(req.from).ok_or(Error::NotFound)?;
+    let s = guard.modify(id, |s| {
+        s.notes = notes;
+        s.absorb(source);
+        Ok(true)
+    })?;
+    s.update_elapsed();
+    Ok(Json(to_response(s)))
+}
+
+async fn cancel_session(
+    State(state): State<SharedState>,
+    IdPath(id): IdPath,
+) -> Result<Json<SessionResponse>> {
+    let mut guard = state.lock()?;
+    hooks::before(&guard, id, EventKind::Cancelled)?;
+    let s = guard.modify(id, |s| match s.cancel() {
+        true => Ok(true),
+        false => Err(Error::Conflict("not_cancellable")),
+    })?;
+    Ok(Json(to_response(s)))
+}
+
+#[derive(Deserialize)]
+struct AdjustReq {
+    delta_secs: i64,
+}
+
+async fn adjust_session(
+    State(state): State<SharedState>,
+    IdPath(id): IdPath,
+    JsonBody(req): JsonBody<AdjustReq>,
+) -> Result<Json<SessionResponse>> {
+    let mut guard = state.lock()?;
+    let s = guard.modify(id, |s| match s.adjust(req.delta_secs) {
+        true => Ok(true),
+        false => Err(Error::Conflict("not_adjustable")),
+    })?;
+    Ok(Json(to_response(s)))
+}
+
+async fn session_by_client_id(
+    State(state): State<SharedState>,
+    Path(client_id): Path<String>,
+) -> Result<Json<SessionResponse>> {
+    let client_id = Uuid::parse_str(&client_id).map_err(|_| Error::InvalidId)?;
+    let mut guard = state.lock()?;
+    let id = *guard.client_ids.get(&client_id).ok_or(Error::NotFound)?;
+    let s = guard.sessions.get_mut(&id).ok_or(Error::NotFound)?;
+    s.update_elapsed();
+    Ok(Json(to_response(s)))
+}
+
+// A PNG QR code of the session's URL, built from the Host the client used so
+// it resolves the same way from another device on that network.
+async fn session_qr(
+    State(state): State<SharedState>,
+    IdPath(id): IdPath,
+    headers: HeaderMap,
+) -> Result<Response> {
+    if !state.lock()?.sessions.contains_key(&id) {
+        return Err(Error::NotFound);
+    }
+    let host = headers
+        .get(header::HOST)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned)
+        .unwrap_or_else(|| format!("localhost:{PORT}"));
+    let url = format!("http://{host}/sessions/{id}");
+    let code = qrcode::QrCode::new(url.as_bytes())
+        .map_err(|_| Error::Malformed("host is too long to encode".into()))?;
+    let mut png = Vec::new();
+    code.render::<image::Luma<u8>>()
+        .build()
+        .write_to(&mut io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(io::Error::other)?;
+    Ok(([(header::CONTENT_TYPE, "image/png")], png).into_response())
+}
+
+async fn session_history(
+    State(state): State<SharedState>,
+    IdPath(id): IdPath,
+) -> Result<Json<Vec<HistoryView>>> {
+    let guard = state.lock()?;
+    let s = guard.sessions.get(&id).ok_or(Error::NotFound)?;
+    let format = guard.hub.timestamps;
+    Ok(Json(s.history.iter().map(|e| e.view(format)).collect()))
+}
+
+#[derive(Deserialize, Validate)]
+struct ActivityQuery {
+    #[serde(default = "default_activity_limit")]
+    #[validate(range(min = 1, max = 200, message = "limit must be between 1 and 200"))]
+    limit: usize,
+}
+
+fn default_activity_limit() -> usize {
+    20
+}
+
+#[derive(Serialize)]
+struct ActivityEntry {
+    id: SessionId,
+    title: Option<String>,
+    #[serde(flatten)]
+    entry: HistoryView,
+}
+
+// The latest transitions across every session, newest first. Only what the
+// histories still hold shows up, so compacted entries are gone from here too.
+async fn activity(
+    State(state): State<SharedState>,
+    QueryParams(q): QueryParams<ActivityQuery>,
+) -> Result<Json<Vec<ActivityEntry>>> {
+    q.validate().map_err(Error::BadQuery)?;
+    let guard = state.lock()?;
+    // Each history is walked newest-first so the stable sort keeps a session's
+    // same-instant entries in reverse order too.
+    let mut recent: Vec<_> = guard
+        .sessions
+        .values()
+        .flat_map(|s| s.history.iter().rev().take(q.limit).map(move |e| (s, e)))
+        .collect();
+    recent.sort_by_key(|(_, e)| std::cmp::Reverse(e.at));
+    let format = guard.hub.timestamps;
+    let feed = recent
+        .into_iter()
+        .take(q.limit)
+        .map(|(s, e)| ActivityEntry {
+            id: s.id,
+            title: s.title.clone(),
+            entry: e.view(format),
+        })
+        .collect();
+    Ok(Json(feed))
+}
+
+// "Same again, go": a fresh running session with the source's settings, so its
+// stats stay separate. `external_id` and the client id are unique and therefore
+// not copied.
+async fn continue_session(
+    State(state): State<SharedState>,
+    IdPath(id): IdPath,
+) -> Result<(StatusCode, Json<SessionResponse>)> {
+    let mut guard = state.lock()?;
+    guard.check_can_create()?;
+    let source = guard.sessions.get(&id).ok_or(Error::NotFound)?;
+    let req = CreateSessionReq {
+        work_minutes: source.work_minutes,
+        break_minutes: source.break_minutes,
+        auto_reset_after: source.auto_reset_after,
+        start_immediately: true,
+        title: source.title.clone(),
+        user: source.user.clone(),
+        calendar_event_id: source.calendar_event_id.clone(),
+        max_pauses: source.max_pauses,
+        pinned: source.pinned,
+        metadata: source.metadata.clone(),
+        color: source.color.clone(),
+        notify_profile: source.notify_profile.clone(),
+        tags: source.tags.clone(),
+        ..CreateSessionReq::default()
+    };
+    Ok((StatusCode::CREATED, Json(guard.create_session(req)?)))
+}
+
+#[derive(Deserialize)]
+struct CompareQuery {
+    a: SessionId,
+    b: SessionId,
+}
+
+#[derive(Serialize)]
+struct CompareSide {
+    id: SessionId,
+    state: PomodoroState,
+    work_minutes: u64,
+    break_minutes: u64,
+    elapsed_secs: u64,
+    focus_score: Option<u64>,
+    pauses: u64,
+    interruptions: u64,
+}
+
+impl CompareSide {
+    fn of(s: &PomodoroSession, now: Instant) -> Self {
+        Self {
+            id: s.id,
+            state: s.state,
+            work_minutes: s.work_minutes,
+            break_minutes: s.break_minutes,
+            elapsed_secs: s.work_elapsed_at(now).as_secs(),
+            focus_score: s.focus_score,
+            pauses: s.pauses,
+            interruptions: s.interruptions,
+        }
+    }
+}
+
+// Each field is `b - a`; `focus_score` is null unless both sessions have one.
+#[derive(Serialize)]
+struct CompareDiff {
+    work_minutes: i64,
+    break_minutes: i64,
+    elapsed_secs: i64,
+    focus_score: Option<i64>,
+    pauses: i64,
+    interruptions: i64,
+}
+
+#[derive(Serialize)]
+struct Comparison {
+    a: CompareSide,
+    b: CompareSide,
+    diff: CompareDiff,
+}
+
+fn signed_diff(a: u64, b: u64) -> i64 {
+    b as i64 - a as i64
+}
+
+// Read-only: elapsed time is projected rather than accumulated into the sessions.
+async fn compare_sessions(
+    State(state): State<SharedState>,
+    QueryParams(q): QueryParams<CompareQuery>,
+) -> Result<Json<Comparison>> {
+    let guard = state.lock()?;
+    let now = clock::instant();
+    let a = CompareSide::of(guard.sessions.get(&q.a).ok_or(Error::NotFound)?, now);
+    let b = CompareSide::of(guard.sessions.get(&q.b).ok_or(Error::NotFound)?, now);
+    let diff = CompareDiff {
+        work_minutes: signed_diff(a.work_minutes, b.work_minutes),
+        break_minutes: signed_diff(a.break_minutes, b.break_minutes),
+        elapsed_secs: signed_diff(a.elapsed_secs, b.elapsed_secs),
+        focus_score: a.focus_score.zip(b.focus_score).map(|(a, b)| signed_diff(a, b)),
+        pauses: signed_diff(a.pauses, b.pauses),
+        interruptions: signed_diff(a.interruptions, b.interruptions),
+    };
+    Ok(Json(Comparison { a, b, diff }))
+}
+
+#[derive(Serialize)]
+struct ProjectedCompletion {
+    id: SessionId,
+    state: PomodoroState,
+    projected_completion: Option<Stamp>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Action {
+    Start,
+    Wait,
+    KeepWorking,
+    TakeBreak,
+    Resume,
+    StartNext,
+    None,
+}
+
+#[derive(Serialize)]
+struct Suggestion {
+    action: Action,
+}
+
+// What a guided client should prompt next. Read-only: work time is projected
+// to now rather than accumulated, so a Running session whose work ran out
+// since the last tick already gets `take_break`.
+//
+//   Idle, prerequisite unfinished      -> wait
+//   Idle                               -> start
+//   Running, work time left            -> keep_working
+//   Running with work done, or Break   -> take_break
+//   Paused, Interrupted                -> resume
+//   Finished without a break           -> take_break
+//   Finished after its break           -> start_next
+//   Cancelled                          -> none
+async fn suggest_action(
+    State(state): State<SharedState>,
+    IdPath(id): IdPath,
+) -> Result<Json<Suggestion>> {
+    let guard = state.lock()?;
+    let s = guard.sessions.get(&id).ok_or(Error::NotFound)?;
+    let action = match s.state {
+        PomodoroState::Idle => {
+            let dep = s.depends_on.and_then(|dep| guard.sessions.get(&dep));
+            if dep.is_some_and(|dep| dep.state != PomodoroState::Finished) {
+                Action::Wait
+            } else {
+                Action::Start
+            }
+        }
+        PomodoroState::Running if s.work_elapsed_at(clock::instant()) < s.total_work() => {
+            Action::KeepWorking
+        }
+        PomodoroState::Running | PomodoroState::Break => Action::TakeBreak,
+        PomodoroState::Paused | PomodoroState::Interrupted => Action::Resume,
+        PomodoroState::Finished if s.break_minutes == 0 => Action::TakeBreak,
+        PomodoroState::Finished => Action::StartNext,
+        PomodoroState::Cancelled => Action::None,
+    };
+    Ok(Json(Suggestion { action }))
+}
+
+// Wall-clock time the session will reach Finished (work plus break) if it keeps
+// running. Paused/Idle sessions have no projection since the wait is open-ended.
+async fn projected_completion(
+    State(state): State<SharedState>,
+    IdPath(id): IdPath,
+) -> Result<Json<ProjectedCompletion>> {
+    let guard = state.lock()?;
+    let s = guard.sessions.get(&id).ok_or(Error::NotFound)?;
+    let clock = Clock::now();
+    let projected_completion = match s.state {
+        PomodoroState::Finished => s.finished_at,
+        _ if s.is_active() => clock.project(s.remaining_total_at(clock.now_instant())),
+        _ => None,
+    };
+    let format = guard.hub.timestamps;
+    Ok(Json(ProjectedCompletion {
+        id,
+        state: s.state,
+        projected_completion: projected_completion.map(|at| Stamp::new(at, format)),
+    }))
+}
+
+async fn update_session(
+    State(state): State<SharedState>,
+    IdPath(id): IdPath,
+    JsonBody(req): JsonBody<UpdateSessionReq>,
+) -> Result<Json<SessionResponse>> {
+    req.validate()?;
+    let mut guard = state.lock()?;
+    if let Some(Some(dep)) = req.depends_on {
+        guard.check_dependency(id, dep)?;
+    }
+    let mut relinked = None;
+    let s = guard.modify(id, |s| {
+        let metadata = match req.metadata {
+            Some(patch) => {
+                let mut merged = s.metadata.clone();
+                for (key, value) in patch {
+                    match value {
+                        Some(value) => merged.insert(key, value),
+                        None => merged.remove(&key),
+                    };
+                }
+                validate_metadata(&merged).map_err(|err| {
+                    let mut errors = ValidationErrors::new();
+                    errors.add("metadata", err);
+                    errors
+                })?;
+                Some(merged)
+            }
+            None => None,
+        };
+        if let Some(work_minutes) = req.work_minutes {
+            s.set_work_minutes(work_minutes);
+        }
+        if let Some(title) = req.title {
+            s.title = title;
+        }
+        if let Some(notes) = req.notes {
+            s.notes = notes;
+        }
+        if let Some(user) = req.user {
+            s.user = user;
+        }
+        relinked = req
+            .calendar_event_id
+            .map(|event| (std::mem::replace(&mut s.calendar_event_id, event.clone()), event));
+        if let Some(pinned) = req.pinned {
+            s.pinned = pinned;
+        }
+        if let Some(metadata) = metadata {
+            s.metadata = metadata;
+        }
+        if let Some(color) = req.color {
+            s.color = color;
+        }
+        if let Some(profile) = req.notify_profile {
+            s.notify_profile = profile;
+        }
+        if let Some(tags) = req.tags {
+            s.tags = normalize_tags(tags);
+        }
+        if let Some(depends_on) = req.depends_on {
+            s.depends_on = depends_on;
+        }
+        if let Some(auto_start) = req.auto_start {
+            s.auto_start = auto_start;
+        }
+        Ok(true)
+    })?;
+    s.update_elapsed();
+    let response = to_response(s);
+    if let Some((old, new)) = relinked {
+        if let Some(old) = old {
+            guard.unlink_calendar_event(&old, id);
+        }
+        if let Some(new) = new {
+            guard.link_calendar_event(new, id);
+        }
+    }
+    Ok(Json(response))
+}
+
+#[derive(Deserialize)]
+struct TagsReq {
+    tags: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct TagReq {
+    tag: String,
+}
+
+// All three tag routes land here: `edit` gets the current tags and returns the
+// new set, which is normalized and checked before anything is stored.
+fn edit_tags(
+    state: &SharedState,
+    id: SessionId,
+    edit: impl FnOnce(Vec<String>) -> Vec<String>,
+) -> Result<Json<SessionResponse>> {
+    let mut guard = state.lock()?;
+    let s = guard.modify(id, |s| {
+        let tags = normalize_tags(edit(s.tags.clone()));
+        if tags.len() > MAX_TAGS as usize {
+            return Err(Error::Validation("at most 32 tags are allowed".into()));
+        }
+        let changed = tags != s.tags;
+        s.tags = tags;
+        Ok(changed)
+    })?;
+    s.update_elapsed();
+    Ok(Json(to_response(s)))
+}
+
+fn tag_name(tag: &str) -> Result<String> {
+    match tag.trim() {
+        "" => Err(Error::Validation("tag must not be blank".into())),
+        tag => Ok(tag.to_string()),
+    }
+}
+
+// Unlike `tags` in PATCH this says outright that the whole set is replaced.
+async fn replace_tags(
+    State(state): State<SharedState>,
+    IdPath(id): IdPath,
+    JsonBody(req): JsonBody<TagsReq>,
+) -> Result<Json<SessionResponse>> {
+    edit_tags(&state, id, |_| req.tags)
+}
+
+async fn add_tag(
+    State(state): State<SharedState>,
+    IdPath(id): IdPath,
+    JsonBody(req): JsonBody<TagReq>,
+) -> Result<Json<SessionResponse>> {
+    let tag = tag_name(&req.tag)?;
+    edit_tags(&state, id, |mut tags| {
+        tags.push(tag);
+        tags
+    })
+}
+
+// Removing a tag the session doesn't have is not an error.
+async fn remove_tag(
+    State(state): State<SharedState>,
+    IdPath(id): IdPath,
+    JsonBody(req): JsonBody<TagReq>,
+) -> Result<Json<SessionResponse>> {
+    let tag = tag_name(&req.tag)?;
+    edit_tags(&state, id, |mut tags| {
+        tags.retain(|t| *t != tag);
+        tags
+    })
+}
+
+#[derive(Deserialize)]
+struct PauseByTagReq {
+    tag: String,
+}
+
+#[derive(Serialize)]
+struct PauseByTag {
+    paused: usize,
+}
+
+// Only Running sessions are paused; breaks carry on. Each goes through the
+// same checks as a pause of its own, but one a hook vetoes, or one out of
+// pauses, keeps running rather than failing the batch.
+async fn pause_by_tag(
+    State(state): State<SharedState>,
+    JsonBody(req): JsonBody<PauseByTagReq>,
+) -> Result<Json<PauseByTag>> {
+    let tag = tag_name(&req.tag)?;
+    let mut guard = state.lock()?;
+    // Maintenance froze every timer, so there'd be nothing to pause, but the
+    // request is refused like any single pause would be.
+    guard.check_maintenance()?;
+    let running: Vec<SessionId> = guard
+        .sessions
+        .values_mut()
+        .filter_map(|s| {
+            s.update_elapsed();
+            (s.state == PomodoroState::Running && s.tags.contains(&tag)).then_some(s.id)
+        })
+        .collect();
+    let mut paused = 0;
+    for id in running {
+        if hooks::before(&guard, id, EventKind::Paused).is_err() {
+            continue;
+        }
+        match guard.modify(id, |s| {
+            s.check_pause_limit()?;
+            Ok(s.pause())
+        }) {
+            Ok(_) => paused += 1,
+            Err(Error::Conflict(_)) => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(Json(PauseByTag { paused }))
+}
+
+#[derive(Deserialize)]
+struct ControlQuery {
+    action_id: Option<String>,
+}
+
+async fn start_session(
+    State(state): State<SharedState>,
+    IdPath(id): IdPath,
+    QueryParams(q): QueryParams<ControlQuery>,
+) -> Result<Json<SessionResponse>> {
+    let mut guard = state.lock()?;
+    // Hooks judge by the state, which may have just moved on with time.
+    if let Some(s) = guard.sessions.get_mut(&id) {
+        s.update_elapsed();
+        // A replay answers with the state as it is, before anything can refuse it.
+        if q.action_id.as_ref().is_some_and(|a| s.recent_actions.contains(a)) {
+            return Ok(Json(to_response(s)));
+        }
+    }
+    if guard.is_blocked(id) {
+        return Err(Error::Conflict("blocked"));
+    }
+    hooks::before(&guard, id, EventKind::Started)?;
+    let s = guard.modify(id, |s| {
+        s.update_elapsed();
+        if !s.first_seen(q.action_id) {
+            return Ok(false);
+        }
+        if !s.start() {
+            return Err(Error::Conflict("already_started"));
+        }
+        Ok(true)
+    })?;
+    s.update_elapsed();
+    Ok(Json(to_response(s)))
+}
+
+async fn pause_session(
+    State(state): State<SharedState>,
+    IdPath(id): IdPath,
+    QueryParams(q): QueryParams<ControlQuery>,
+) -> Result<Json<SessionResponse>> {
+    let mut guard = state.lock()?;
+    hooks::before(&guard, id, EventKind::Paused)?;
+    let s = guard.modify(id, |s| {
+        s.check_pause_limit()?;
+        Ok(s.first_seen(q.action_id) && s.pause())
+    })?;
+    s.update_elapsed();
+    Ok(Json(to_response(s)))
+}
+
+async fn interrupt_session(
+    State(state): State<SharedState>,
+    IdPath(id): IdPath,
+    QueryParams(q): QueryParams<ControlQuery>,
+) -> Result<Json<SessionResponse>> {
+    let mut guard = state.lock()?;
+    hooks::before(&guard, id, EventKind::Interrupted)?;
+    let s = guard.modify(id, |s| {
+        Ok(s.first_seen(q.action_id) && s.interrupt())
+    })?;
+    s.update_elapsed();
+    Ok(Json(to_response(s)))
+}
+
+async fn resume_session(
+    State(state): State<SharedState>,
+    IdPath(id): IdPath,
+    QueryParams(q): QueryParams<ControlQuery>,
+) -> Result<Json<SessionResponse>> {
+    let mut guard = state.lock()?;
+    hooks::before(&guard, id, EventKind::Resumed)?;
+    let s = guard.modify(id, |s| {
+        Ok(s.first_seen(q.action_id) && s.resume())
+    })?;
+    s.update_elapsed();
+    Ok(Json(to_response(s)))
+}
+
+// Start or resume depending on the current state, so clients needn't check first.
+async fn play_session(
+    State(state): State<SharedState>,
+    IdPath(id): IdPath,
+    QueryParams(q): QueryParams<ControlQuery>,
+) -> Result<Json<SessionResponse>> {
+    let mut guard = state.lock()?;
+    if let Some(s) = guard.sessions.get_mut(&id) {
+        s.update_elapsed();
+    }
+    let startable = guard
+        .sessions
+        .get(&id)
+        .is_some_and(|s| matches!(s.state, PomodoroState::Idle | PomodoroState::Finished));
+    if startable && guard.is_blocked(id) {
+        return Err(Error::Conflict("blocked"));
+    }
+    let event = if startable {
+        EventKind::Started
+    } else {
+        EventKind::Resumed
+    };
+    hooks::before(&guard, id, event)?;
+    let s = guard.modify(id, |s| {
+        s.update_elapsed();
+        if !s.first_seen(q.action_id) {
+            return Ok(false);
+        }
+        match s.state {
+            PomodoroState::Idle | PomodoroState::Finished => Ok(s.start()),
+            PomodoroState::Paused | PomodoroState::Interrupted => Ok(s.resume()),
+            PomodoroState::Running | PomodoroState::Break => {
+                Err(Error::Conflict("already_running"))
+            }
+            PomodoroState::Cancelled => Err(Error::Conflict("cancelled")),
+        }
+    })?;
+    s.update_elapsed();
+    Ok(Json(to_response(s)))
 }
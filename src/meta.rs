@@ -0,0 +1,68 @@
+use axum::Json;
+use serde::Serialize;
+
+use crate::{Phase, PomodoroState, NOTIFY_PROFILES};
+
+#[derive(Serialize)]
+struct Described<T> {
+    value: T,
+    description: &'static str,
+}
+
+#[derive(Serialize)]
+pub struct Meta {
+    states: Vec<Described<PomodoroState>>,
+    phases: Vec<Described<Phase>>,
+    notify_profiles: &'static [&'static str],
+}
+
+const STATES: [PomodoroState; 7] = [
+    PomodoroState::Idle,
+    PomodoroState::Running,
+    PomodoroState::Break,
+    PomodoroState::Paused,
+    PomodoroState::Interrupted,
+    PomodoroState::Finished,
+    PomodoroState::Cancelled,
+];
+
+// The match is exhaustive on purpose: a new state won't compile until it has a
+// description here, which is the reminder to add it to STATES too.
+fn describe_state(state: PomodoroState) -> &'static str {
+    match state {
+        PomodoroState::Idle => "Created or reset, not started",
+        PomodoroState::Running => "Counting down work time",
+        PomodoroState::Break => "Work done, counting down the break",
+        PomodoroState::Paused => "Stopped by the user; resume continues where it left off",
+        PomodoroState::Interrupted => "Stopped by an interruption; resume continues",
+        PomodoroState::Finished => "Work and break both done",
+        PomodoroState::Cancelled => "Abandoned early; kept for the record and read-only",
+    }
+}
+
+fn describe_phase(phase: Phase) -> &'static str {
+    match phase {
+        Phase::Work => "The work part of a round",
+        Phase::Break => "The break that follows it",
+    }
+}
+
+pub async fn meta() -> Json<Meta> {
+    Json(Meta {
+        states: STATES
+            .into_iter()
+            .map(|value| Described {
+                value,
+                description: describe_state(value),
+            })
+            .collect(),
+        phases: [Phase::Work, Phase::Break]
+            .into_iter()
+            .map(|value| Described {
+                value,
+                description: describe_phase(value),
+            })
+            .collect(),
+        notify_profiles: &NOTIFY_PROFILES,
+    })
+}
@@ -0,0 +1,84 @@
+use std::{
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use axum::{extract::State, http::header, response::IntoResponse};
+
+use crate::hub::Hub;
+
+const BUCKETS: [u64; 10] = [60, 300, 600, 900, 1500, 1800, 2700, 3600, 5400, 7200];
+
+pub struct Histogram {
+    name: &'static str,
+    help: &'static str,
+    buckets: [AtomicU64; BUCKETS.len()],
+    count: AtomicU64,
+    sum: AtomicU64,
+}
+
+impl Histogram {
+    const fn new(name: &'static str, help: &'static str) -> Self {
+        Self {
+            name,
+            help,
+            buckets: [const { AtomicU64::new(0) }; BUCKETS.len()],
+            count: AtomicU64::new(0),
+            sum: AtomicU64::new(0),
+        }
+    }
+
+    pub fn observe(&self, secs: u64) {
+        if let Some(i) = BUCKETS.iter().position(|&le| secs <= le) {
+            self.buckets[i].fetch_add(1, Ordering::Relaxed);
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum.fetch_add(secs, Ordering::Relaxed);
+    }
+
+    fn render(&self, out: &mut String) {
+        let name = self.name;
+        let _ = writeln!(out, "# HELP {name} {}", self.help);
+        let _ = writeln!(out, "# TYPE {name} histogram");
+        let mut cumulative = 0;
+        for (le, bucket) in BUCKETS.iter().zip(&self.buckets) {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let _ = writeln!(out, "{name}_bucket{{le=\"{le}\"}} {cumulative}");
+        }
+        let count = self.count.load(Ordering::Relaxed);
+        let _ = writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {count}");
+        let _ = writeln!(out, "{name}_sum {}", self.sum.load(Ordering::Relaxed));
+        let _ = writeln!(out, "{name}_count {count}");
+    }
+}
+
+pub struct Metrics {
+    pub session_duration: Histogram,
+    // Fed by transitions that end a started session before it finishes.
+    pub early_stop_duration: Histogram,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            session_duration: Histogram::new(
+                "pomodoro_session_duration_seconds",
+                "Work seconds elapsed when a session reached Finished.",
+            ),
+            early_stop_duration: Histogram::new(
+                "pomodoro_session_early_stop_seconds",
+                "Work seconds elapsed when a session was stopped before finishing.",
+            ),
+        }
+    }
+}
+
+pub async fn metrics(State(hub): State<Arc<Hub>>) -> impl IntoResponse {
+    let mut out = String::new();
+    hub.metrics.session_duration.render(&mut out);
+    hub.metrics.early_stop_duration.render(&mut out);
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], out)
+}
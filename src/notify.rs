@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use crate::{
+    config::{Config, NotifierKind},
+    events::EventKind,
+    webhook::{Deliveries, Webhook},
+    SessionResponse,
+};
+
+#[derive(Clone)]
+pub struct SessionEvent {
+    pub kind: EventKind,
+    pub session: SessionResponse,
+}
+
+// A destination for session events. Called from the ticker outside the state
+// lock; implementations that do I/O should hand it off rather than block.
+pub trait Notifier: Send + Sync {
+    fn notify(&self, event: SessionEvent);
+}
+
+// The body is the session itself, as it always has been for the webhook.
+impl Notifier for Webhook {
+    fn notify(&self, event: SessionEvent) {
+        if let Ok(body) = serde_json::to_vec(&event.session) {
+            self.spawn(body);
+        }
+    }
+}
+
+pub struct LogNotifier;
+
+impl Notifier for LogNotifier {
+    fn notify(&self, event: SessionEvent) {
+        tracing::info!(
+            target: "notify",
+            session_id = event.session.id.0,
+            kind = ?event.kind,
+            elapsed_secs = event.session.elapsed_secs,
+            "session event"
+        );
+    }
+}
+
+pub struct NoopNotifier;
+
+impl Notifier for NoopNotifier {
+    fn notify(&self, _: SessionEvent) {}
+}
+
+pub type Notifiers = Arc<[Arc<dyn Notifier>]>;
+
+// A webhook sink without a configured URL is skipped.
+pub fn from_config(config: &Config, deliveries: &Deliveries) -> Notifiers {
+    config
+        .notifiers
+        .iter()
+        .filter_map(|kind| -> Option<Arc<dyn Notifier>> {
+            match kind {
+                NotifierKind::Webhook => Some(Webhook::from_config(config, deliveries)?),
+                NotifierKind::Log => Some(Arc::new(LogNotifier)),
+                NotifierKind::Noop => Some(Arc::new(NoopNotifier)),
+            }
+        })
+        .collect()
+}
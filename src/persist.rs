@@ -0,0 +1,132 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::Utc;
+
+use crate::{clock, config::StateFormat, error::Result, id::SessionId, AppState, SharedState};
+
+// Binary files start with MAGIC and a little-endian u16 version. Bump the
+// version whenever AppState's serialized layout changes: postcard is positional,
+// so an old file would otherwise decode into the wrong fields.
+const MAGIC: &[u8; 4] = b"POMO";
+const BINARY_VERSION: u16 = 16;
+
+pub fn load(path: &Path) -> Result<Option<AppState>> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    let mut state = match decode(&bytes) {
+        Ok(state) => state,
+        Err(err) => {
+            set_aside(path, &err)?;
+            return Ok(None);
+        }
+    };
+    // Instants don't survive a restart; active sessions resume counting from load.
+    let now = clock::instant();
+    for s in state.sessions.values_mut() {
+        if s.is_active() {
+            s.started_at = Some(now);
+        }
+    }
+    state.reindex();
+    // A hand-edited or older file could carry sessions past its counter.
+    if let Some(&SessionId(max)) = state.sessions.keys().next_back() {
+        state.next_id = state.next_id.max(max);
+    }
+    Ok(Some(state))
+}
+
+// An unreadable file is moved out of the way rather than refusing to start;
+// the next flush writes a fresh one in its place and the old bytes stay on
+// disk for whoever wants to dig through them.
+fn set_aside(path: &Path, err: &io::Error) -> io::Result<()> {
+    let mut name = path.file_name().unwrap_or_default().to_owned();
+    name.push(format!(".{}.bak", Utc::now().format("%Y%m%dT%H%M%SZ")));
+    let backup = path.with_file_name(name);
+    fs::rename(path, &backup)?;
+    tracing::warn!(
+        path = %path.display(),
+        backup = %backup.display(),
+        error = %err,
+        "unreadable state file moved aside; starting empty"
+    );
+    Ok(())
+}
+
+fn decode(bytes: &[u8]) -> io::Result<AppState> {
+    let Some(rest) = bytes.strip_prefix(MAGIC) else {
+        return Ok(serde_json::from_slice(bytes)?);
+    };
+    let (version, body) = rest.split_at_checked(2).ok_or(io::ErrorKind::UnexpectedEof)?;
+    let version = u16::from_le_bytes([version[0], version[1]]);
+    if version != BINARY_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("state file format version {version}, expected {BINARY_VERSION}"),
+        ));
+    }
+    postcard::from_bytes(body).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+fn encode(state: &AppState, format: StateFormat) -> io::Result<Vec<u8>> {
+    match format {
+        StateFormat::Json => Ok(serde_json::to_vec(state)?),
+        StateFormat::Binary => {
+            let body = postcard::to_stdvec(state)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            let mut bytes = MAGIC.to_vec();
+            bytes.extend_from_slice(&BINARY_VERSION.to_le_bytes());
+            bytes.extend_from_slice(&body);
+            Ok(bytes)
+        }
+    }
+}
+
+// Serializes under the lock but writes outside it, via a temp file and rename
+// so a crash mid-write never leaves a truncated state file behind. `dirty` is
+// cleared with the snapshot taken, so changes made during the write mark it
+// again; a write that fails marks it too, and the next flush retries.
+pub fn flush(state: &SharedState, path: &Path, format: StateFormat) -> Result<()> {
+    let bytes = {
+        let mut guard = state.lock()?;
+        if !guard.dirty {
+            return Ok(());
+        }
+        let bytes = encode(&guard, format)?;
+        guard.dirty = false;
+        bytes
+    };
+    let written = write(path, &bytes);
+    if written.is_err() {
+        state.lock()?.dirty = true;
+    }
+    Ok(written?)
+}
+
+fn write(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, bytes)?;
+    fs::rename(&tmp, path)
+}
+
+// Coalesces any number of mutations into at most one save per `interval`.
+pub async fn run_flusher(
+    state: SharedState,
+    path: PathBuf,
+    interval: Duration,
+    format: StateFormat,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        if let Err(err) = flush(&state, &path, format) {
+            tracing::error!(path = %path.display(), error = %err, "failed to save state");
+        }
+    }
+}
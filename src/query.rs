@@ -0,0 +1,183 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use validator::{ValidationError, ValidationErrors};
+
+use crate::{
+    error::{Error, Result},
+    timestamp, PomodoroSession, PomodoroState, MAX_LIMIT,
+};
+
+// The raw `GET /sessions` parameters. Everything comes in as text so that one
+// bad value can't mask the rest behind a bare deserialize failure; `parse`
+// checks them all and answers a single 400 naming every parameter it rejected.
+#[derive(Deserialize, Default)]
+pub struct SessionQuery {
+    format: Option<String>,
+    changed_since: Option<String>,
+    q: Option<String>,
+    state: Option<String>,
+    tag: Option<String>,
+    calendar_event_id: Option<String>,
+    created_after: Option<String>,
+    created_before: Option<String>,
+    offset: Option<String>,
+    limit: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Csv,
+}
+
+// A checked `SessionQuery`. `needle` is already lowercased.
+pub struct Filter {
+    pub format: Option<Format>,
+    pub changed_since: Option<u64>,
+    needle: Option<String>,
+    state: Option<PomodoroState>,
+    tag: Option<String>,
+    calendar_event_id: Option<String>,
+    created_after: Option<DateTime<Utc>>,
+    created_before: Option<DateTime<Utc>>,
+    pub offset: usize,
+    pub limit: usize,
+    // Whether `offset` or `limit` was given rather than defaulted.
+    paged: bool,
+}
+
+fn check<T>(
+    errors: &mut ValidationErrors,
+    field: &'static str,
+    raw: Option<&str>,
+    parse: impl FnOnce(&str) -> Option<T>,
+    message: &'static str,
+) -> Option<T> {
+    let raw = raw?;
+    let parsed = parse(raw);
+    if parsed.is_none() {
+        errors.add(field, ValidationError::new(field).with_message(message.into()));
+    }
+    parsed
+}
+
+impl SessionQuery {
+    pub fn parse(self) -> Result<Filter> {
+        let mut errors = ValidationErrors::new();
+        let format = check(
+            &mut errors,
+            "format",
+            self.format.as_deref(),
+            |v| match v.to_ascii_lowercase().as_str() {
+                "json" => Some(Format::Json),
+                "csv" => Some(Format::Csv),
+                _ => None,
+            },
+            "format must be json or csv",
+        );
+        let changed_since = check(
+            &mut errors,
+            "changed_since",
+            self.changed_since.as_deref(),
+            |v| v.parse().ok(),
+            "changed_since must be a version number",
+        );
+        let state = check(
+            &mut errors,
+            "state",
+            self.state.as_deref(),
+            |v| serde_json::from_value(v.into()).ok(),
+            "state must be one of Idle, Running, Break, Paused, Interrupted, Finished, Cancelled",
+        );
+        let created_after = check(
+            &mut errors,
+            "created_after",
+            self.created_after.as_deref(),
+            timestamp::parse,
+            "created_after must be an RFC 3339 timestamp or epoch milliseconds",
+        );
+        let created_before = check(
+            &mut errors,
+            "created_before",
+            self.created_before.as_deref(),
+            timestamp::parse,
+            "created_before must be an RFC 3339 timestamp or epoch milliseconds",
+        );
+        if let (Some(after), Some(before)) = (created_after, created_before)
+            && after > before
+        {
+            errors.add(
+                "created_before",
+                ValidationError::new("range")
+                    .with_message("created_before must not be earlier than created_after".into()),
+            );
+        }
+        let offset = check(
+            &mut errors,
+            "offset",
+            self.offset.as_deref(),
+            |v| v.parse().ok(),
+            "offset must be a non-negative integer",
+        );
+        let limit = check(
+            &mut errors,
+            "limit",
+            self.limit.as_deref(),
+            |v| v.parse().ok(),
+            "limit must be a non-negative integer",
+        );
+        if !errors.is_empty() {
+            return Err(Error::BadQuery(errors));
+        }
+        Ok(Filter {
+            format,
+            changed_since,
+            needle: self.q.map(|q| q.to_lowercase()),
+            state,
+            tag: self.tag,
+            calendar_event_id: self.calendar_event_id,
+            created_after,
+            created_before,
+            paged: offset.is_some() || limit.is_some(),
+            offset: offset.unwrap_or(0),
+            limit: limit.unwrap_or(crate::default_limit()).min(MAX_LIMIT),
+        })
+    }
+}
+
+impl Filter {
+    pub fn calendar_event_id(&self) -> Option<&str> {
+        self.calendar_event_id.as_deref()
+    }
+
+    // Any of these, paging included, switches a plain listing to a filtered `Page`.
+    pub fn is_search(&self) -> bool {
+        self.paged
+            || self.needle.is_some()
+            || self.state.is_some()
+            || self.tag.is_some()
+            || self.calendar_event_id.is_some()
+            || self.created_after.is_some()
+            || self.created_before.is_some()
+    }
+
+    // `q` is a case-insensitive substring of the title or the notes; the
+    // creation range is inclusive at both ends.
+    pub fn matches(&self, s: &PomodoroSession) -> bool {
+        let text_matches = self.needle.as_deref().is_none_or(|needle| {
+            [&s.title, &s.notes]
+                .into_iter()
+                .flatten()
+                .any(|text| text.to_lowercase().contains(needle))
+        });
+        text_matches
+            && self.state.is_none_or(|state| s.state == state)
+            && self.tag.as_ref().is_none_or(|tag| s.tags.contains(tag))
+            && self
+                .calendar_event_id
+                .as_ref()
+                .is_none_or(|event| s.calendar_event_id.as_ref() == Some(event))
+            && self.created_after.is_none_or(|after| s.created_at >= after)
+            && self.created_before.is_none_or(|before| s.created_at <= before)
+    }
+}
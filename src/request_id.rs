@@ -0,0 +1,34 @@
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+
+static REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+// Reuses the caller's `X-Request-Id` when it is a valid header value, otherwise
+// mints a UUID. The id is set on the request (for handlers), the tracing span
+// and the response.
+pub async fn propagate(mut req: Request, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .filter(|v| !v.is_empty())
+        .cloned()
+        .unwrap_or_else(|| {
+            HeaderValue::from_str(&uuid::Uuid::new_v4().to_string())
+                .expect("a UUID is a valid header value")
+        });
+    req.headers_mut().insert(&REQUEST_ID_HEADER, id.clone());
+    let span = tracing::info_span!(
+        "request",
+        request_id = id.to_str().unwrap_or_default(),
+        method = %req.method(),
+        path = req.uri().path(),
+    );
+    let mut res = next.run(req).instrument(span).await;
+    res.headers_mut().insert(&REQUEST_ID_HEADER, id);
+    res
+}
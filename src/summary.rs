@@ -0,0 +1,100 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::{DateTime, Local, NaiveDate, NaiveTime};
+use serde::Serialize;
+
+use crate::{
+    clock, error::Result, webhook::Webhook, AppState, PomodoroSession, PomodoroState, SharedState,
+};
+
+#[derive(Serialize)]
+struct DaySummary {
+    date: NaiveDate,
+    sessions_completed: usize,
+    focus_minutes: u64,
+}
+
+// Counts sessions whose latest finish falls on `day` in local time.
+fn summarize(state: &AppState, day: NaiveDate) -> DaySummary {
+    let finished: Vec<_> = state
+        .sessions
+        .values()
+        .filter(|s| s.state == PomodoroState::Finished)
+        .filter(|s| s.finished_at.is_some_and(|at| at.with_timezone(&Local).date_naive() == day))
+        .collect();
+    DaySummary {
+        date: day,
+        sessions_completed: finished.len(),
+        focus_minutes: finished.iter().map(|s| s.elapsed.as_secs()).sum::<u64>() / 60,
+    }
+}
+
+// Next wall-clock occurrence of `at` strictly after `now`. Days where `at`
+// doesn't exist (a DST gap) are skipped.
+fn next_run(now: DateTime<Local>, at: NaiveTime) -> DateTime<Local> {
+    let mut day = now.date_naive();
+    loop {
+        if let Some(run) = day.and_time(at).and_local_timezone(Local).earliest()
+            && run > now
+        {
+            return run;
+        }
+        day = day.succ_opt().unwrap_or(day);
+    }
+}
+
+// Pushes the day's summary to `webhook` each day at `at`, local time.
+pub struct SummaryPush {
+    state: SharedState,
+    webhook: Arc<Webhook>,
+    at: NaiveTime,
+    skip_empty: bool,
+    next: DateTime<Local>,
+}
+
+impl SummaryPush {
+    pub fn new(state: SharedState, webhook: Arc<Webhook>, at: NaiveTime, skip_empty: bool) -> Self {
+        Self {
+            state,
+            webhook,
+            at,
+            skip_empty,
+            next: next_run(clock::local(), at),
+        }
+    }
+
+    fn wait(&self) -> Duration {
+        (self.next - clock::local()).to_std().unwrap_or_default()
+    }
+
+    // Sends the summary for the run that's due, if any. Only fails once the
+    // state lock is poisoned.
+    pub fn push_due(&mut self) -> Result<()> {
+        if clock::local() < self.next {
+            return Ok(());
+        }
+        let run = self.next;
+        self.next = next_run(run, self.at);
+        let summary = {
+            let mut guard = self.state.lock()?;
+            guard.sessions.values_mut().for_each(PomodoroSession::update_elapsed);
+            summarize(&guard, run.date_naive())
+        };
+        if self.skip_empty && summary.sessions_completed == 0 {
+            return Ok(());
+        }
+        if let Ok(body) = serde_json::to_vec(&summary) {
+            self.webhook.spawn(body);
+        }
+        Ok(())
+    }
+}
+
+pub async fn run_summary(mut push: SummaryPush) {
+    loop {
+        tokio::time::sleep(push.wait()).await;
+        if push.push_due().is_err() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::Serialize;
+
+use crate::hub::Hub;
+
+#[cfg(not(test))]
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+// Restarts happen in real time, which tests would rather not wait out.
+#[cfg(test)]
+const MIN_BACKOFF: Duration = Duration::from_millis(10);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+// A task that stayed up this long is considered healthy again, so its next
+// failure restarts it at the shortest backoff.
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Default, Serialize)]
+struct TaskHealth {
+    running: bool,
+    restarts: u64,
+    last_error: Option<String>,
+}
+
+// The supervised tasks by name, for `/healthz`.
+#[derive(Clone, Default)]
+pub struct Tasks(Arc<Mutex<BTreeMap<&'static str, TaskHealth>>>);
+
+impl Tasks {
+    fn update(&self, name: &'static str, f: impl FnOnce(&mut TaskHealth)) {
+        if let Ok(mut tasks) = self.0.lock() {
+            f(tasks.entry(name).or_default());
+        }
+    }
+
+    fn snapshot(&self) -> BTreeMap<&'static str, TaskHealth> {
+        self.0.lock().map(|t| t.clone()).unwrap_or_default()
+    }
+}
+
+// Runs a background loop that is never meant to end, restarting it with
+// exponential backoff whenever it panics or returns, and reports each restart
+// in `tasks`. Restarting is all it does: a panic that poisoned the state lock
+// leaves it poisoned, and the restarted loop stops again at its first lock.
+pub fn spawn<F, Fut>(tasks: Tasks, name: &'static str, task: F)
+where
+    F: Fn() -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut backoff = MIN_BACKOFF;
+        loop {
+            tasks.update(name, |h| h.running = true);
+            let started = Instant::now();
+            let error = match tokio::spawn(task()).await {
+                Ok(()) => "task exited".to_string(),
+                Err(err) if err.is_panic() => panic_message(err.into_panic()),
+                // Only happens when the runtime is shutting down.
+                Err(_) => return,
+            };
+            if started.elapsed() >= STABLE_AFTER {
+                backoff = MIN_BACKOFF;
+            }
+            tracing::error!(task = name, error, ?backoff, "background task stopped; restarting");
+            tasks.update(name, |h| {
+                h.running = false;
+                h.restarts += 1;
+                h.last_error = Some(error);
+            });
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    });
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(msg) => *msg,
+        Err(payload) => payload
+            .downcast_ref::<&str>()
+            .map_or_else(|| "panic".to_string(), |msg| msg.to_string()),
+    }
+}
+
+#[derive(Serialize)]
+struct Health {
+    status: &'static str,
+    tasks: BTreeMap<&'static str, TaskHealth>,
+}
+
+// 503 while any supervised task is down and waiting to be restarted.
+pub async fn healthz(State(hub): State<Arc<Hub>>) -> impl IntoResponse {
+    let tasks = hub.tasks.snapshot();
+    let healthy = tasks.values().all(|t| t.running);
+    let (code, status) = if healthy {
+        (StatusCode::OK, "ok")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "degraded")
+    };
+    (code, Json(Health { status, tasks }))
+}
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::{
+    clock,
+    error::{Error, Result},
+    extract::{IdPath, JsonBody},
+    AppContext, CreateSessionReq, Round, SessionResponse, SharedState,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateReq {
+    name: String,
+    // Optional alongside a `pattern`, which sets the work length itself.
+    #[serde(default)]
+    work_minutes: u64,
+    #[serde(default)]
+    break_minutes: u64,
+    auto_reset_after: Option<u64>,
+    #[serde(default)]
+    pinned: bool,
+    #[serde(default)]
+    metadata: HashMap<String, String>,
+    color: Option<String>,
+    // Cycled per round by sessions made from this template; see `CreateSessionReq`.
+    #[serde(default)]
+    pattern: Vec<Round>,
+}
+
+impl TemplateReq {
+    // A template has to pass the same checks as the create it stands in for,
+    // or an out-of-range length would only surface once it's instantiated.
+    fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(Error::Validation("name must not be empty".into()));
+        }
+        self.session_req()?;
+        Ok(())
+    }
+
+    fn session_req(&self) -> Result<CreateSessionReq> {
+        let mut req = CreateSessionReq {
+            work_minutes: self.work_minutes,
+            break_minutes: self.break_minutes,
+            auto_reset_after: self.auto_reset_after,
+            pinned: self.pinned,
+            metadata: self.metadata.clone(),
+            color: self.color.clone(),
+            pattern: self.pattern.clone(),
+            ..CreateSessionReq::default()
+        };
+        req.validate()?;
+        req.resolve_end_at(clock::utc())?;
+        Ok(req)
+    }
+}
+
+// State stores the bare `TemplateReq` keyed by id: `flatten` would keep it out
+// of non-self-describing formats like the binary state file.
+#[derive(Serialize)]
+struct Template {
+    id: u64,
+    #[serde(flatten)]
+    config: TemplateReq,
+}
+
+pub fn router() -> Router<AppContext> {
+    Router::new()
+        .route("/templates", post(create_template).get(list_templates))
+        .route(
+            "/templates/:id",
+            get(get_template).put(update_template).delete(delete_template),
+        )
+        .route("/templates/:id/instantiate", post(instantiate))
+}
+
+async fn create_template(
+    State(state): State<SharedState>,
+    JsonBody(req): JsonBody<TemplateReq>,
+) -> Result<(StatusCode, Json<Template>)> {
+    req.validate()?;
+    let mut guard = state.lock()?;
+    guard.next_template_id += 1;
+    let id = guard.next_template_id;
+    guard.templates.insert(id, req.clone());
+    guard.dirty = true;
+    Ok((StatusCode::CREATED, Json(Template { id, config: req })))
+}
+
+async fn list_templates(State(state): State<SharedState>) -> Result<Json<Vec<Template>>> {
+    let guard = state.lock()?;
+    let mut templates: Vec<Template> = guard
+        .templates
+        .iter()
+        .map(|(&id, config)| Template {
+            id,
+            config: config.clone(),
+        })
+        .collect();
+    templates.sort_by_key(|t| t.id);
+    Ok(Json(templates))
+}
+
+async fn get_template(
+    State(state): State<SharedState>,
+    IdPath(id): IdPath<u64>,
+) -> Result<Json<Template>> {
+    let guard = state.lock()?;
+    let config = guard.templates.get(&id).ok_or(Error::NotFound)?.clone();
+    Ok(Json(Template { id, config }))
+}
+
+async fn update_template(
+    State(state): State<SharedState>,
+    IdPath(id): IdPath<u64>,
+    JsonBody(req): JsonBody<TemplateReq>,
+) -> Result<Json<Template>> {
+    req.validate()?;
+    let mut guard = state.lock()?;
+    let config = guard.templates.get_mut(&id).ok_or(Error::NotFound)?;
+    *config = req.clone();
+    guard.dirty = true;
+    Ok(Json(Template { id, config: req }))
+}
+
+async fn delete_template(
+    State(state): State<SharedState>,
+    IdPath(id): IdPath<u64>,
+) -> Result<StatusCode> {
+    let mut guard = state.lock()?;
+    guard.templates.remove(&id).ok_or(Error::NotFound)?;
+    guard.dirty = true;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn instantiate(
+    State(state): State<SharedState>,
+    IdPath(id): IdPath<u64>,
+) -> Result<(StatusCode, Json<SessionResponse>)> {
+    let mut guard = state.lock()?;
+    let config = guard.templates.get(&id).ok_or(Error::NotFound)?.clone();
+    guard.check_can_create()?;
+    let response = guard.create_session(config.session_req()?)?;
+    Ok((StatusCode::CREATED, Json(response)))
+}
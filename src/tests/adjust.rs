@@ -0,0 +1,47 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use super::{advance, TestApp};
+
+async fn adjust(app: &TestApp, id: u64, delta_secs: i64) -> Value {
+    let body = json!({ "delta_secs": delta_secs });
+    let res = app.post_json(&format!("/sessions/{id}/adjust"), body).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    res.json()
+}
+
+#[tokio::test]
+async fn adding_time_can_finish_a_running_session() {
+    let app = TestApp::new();
+    let id = app.create_minutes(10).await;
+    app.act(id, "start").await;
+    advance(60);
+    let s = adjust(&app, id, 9 * 60).await;
+    assert_eq!((&s["state"], &s["elapsed_secs"]), (&json!("Finished"), &json!(600)));
+    let history = app.get(&format!("/sessions/{id}/history")).await.json();
+    let adjusted = history.as_array().unwrap().iter().find(|e| e["event"] == "adjusted");
+    let adjusted = adjusted.unwrap();
+    assert_eq!((&adjusted["from"], &adjusted["delta_secs"]), (&json!("Running"), &json!(540)));
+    // Finished is final; there is nothing left to correct.
+    let res = app.post_json(&format!("/sessions/{id}/adjust"), json!({ "delta_secs": -60 })).await;
+    assert_eq!((res.status, res.error()), (StatusCode::CONFLICT, "not_adjustable".into()));
+}
+
+#[tokio::test]
+async fn adjustments_are_clamped_to_the_work_phase() {
+    let app = TestApp::new();
+    let id = app.create_minutes(10).await;
+    app.act(id, "start").await;
+    advance(9 * 60);
+    app.act(id, "pause").await;
+    let s = adjust(&app, id, -300).await;
+    assert_eq!((&s["state"], &s["remaining_secs"]), (&json!("Paused"), &json!(360)));
+    assert_eq!(adjust(&app, id, -100_000).await["elapsed_secs"], 0);
+    // Paused past the end, it finishes as soon as it resumes.
+    let s = adjust(&app, id, 100_000).await;
+    assert_eq!((&s["state"], &s["elapsed_secs"]), (&json!("Paused"), &json!(600)));
+    assert_eq!(app.act(id, "resume").await["state"], "Finished");
+    let idle = app.create_minutes(10).await;
+    let res = app.post_json(&format!("/sessions/{idle}/adjust"), json!({ "delta_secs": 60 })).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+}
@@ -0,0 +1,64 @@
+use axum::http::StatusCode;
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+
+use super::{advance, id, TestApp};
+use crate::clock;
+
+fn stamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+// Four one-minute sessions finishing an hour apart, the first at `base + 1m`.
+async fn finish_hourly(app: &TestApp) -> (DateTime<Utc>, Vec<u64>) {
+    let base = clock::utc();
+    let mut ids = Vec::new();
+    for _ in 0..4 {
+        let id = app.create_minutes(1).await;
+        app.act(id, "start").await;
+        advance(3600);
+        ids.push(id);
+    }
+    (base, ids)
+}
+
+fn ids(page: &serde_json::Value) -> Vec<u64> {
+    page["items"].as_array().unwrap().iter().map(id).collect()
+}
+
+#[tokio::test]
+async fn completed_in_a_sub_range() {
+    let app = TestApp::new();
+    let (base, all) = finish_hourly(&app).await;
+    let (from, to) = (base + TimeDelta::hours(1), base + TimeDelta::hours(3));
+    let res = app.get(&format!("/sessions/completed?from={}&to={}", stamp(from), stamp(to))).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    let page = res.json();
+    assert_eq!(ids(&page), all[1..3]);
+    assert_eq!(page["total"], 2);
+}
+
+#[tokio::test]
+async fn completed_is_sorted_and_paged() {
+    let app = TestApp::new();
+    let (_, all) = finish_hourly(&app).await;
+    // A session still running is never listed.
+    let running = app.create_minutes(25).await;
+    app.act(running, "start").await;
+    let page = app.get("/sessions/completed?offset=1&limit=2").await.json();
+    assert_eq!(ids(&page), all[1..3]);
+    assert_eq!(page["total"], 4);
+}
+
+#[tokio::test]
+async fn completed_rejects_an_inverted_range() {
+    let app = TestApp::new();
+    let now = clock::utc();
+    let path = format!(
+        "/sessions/completed?from={}&to={}",
+        stamp(now),
+        stamp(now - TimeDelta::days(1))
+    );
+    let res = app.get(&path).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(res.error(), "invalid_query");
+}
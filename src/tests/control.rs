@@ -0,0 +1,385 @@
+use std::sync::Arc;
+
+use axum::http::StatusCode;
+use chrono::{DateTime, TimeDelta};
+use serde_json::{json, Value};
+use tokio::sync::Barrier;
+
+use super::{advance, id, TestApp};
+use crate::{clock, config::FinishedStart, RECENT_ACTIONS_CAP};
+
+#[tokio::test]
+async fn paused_time_is_not_counted() {
+    let app = TestApp::new();
+    let id = app.create_minutes(25).await;
+    app.act(id, "start").await;
+    advance(60);
+    assert_eq!(app.act(id, "pause").await["elapsed_secs"], 60);
+    advance(300);
+    let s = app.session(id).await;
+    assert_eq!(s["state"], "Paused");
+    assert_eq!(s["elapsed_secs"], 60);
+}
+
+#[tokio::test]
+async fn counting_restarts_at_resume() {
+    let app = TestApp::new();
+    let id = app.create_minutes(25).await;
+    app.act(id, "start").await;
+    advance(10);
+    app.act(id, "pause").await;
+    advance(120);
+    // Polled straight after the resume, nothing of the pause has leaked in.
+    assert_eq!(app.act(id, "resume").await["elapsed_secs"], 10);
+    assert_eq!(app.session(id).await["elapsed_secs"], 10);
+    advance(30);
+    assert_eq!(app.session(id).await["elapsed_secs"], 40);
+}
+
+#[tokio::test]
+async fn repeated_pauses_each_stop_the_clock() {
+    let app = TestApp::new();
+    let id = app.create_minutes(25).await;
+    app.act(id, "start").await;
+    for _ in 0..3 {
+        advance(20);
+        app.act(id, "pause").await;
+        advance(1_000);
+        app.act(id, "resume").await;
+    }
+    assert_eq!(app.session(id).await["elapsed_secs"], 60);
+}
+
+#[tokio::test]
+async fn interruptions_are_counted_apart_from_pauses() {
+    let app = TestApp::new();
+    let id = app.create_minutes(25).await;
+    app.act(id, "start").await;
+    advance(30);
+    assert_eq!(app.act(id, "interrupt").await["state"], "Interrupted");
+    advance(60);
+    let s = app.act(id, "resume").await;
+    assert_eq!(s["state"], "Running");
+    assert_eq!(s["elapsed_secs"], 30);
+    assert_eq!(s["interruptions"], 1);
+    assert_eq!(s["pauses"], 0);
+    app.act(id, "pause").await;
+    let s = app.session(id).await;
+    assert_eq!((s["pauses"].as_u64(), s["interruptions"].as_u64()), (Some(1), Some(1)));
+}
+
+#[tokio::test]
+async fn paused_for_secs_counts_the_current_pause() {
+    let app = TestApp::new();
+    let id = app.create_minutes(25).await;
+    assert_eq!(app.session(id).await["paused_for_secs"], Value::Null);
+    app.act(id, "start").await;
+    advance(60);
+    assert_eq!(app.act(id, "pause").await["paused_for_secs"], 0);
+    advance(12 * 60);
+    assert_eq!(app.session(id).await["paused_for_secs"], 12 * 60);
+    assert_eq!(app.act(id, "resume").await["paused_for_secs"], Value::Null);
+    advance(30);
+    app.act(id, "interrupt").await;
+    advance(45);
+    // A new pause counts from its own start.
+    assert_eq!(app.session(id).await["paused_for_secs"], 45);
+}
+
+#[tokio::test]
+async fn current_is_the_most_recently_started() {
+    let app = TestApp::new();
+    assert_eq!(app.get("/sessions/current").await.status, StatusCode::NO_CONTENT);
+    let (first, second) = (app.create_minutes(25).await, app.create_minutes(25).await);
+    app.act(second, "start").await;
+    advance(5);
+    app.act(first, "start").await;
+    assert_eq!(id(&app.get("/sessions/current").await.json()), first);
+    // Pausing it falls back to the other one still running.
+    app.act(first, "pause").await;
+    assert_eq!(id(&app.get("/sessions/current").await.json()), second);
+    app.act(second, "pause").await;
+    assert_eq!(app.get("/sessions/current").await.status, StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn a_replayed_start_is_a_no_op() {
+    let app = TestApp::new();
+    let id = app.create_minutes(25).await;
+    app.act(id, "start?action_id=a").await;
+    advance(5);
+    assert_eq!(app.act(id, "start?action_id=a").await["elapsed_secs"], 5);
+    // Only the replay is let off; a start without the id is a conflict as usual.
+    let res = app.post(&format!("/sessions/{id}/start")).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn a_start_replayed_after_the_finish_keeps_it_finished() {
+    let app = TestApp::new();
+    let id = id(&app.create(json!({ "work_minutes": 1, "break_minutes": 0 })).await);
+    app.act(id, "start?action_id=a").await;
+    advance(60);
+    let s = app.act(id, "start?action_id=a").await;
+    assert_eq!((s["state"].as_str(), s["elapsed_secs"].as_u64()), (Some("Finished"), Some(60)));
+}
+
+#[tokio::test]
+async fn only_recent_action_ids_are_remembered() {
+    let app = TestApp::new();
+    let id = app.create_minutes(25).await;
+    app.act(id, "start?action_id=first").await;
+    for n in 0..RECENT_ACTIONS_CAP {
+        app.act(id, &format!("pause?action_id=p{n}")).await;
+    }
+    // `first` has been pushed out, so this is a real start again, and refused.
+    let res = app.post(&format!("/sessions/{id}/start?action_id=first")).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn projected_completion_shifts_by_the_pause() {
+    let app = TestApp::new();
+    let id = id(&app.create(json!({ "work_minutes": 10, "break_minutes": 0 })).await);
+    let projected = || async {
+        let res = app.get(&format!("/sessions/{id}/projected-completion")).await.json();
+        res["projected_completion"].as_str().map(|at| DateTime::parse_from_rfc3339(at).unwrap())
+    };
+    assert_eq!(projected().await, None);
+    let start = clock::utc();
+    app.act(id, "start").await;
+    assert_eq!(projected().await, Some((start + TimeDelta::minutes(10)).into()));
+    advance(120);
+    app.act(id, "pause").await;
+    // Indefinite while paused.
+    assert_eq!(projected().await, None);
+    advance(300);
+    app.act(id, "resume").await;
+    let shifted = start + TimeDelta::minutes(10) + TimeDelta::seconds(300);
+    assert_eq!(projected().await, Some(shifted.into()));
+}
+
+#[tokio::test]
+async fn play_starts_or_resumes() {
+    let app = TestApp::new();
+    let id = app.create_minutes(25).await;
+    assert_eq!(app.act(id, "play").await["state"], "Running");
+    advance(30);
+    let res = app.post(&format!("/sessions/{id}/play")).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    app.act(id, "pause").await;
+    advance(60);
+    let s = app.act(id, "play").await;
+    assert_eq!((s["state"].as_str(), s["elapsed_secs"].as_u64()), (Some("Running"), Some(30)));
+    assert_eq!(s["pauses"], 1);
+    app.act(id, "interrupt").await;
+    assert_eq!(app.act(id, "play").await["state"], "Running");
+}
+
+#[tokio::test]
+async fn pause_by_tag_pauses_only_matching_running_sessions() {
+    let app = TestApp::new();
+    let tagged = |tags: &[&str]| json!({ "work_minutes": 25, "tags": tags });
+    let study = id(&app.create(tagged(&["study", "math"])).await);
+    let study_too = id(&app.create(tagged(&["study"])).await);
+    let chores = id(&app.create(tagged(&["chores"])).await);
+    let study_idle = id(&app.create(tagged(&["study"])).await);
+    for id in [study, study_too, chores] {
+        app.act(id, "start").await;
+    }
+    let res = app.post_json("/sessions/actions/pause-by-tag", json!({ "tag": "study" })).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    assert_eq!(res.json()["paused"], 2);
+    for (id, state) in [(study, "Paused"), (study_too, "Paused"), (chores, "Running")] {
+        assert_eq!(app.session(id).await["state"], state, "session {id}");
+    }
+    assert_eq!(app.session(study_idle).await["state"], "Idle");
+    // Each pause is an ordinary one: a new version and a history entry.
+    let started = app.session(chores).await["version"].as_u64().unwrap();
+    let s = app.session(study).await;
+    assert!(s["version"].as_u64().unwrap() > started);
+    let history = app.get(&format!("/sessions/{study}/history")).await.json();
+    assert_eq!(history.as_array().unwrap().last().unwrap()["event"], "paused");
+}
+
+#[tokio::test]
+async fn pause_by_tag_checks_like_a_single_pause() {
+    let app = TestApp::new();
+    let id = id(&app.create(json!({ "work_minutes": 25, "tags": ["study"] })).await);
+    app.act(id, "start").await;
+    let pause = |tag: &'static str| {
+        app.post_json("/sessions/actions/pause-by-tag", json!({ "tag": tag }))
+    };
+    let res = pause("  ").await;
+    assert_eq!((res.status, res.error()), (StatusCode::UNPROCESSABLE_ENTITY, "validation".into()));
+    app.post_json("/admin/maintenance", json!({ "enabled": true })).await;
+    let res = pause("study").await;
+    assert_eq!((res.status, res.error()), (StatusCode::SERVICE_UNAVAILABLE, "maintenance".into()));
+    app.post_json("/admin/maintenance", json!({ "enabled": false })).await;
+    // Surrounding space is trimmed, as it is when tagging.
+    assert_eq!(pause(" study ").await.json()["paused"], 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn of_two_racing_starts_exactly_one_wins() {
+    let app = Arc::new(TestApp::new());
+    for _ in 0..32 {
+        let id = app.create_minutes(25).await;
+        let barrier = Arc::new(Barrier::new(2));
+        let starts = [(); 2].map(|()| {
+            let (app, barrier) = (app.clone(), barrier.clone());
+            tokio::spawn(async move {
+                barrier.wait().await;
+                app.post(&format!("/sessions/{id}/start")).await
+            })
+        });
+        let mut statuses = Vec::new();
+        for start in starts {
+            let res = start.await.unwrap();
+            if res.status == StatusCode::CONFLICT {
+                assert_eq!(res.error(), "already_started");
+            }
+            statuses.push(res.status);
+        }
+        statuses.sort();
+        assert_eq!(statuses, [StatusCode::OK, StatusCode::CONFLICT], "session {id}");
+    }
+}
+
+#[tokio::test]
+async fn a_cancelled_session_is_kept_but_read_only() {
+    let app = TestApp::new();
+    let id = app.create_minutes(25).await;
+    app.act(id, "start").await;
+    advance(90);
+    let s = app.act(id, "cancel").await;
+    assert_eq!((&s["state"], &s["elapsed_secs"]), (&json!("Cancelled"), &json!(90)));
+    advance(600);
+    assert_eq!(app.session(id).await["elapsed_secs"], 90);
+    assert_eq!(app.get("/stats").await.json()["cancelled"], 1);
+    for action in ["start", "pause", "resume", "play", "cancel"] {
+        let res = app.post(&format!("/sessions/{id}/{action}")).await;
+        assert_eq!(res.status, StatusCode::CONFLICT, "{action}: {}", res.text());
+    }
+    let res = app.patch_json(&format!("/sessions/{id}"), json!({ "title": "again" })).await;
+    assert_eq!((res.status, res.error()), (StatusCode::CONFLICT, "cancelled".into()));
+    assert_eq!(app.session(id).await["title"], Value::Null);
+    // Never-started sessions have nothing to cancel.
+    let idle = app.create_minutes(25).await;
+    let res = app.post(&format!("/sessions/{idle}/cancel")).await;
+    assert_eq!((res.status, res.error()), (StatusCode::CONFLICT, "not_cancellable".into()));
+}
+
+#[tokio::test]
+async fn work_waits_for_the_break_when_breaks_are_enforced() {
+    let mut app = TestApp::with(|c| c.enforce_breaks = true);
+    let resting = id(&app.create(json!({ "work_minutes": 1, "break_minutes": 5 })).await);
+    app.act(resting, "start").await;
+    advance(60);
+    app.tick();
+    let next = app.create_minutes(25).await;
+    for action in ["start", "play"] {
+        let res = app.post(&format!("/sessions/{next}/{action}")).await;
+        assert_eq!(res.status, StatusCode::CONFLICT);
+        assert_eq!(res.json(), json!({ "error": "break_not_finished", "remaining_secs": 300 }));
+    }
+    advance(120);
+    let res = app.post(&format!("/sessions/{next}/start")).await;
+    assert_eq!(res.json()["remaining_secs"], 180);
+
+    advance(180);
+    app.tick();
+    assert_eq!(app.act(next, "start").await["state"], "Running");
+}
+
+#[tokio::test]
+async fn breaks_are_not_enforced_by_default() {
+    let mut app = TestApp::new();
+    let resting = id(&app.create(json!({ "work_minutes": 1, "break_minutes": 5 })).await);
+    app.act(resting, "start").await;
+    advance(60);
+    app.tick();
+    let next = app.create_minutes(25).await;
+    assert_eq!(app.act(next, "start").await["state"], "Running");
+}
+
+#[tokio::test]
+async fn starting_a_finished_session_is_refused_by_default() {
+    let app = TestApp::new();
+    let id = app.create_minutes(1).await;
+    app.act(id, "start").await;
+    advance(60);
+    let finished = app.session(id).await;
+    let res = app.post(&format!("/sessions/{id}/start")).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.error(), "already_finished");
+    assert_eq!(app.session(id).await, finished);
+}
+
+#[tokio::test]
+async fn a_finished_session_can_be_configured_to_restart() {
+    let app = TestApp::with(|c| c.finished_start = FinishedStart::Restart);
+    let id = app.create_minutes(1).await;
+    app.act(id, "start").await;
+    advance(60);
+    assert_eq!(app.session(id).await["state"], "Finished");
+    let restarted = app.act(id, "start").await;
+    assert_eq!((&restarted["state"], &restarted["elapsed_secs"]), (&json!("Running"), &json!(0)));
+}
+
+#[tokio::test]
+async fn pauses_past_the_limit_are_refused_until_the_next_round() {
+    let app = TestApp::with(|c| c.finished_start = FinishedStart::Restart);
+    let id = id(&app.create(json!({ "work_minutes": 1, "max_pauses": 2 })).await);
+    app.act(id, "start").await;
+    for _ in 0..2 {
+        app.act(id, "pause").await;
+        app.act(id, "resume").await;
+    }
+    let res = app.post(&format!("/sessions/{id}/pause")).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.json(), json!({ "error": "pause_limit_reached" }));
+    let s = app.session(id).await;
+    assert_eq!((&s["state"], &s["pauses"]), (&json!("Running"), &json!(2)));
+
+    // Starting over begins a fresh allowance.
+    advance(60);
+    assert_eq!(app.session(id).await["state"], "Finished");
+    app.act(id, "start").await;
+    assert_eq!(app.act(id, "pause").await["state"], "Paused");
+    // Without a limit, pausing is never refused.
+    let free = app.create_minutes(25).await;
+    app.act(free, "start").await;
+    for _ in 0..5 {
+        app.act(free, "pause").await;
+        app.act(free, "resume").await;
+    }
+}
+
+#[tokio::test]
+async fn single_tasking_allows_one_running_session() {
+    let app = TestApp::with(|c| c.max_concurrent_running = Some(1));
+    let first = app.create_minutes(25).await;
+    let second = app.create_minutes(25).await;
+    app.act(first, "start").await;
+    for action in ["start", "play"] {
+        let res = app.post(&format!("/sessions/{second}/{action}")).await;
+        assert_eq!(res.status, StatusCode::CONFLICT);
+        assert_eq!(res.json(), json!({ "error": "too_many_running" }));
+    }
+    // Pausing frees the slot, and then the first can't resume.
+    app.act(first, "pause").await;
+    app.act(second, "start").await;
+    let res = app.post(&format!("/sessions/{first}/resume")).await;
+    assert_eq!((res.status, res.error()), (StatusCode::CONFLICT, "too_many_running".into()));
+}
+
+#[tokio::test]
+async fn running_sessions_are_unlimited_by_default() {
+    let app = TestApp::new();
+    for _ in 0..10 {
+        let id = app.create_minutes(25).await;
+        app.act(id, "start").await;
+    }
+    assert_eq!(app.get("/stats/remaining").await.json()["active_sessions"], 10);
+}
@@ -0,0 +1,23 @@
+use super::TestApp;
+use crate::id::SessionId;
+
+#[tokio::test]
+async fn counters_saturate_at_the_top() {
+    let app = TestApp::new();
+    let id = app.create_minutes(25).await;
+    app.act(id, "start").await;
+    {
+        let mut state = app.ctx.state.lock().unwrap();
+        state.version = u64::MAX - 1;
+        let s = state.sessions.get_mut(&SessionId(id)).unwrap();
+        s.pauses = u64::MAX;
+        s.interruptions = u64::MAX;
+    }
+    let s = app.act(id, "pause").await;
+    assert_eq!((s["pauses"].as_u64(), s["version"].as_u64()), (Some(u64::MAX), Some(u64::MAX)));
+    app.act(id, "resume").await;
+    let s = app.act(id, "interrupt").await;
+    assert_eq!(s["interruptions"].as_u64(), Some(u64::MAX));
+    assert_eq!(s["version"].as_u64(), Some(u64::MAX));
+    assert_eq!(app.ctx.state.lock().unwrap().version, u64::MAX);
+}
@@ -0,0 +1,52 @@
+use axum::http::StatusCode;
+use serde_json::json;
+
+use super::{advance, id, TestApp};
+
+#[tokio::test]
+async fn a_dependent_session_is_blocked_until_its_prerequisite_finishes() {
+    let app = TestApp::new();
+    let first = app.create_minutes(1).await;
+    let second = id(&app.create(json!({ "work_minutes": 5, "depends_on": first })).await);
+    let res = app.post(&format!("/sessions/{second}/start")).await;
+    assert_eq!((res.status, res.error()), (StatusCode::CONFLICT, "blocked".into()));
+    let body = json!({ "work_minutes": 5, "depends_on": first, "start_immediately": true });
+    let res = app.post_json("/sessions", body).await;
+    assert_eq!((res.status, res.error()), (StatusCode::CONFLICT, "blocked".into()));
+    app.act(first, "start").await;
+    advance(60);
+    assert_eq!(app.act(second, "start").await["state"], "Running");
+}
+
+#[tokio::test]
+async fn auto_start_follows_the_prerequisite() {
+    let mut app = TestApp::new();
+    let first = app.create_minutes(1).await;
+    let body = json!({ "work_minutes": 5, "depends_on": first, "auto_start": true });
+    let second = id(&app.create(body).await);
+    app.act(first, "start").await;
+    advance(59);
+    app.tick();
+    assert_eq!(app.session(second).await["state"], "Idle");
+    advance(1);
+    app.tick();
+    let s = app.session(second).await;
+    assert_eq!((&s["state"], &s["elapsed_secs"]), (&json!("Running"), &json!(0)));
+}
+
+#[tokio::test]
+async fn dependency_cycles_and_unknown_prerequisites_are_rejected() {
+    let app = TestApp::new();
+    let a = app.create_minutes(5).await;
+    let b = id(&app.create(json!({ "work_minutes": 5, "depends_on": a })).await);
+    let c = id(&app.create(json!({ "work_minutes": 5, "depends_on": b })).await);
+    for dep in [c, a] {
+        let res = app.patch_json(&format!("/sessions/{a}"), json!({ "depends_on": dep })).await;
+        assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", res.text());
+        assert_eq!(res.json()["message"], "depends_on would create a cycle");
+    }
+    let res = app.post_json("/sessions", json!({ "work_minutes": 5, "depends_on": 9999 })).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(res.json()["message"], "depends_on: no session 9999");
+    assert_eq!(app.session(a).await["depends_on"], serde_json::Value::Null);
+}
@@ -0,0 +1,39 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use serde_json::json;
+
+use super::{id, Reply, TestApp};
+
+async fn get_with(app: &TestApp, path: &str, envelope: &str) -> Reply {
+    let req = Request::get(path).header("x-envelope", envelope).body(Body::empty());
+    app.send(req.unwrap()).await
+}
+
+#[tokio::test]
+async fn bare_by_default() {
+    let app = TestApp::new();
+    let id = app.create_minutes(25).await;
+    assert_eq!(app.session(id).await["id"], id);
+    let data = get_with(&app, &format!("/sessions/{id}"), "data").await.json();
+    assert_eq!(data["data"]["id"], id);
+    let res = app.get("/sessions/99").await;
+    assert_eq!((res.status, res.json()), (StatusCode::NOT_FOUND, json!({ "error": "not_found" })));
+}
+
+#[tokio::test]
+async fn enveloped_when_configured() {
+    let app = TestApp::with(|config| config.envelope = true);
+    let res = app.post_json("/sessions", json!({ "work_minutes": 25 })).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+    let id = id(&res.json()["data"]);
+    let res = app.get(&format!("/sessions/{id}")).await;
+    assert_eq!(res.json()["data"]["work_minutes"], 25);
+    assert_eq!(app.get("/sessions").await.json()["data"][0]["id"], id);
+    let bare = get_with(&app, &format!("/sessions/{id}"), "bare").await.json();
+    assert_eq!(bare["id"], id);
+    // Errors keep their own shape.
+    let res = app.get("/sessions/99").await;
+    assert_eq!(res.json(), json!({ "error": "not_found" }));
+}
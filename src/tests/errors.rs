@@ -0,0 +1,129 @@
+use std::io;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    response::IntoResponse,
+};
+use serde_json::{json, Value};
+
+use super::TestApp;
+use crate::error::Error;
+
+async fn render(err: Error) -> (StatusCode, Value) {
+    let res = err.into_response();
+    let status = res.status();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn each_variant_maps_to_its_status_and_code() {
+    let cases = [
+        (Error::Unauthorized, StatusCode::UNAUTHORIZED, "unauthorized"),
+        (Error::InvalidId, StatusCode::BAD_REQUEST, "invalid_id"),
+        (Error::NotFound, StatusCode::NOT_FOUND, "not_found"),
+        (Error::Conflict("duplicate_id"), StatusCode::CONFLICT, "duplicate_id"),
+        (Error::Locked("focus_lock"), StatusCode::LOCKED, "focus_lock"),
+        (Error::Unavailable("maintenance"), StatusCode::SERVICE_UNAVAILABLE, "maintenance"),
+        (Error::Io(io::Error::other("disk")), StatusCode::INTERNAL_SERVER_ERROR, "io"),
+        (Error::Internal, StatusCode::INTERNAL_SERVER_ERROR, "internal"),
+    ];
+    for (err, status, code) in cases {
+        assert_eq!(render(err).await, (status, json!({ "error": code })));
+    }
+}
+
+#[tokio::test]
+async fn variants_with_details_carry_them_in_the_body() {
+    assert_eq!(
+        render(Error::Malformed("expected value".into())).await,
+        (
+            StatusCode::BAD_REQUEST,
+            json!({ "error": "malformed", "message": "expected value" })
+        )
+    );
+    assert_eq!(
+        render(Error::Validation("too long".into())).await,
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            json!({ "error": "validation", "message": "too long" })
+        )
+    );
+    assert_eq!(
+        render(Error::BreakNotFinished(42)).await,
+        (
+            StatusCode::CONFLICT,
+            json!({ "error": "break_not_finished", "remaining_secs": 42 })
+        )
+    );
+    assert_eq!(
+        render(Error::bad_query("days", "must be positive".into())).await,
+        (
+            StatusCode::BAD_REQUEST,
+            json!({ "error": "invalid_query", "fields": { "days": ["must be positive"] } })
+        )
+    );
+}
+
+#[tokio::test]
+async fn handlers_answer_with_the_mapped_errors() {
+    let app = TestApp::new();
+    let res = app.get("/sessions/999").await;
+    assert_eq!((res.status, res.error()), (StatusCode::NOT_FOUND, "not_found".into()));
+    let res = app.get("/sessions/abc").await;
+    assert_eq!((res.status, res.error()), (StatusCode::BAD_REQUEST, "invalid_id".into()));
+    let res = app.post_json("/sessions", json!({ "work_minutes": 2000 })).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(res.json()["fields"]["work_minutes"].is_array(), "{}", res.text());
+    let res = app.post_json("/sessions", json!({})).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(res.json()["message"], "work_minutes, pattern or end_at is required");
+}
+
+#[tokio::test]
+async fn unreadable_bodies_are_400_and_invalid_ones_422() {
+    let app = TestApp::new();
+    let raw = |body: &str, content_type: &str| {
+        Request::post("/sessions")
+            .header(header::CONTENT_TYPE, content_type)
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let unreadable = [
+        raw("{\"work_minutes\": 25", "application/json"),
+        raw("{\"work_minutes\": \"soon\"}", "application/json"),
+        raw("[25]", "application/json"),
+        raw("{\"work_minutes\": 25}", "text/plain"),
+    ];
+    for req in unreadable {
+        let res = app.send(req).await;
+        assert_eq!((res.status, res.error()), (StatusCode::BAD_REQUEST, "malformed".into()));
+        assert!(res.json()["message"].is_string());
+    }
+    let invalid = raw("{\"work_minutes\": 25, \"color\": \"red\"}", "application/json");
+    let res = app.send(invalid).await;
+    assert_eq!((res.status, res.error()), (StatusCode::UNPROCESSABLE_ENTITY, "validation".into()));
+    // Queries the same way round: unparseable and out of range are both 400.
+    for path in ["/sessions?limit=lots", "/activity?limit=0"] {
+        let res = app.get(path).await;
+        assert_eq!(res.status, StatusCode::BAD_REQUEST, "{path}: {}", res.text());
+        assert_eq!(res.error(), "invalid_query");
+    }
+    assert!(app.ctx.state.lock().unwrap().sessions.is_empty());
+}
+
+#[tokio::test]
+async fn poisoned_state_is_a_500_not_a_panic() {
+    let app = TestApp::new();
+    let state = app.ctx.state.clone();
+    std::thread::spawn(move || {
+        let _guard = state.lock().unwrap();
+        panic!("poison the state lock");
+    })
+    .join()
+    .unwrap_err();
+    let res = app.get("/sessions").await;
+    assert_eq!(res.status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(res.error(), "internal");
+}
@@ -0,0 +1,170 @@
+use std::time::Duration;
+
+use axum::{
+    body::{Body, BodyDataStream},
+    http::{Request, StatusCode},
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use super::{advance, TestApp};
+use crate::config::Nudges;
+
+// The data of the next event on an open stream.
+async fn next_data(stream: &mut BodyDataStream) -> Value {
+    let mut text = String::new();
+    while !text.contains("\n\n") {
+        let chunk = stream.next().await.unwrap().unwrap();
+        text.push_str(std::str::from_utf8(&chunk).unwrap());
+    }
+    let data = text.lines().find_map(|l| l.strip_prefix("data:")).unwrap();
+    serde_json::from_str(data.trim()).unwrap()
+}
+
+fn events(res: Response) -> BodyDataStream {
+    assert_eq!(res.status(), StatusCode::OK);
+    res.into_body().into_data_stream()
+}
+
+#[tokio::test]
+async fn session_streams_are_capped_until_one_closes() {
+    let app = TestApp::with(|config| config.max_subscribers_per_session = 2);
+    let id = app.create_minutes(25).await;
+    let path = format!("/sessions/{id}/events");
+    let first = app.open(&path).await;
+    let second = app.open(&path).await;
+    assert_eq!((first.status(), second.status()), (StatusCode::OK, StatusCode::OK));
+    let refused = app.get(&path).await;
+    assert_eq!(refused.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(refused.error(), "too_many_subscribers");
+    // The cap is per session.
+    let other = app.create_minutes(25).await;
+    assert_eq!(app.open(&format!("/sessions/{other}/events")).await.status(), StatusCode::OK);
+    drop(first);
+    assert_eq!(app.open(&path).await.status(), StatusCode::OK);
+    drop(second);
+}
+
+#[tokio::test]
+async fn progress_mode_sends_compact_events() {
+    let app = TestApp::new();
+    let id = app.create_minutes(10).await;
+    app.act(id, "start").await;
+    advance(150);
+    let mut stream = events(app.open(&format!("/sessions/{id}/events?mode=progress")).await);
+    assert_eq!(next_data(&mut stream).await, json!({ "percent": 25, "phase": "work" }));
+    let mut full = events(app.open(&format!("/sessions/{id}/events")).await);
+    let event = next_data(&mut full).await;
+    assert_eq!((event["id"].as_u64(), event["elapsed_secs"].as_u64()), (Some(id), Some(150)));
+}
+
+#[tokio::test]
+async fn the_server_feed_carries_every_sessions_events_by_type() {
+    let app = TestApp::new();
+    let mut started = events(app.open("/events?types=started,finished").await);
+    let first = app.create_minutes(1).await;
+    let second = app.create_minutes(25).await;
+    app.act(first, "start").await;
+    app.act(second, "start").await;
+    let event = next_data(&mut started).await;
+    assert_eq!((event["session_id"].as_u64(), &event["type"]), (Some(first), &json!("started")));
+    let event = next_data(&mut started).await;
+    assert_eq!((event["session_id"].as_u64(), &event["type"]), (Some(second), &json!("started")));
+    advance(60);
+    app.session(first).await;
+    let event = next_data(&mut started).await;
+    assert_eq!((event["session_id"].as_u64(), &event["type"]), (Some(first), &json!("finished")));
+}
+
+#[tokio::test]
+async fn a_reconnecting_client_replays_what_it_missed() {
+    let app = TestApp::new();
+    let id = app.create_minutes(25).await;
+    app.act(id, "start").await;
+    app.act(id, "pause").await;
+    // Having seen the first event (created), it gets the rest.
+    let req = Request::get("/events").header("last-event-id", "1").body(Body::empty());
+    let mut stream = events(app.router.clone().oneshot(req.unwrap()).await.unwrap());
+    assert_eq!(next_data(&mut stream).await["type"], "started");
+    assert_eq!(next_data(&mut stream).await["type"], "paused");
+}
+
+#[tokio::test]
+async fn streams_say_goodbye_when_the_server_closes() {
+    let app = TestApp::new();
+    let id = app.create_minutes(25).await;
+    let mut session = events(app.open(&format!("/sessions/{id}/events")).await);
+    let mut feed = events(app.open("/events").await);
+    app.ctx.hub.events.close_streams();
+    for stream in [&mut session, &mut feed] {
+        let mut text = String::new();
+        let read = async {
+            while let Some(chunk) = stream.next().await {
+                text.push_str(std::str::from_utf8(&chunk.unwrap()).unwrap());
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), read).await.unwrap();
+        let last = text.trim_end().rsplit("\n\n").next().unwrap();
+        assert_eq!(last, "event: closing\ndata: server closing");
+    }
+}
+
+#[tokio::test]
+async fn the_pulse_reports_aggregates_every_interval() {
+    let app = TestApp::with(|c| c.pulse_interval = Duration::from_millis(20));
+    let id = app.create_minutes(25).await;
+    app.act(id, "start").await;
+    app.create_minutes(5).await;
+    let mut pulse = events(app.open("/pulse").await);
+    let first = next_data(&mut pulse).await;
+    assert_eq!(first["active_sessions"], 1);
+    assert_eq!(first["total_remaining_secs"], 25 * 60);
+    assert_eq!(first["min_remaining_secs"], 25 * 60);
+    let at: DateTime<Utc> = first["at"].as_str().unwrap().parse().unwrap();
+    let soonest: DateTime<Utc> = first["soonest_finish"].as_str().unwrap().parse().unwrap();
+    assert_eq!((soonest - at).num_seconds(), 25 * 60);
+
+    advance(60);
+    let second = next_data(&mut pulse).await;
+    assert_eq!(second["total_remaining_secs"], 24 * 60);
+    assert_eq!(second["soonest_finish"], first["soonest_finish"]);
+}
+
+// The next nudge on a session stream, skipping the session updates around it.
+async fn next_nudge(stream: &mut BodyDataStream) -> Value {
+    loop {
+        let data = next_data(stream).await;
+        if data.get("milestone").is_some() {
+            return data;
+        }
+    }
+}
+
+#[tokio::test]
+async fn nudges_fire_at_each_milestone() {
+    let app = TestApp::with(|c| {
+        c.nudges = Some(Nudges {
+            every: Some(Duration::from_secs(600)),
+            percents: vec![90],
+        })
+    });
+    let id = app.create_minutes(25).await;
+    app.act(id, "start").await;
+    let mut stream = events(app.open(&format!("/sessions/{id}/events")).await);
+    next_data(&mut stream).await;
+
+    advance(600);
+    let nudge = next_nudge(&mut stream).await;
+    assert_eq!(nudge["milestone"], "interval");
+    assert_eq!((&nudge["elapsed_secs"], &nudge["remaining_secs"]), (&json!(600), &json!(900)));
+    assert_eq!(nudge["message"], "10 minutes in, keep going");
+    advance(600);
+    assert_eq!(next_nudge(&mut stream).await["elapsed_secs"], 1200);
+    advance(150);
+    let nudge = next_nudge(&mut stream).await;
+    assert_eq!((&nudge["milestone"], &nudge["percent"]), (&json!("percent"), &json!(90)));
+    assert_eq!(nudge["message"], "90% done, 2 minutes left");
+}
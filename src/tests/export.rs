@@ -0,0 +1,99 @@
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+};
+use serde_json::{json, Value};
+
+use super::{id, TestApp};
+use crate::EXPORT_CHUNK;
+
+#[tokio::test]
+async fn ndjson_export_has_one_session_per_line() {
+    let app = TestApp::new();
+    // Enough to span more than one locked chunk.
+    let count = EXPORT_CHUNK as u64 + 5;
+    for minutes in 1..=count {
+        app.create_minutes(minutes).await;
+    }
+    let res = app.get("/export.ndjson").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.header("content-type"), Some("application/x-ndjson"));
+    let text = res.text();
+    assert!(text.ends_with('\n'));
+    let lines: Vec<Value> = text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(lines.iter().map(id).collect::<Vec<_>>(), (1..=count).collect::<Vec<_>>());
+    assert!(lines.iter().zip(1..).all(|(s, minutes)| s["work_minutes"] == minutes));
+}
+
+#[tokio::test]
+async fn ndjson_export_of_nothing_is_empty() {
+    let app = TestApp::new();
+    let res = app.get("/export.ndjson").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert!(res.body.is_empty());
+}
+
+#[tokio::test]
+async fn sessions_list_as_csv() {
+    let app = TestApp::new();
+    let body = json!({
+        "work_minutes": 25,
+        "title": "Write \"intro\", then outline",
+        "tags": ["writing", "deep"],
+        "metadata": { "project": "p1", "lane": "a" },
+    });
+    let id = id(&app.create(body).await);
+    let res = app.get("/sessions?format=csv").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.header("content-type"), Some("text/csv"));
+    let mut reader = csv::Reader::from_reader(res.body.as_slice());
+    let headers = reader.headers().unwrap().clone();
+    let rows: Vec<_> = reader.records().map(Result::unwrap).collect();
+    assert_eq!(rows.len(), 1);
+    let field = |name| &rows[0][headers.iter().position(|h| h == name).unwrap()];
+    assert_eq!(field("id"), id.to_string());
+    assert_eq!(field("state"), "idle");
+    assert_eq!(field("work_minutes"), "25");
+    assert_eq!(field("title"), "Write \"intro\", then outline");
+    assert_eq!(field("tags"), "deep;writing");
+    assert_eq!(field("metadata"), "lane=a;project=p1");
+    assert_eq!(field("finished_at"), "");
+
+    let req = Request::get("/sessions").header(header::ACCEPT, "text/csv").body(Body::empty());
+    let by_accept = app.send(req.unwrap()).await;
+    assert_eq!(by_accept.body, res.body);
+    // JSON stays the default.
+    assert!(app.get("/sessions").await.json().is_array());
+}
+
+#[tokio::test]
+async fn exports_serve_byte_ranges() {
+    let app = TestApp::new();
+    for minutes in 1..=3 {
+        app.create_minutes(minutes).await;
+    }
+    for path in ["/export.ndjson", "/sessions?format=csv"] {
+        let full = app.get(path).await;
+        assert_eq!(full.header("accept-ranges"), Some("bytes"));
+        let len = full.body.len();
+        let range = |spec: &str| {
+            let req = Request::get(path).header(header::RANGE, spec).body(Body::empty());
+            app.send(req.unwrap())
+        };
+
+        let res = range("bytes=10-29").await;
+        assert_eq!(res.status, StatusCode::PARTIAL_CONTENT, "{path}");
+        assert_eq!(res.header("content-range"), Some(&*format!("bytes 10-29/{len}")));
+        assert_eq!(res.body, full.body[10..30]);
+        // Resuming from an offset, and the last few bytes.
+        assert_eq!(range("bytes=20-").await.body, full.body[20..]);
+        assert_eq!(range("bytes=-5").await.body, full.body[len - 5..]);
+
+        let res = range(&format!("bytes={len}-")).await;
+        assert_eq!(res.status, StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(res.header("content-range"), Some(&*format!("bytes */{len}")));
+        // More than one range is answered with the whole body.
+        let res = range("bytes=0-1,5-6").await;
+        assert_eq!((res.status, res.body), (StatusCode::OK, full.body));
+    }
+}
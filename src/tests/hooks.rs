@@ -0,0 +1,58 @@
+use std::sync::{Arc, Mutex};
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use super::{advance, TestApp};
+use crate::{
+    error::{Error, Result},
+    events::EventKind,
+    hooks::TransitionHook,
+    AppState, PomodoroSession, PomodoroState,
+};
+
+// Refuses every pause.
+struct NoPausing;
+
+impl TransitionHook for NoPausing {
+    fn before(&self, _: &AppState, _: &PomodoroSession, event: EventKind) -> Result<()> {
+        match event {
+            EventKind::Paused => Err(Error::Conflict("no_pausing")),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Notes every transition that went through.
+#[derive(Default)]
+struct Observer(Mutex<Vec<(EventKind, PomodoroState, PomodoroState)>>);
+
+impl TransitionHook for Observer {
+    fn after(&self, session: &PomodoroSession, event: EventKind, from: PomodoroState) {
+        self.0.lock().unwrap().push((event, from, session.state));
+    }
+}
+
+#[tokio::test]
+async fn a_hook_can_veto_a_transition_and_observe_the_rest() {
+    let observer = Arc::new(Observer::default());
+    let mut app = TestApp::with_hooks(vec![Arc::new(NoPausing), observer.clone()]);
+    let id = app.create_minutes(1).await;
+    app.act(id, "start").await;
+
+    let res = app.post(&format!("/sessions/{id}/pause")).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.json(), json!({ "error": "no_pausing" }));
+    assert_eq!(app.session(id).await["state"], "Running");
+
+    advance(60);
+    app.tick();
+    let seen = observer.0.lock().unwrap();
+    assert!(matches!(
+        seen[..],
+        [
+            (EventKind::Started, PomodoroState::Idle, PomodoroState::Running),
+            (EventKind::Finished, PomodoroState::Running, PomodoroState::Finished),
+        ]
+    ));
+}
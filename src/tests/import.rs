@@ -0,0 +1,73 @@
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+};
+use serde_json::json;
+
+use super::{Reply, TestApp};
+
+const CSV: &str = "\
+title,work_minutes,break_minutes,finished_at
+write,25,5,2026-03-01T10:30:00Z
+read,abc,5,2026-03-01T11:00:00Z
+review,50,,1772362800000
+plan,10,0,
+later,25,5,2999-01-01T00:00:00Z
+";
+
+async fn import(app: &TestApp, query: &str, csv: &str) -> Reply {
+    let req = Request::post(format!("/import/csv{query}"))
+        .header(header::CONTENT_TYPE, "text/csv")
+        .body(Body::from(csv.to_string()))
+        .unwrap();
+    app.send(req).await
+}
+
+#[tokio::test]
+async fn rows_are_imported_with_their_finish_and_bad_ones_reported() {
+    let app = TestApp::new();
+    let res = import(&app, "", CSV).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    let report = res.json();
+    assert_eq!(report["imported"], json!([1, 2, 3]));
+    let errors = report["errors"].as_array().unwrap();
+    let lines: Vec<_> = errors.iter().map(|e| e["line"].as_u64().unwrap()).collect();
+    assert_eq!(lines, [3, 6]);
+    assert_eq!(errors[1]["message"], "finished_at is in the future");
+
+    let write = app.session(1).await;
+    assert_eq!((&write["state"], &write["title"]), (&json!("Finished"), &json!("write")));
+    assert_eq!(write["finished_at"], "2026-03-01T10:30:00Z");
+    // Backdated by the work and the break.
+    assert_eq!(write["created_at"], "2026-03-01T10:00:00Z");
+    assert_eq!((&write["elapsed_secs"], &write["rounds_completed"]), (&json!(1500), &json!(1)));
+    let review = app.session(2).await;
+    assert_eq!(review["finished_at"], "2026-03-01T11:00:00Z");
+    assert_eq!(app.session(3).await["state"], "Idle");
+}
+
+#[tokio::test]
+async fn a_strict_import_is_all_or_nothing() {
+    let app = TestApp::new();
+    let res = import(&app, "?strict=true", CSV).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    let report = res.json();
+    assert_eq!(report["imported"], json!([]));
+    assert_eq!(report["errors"].as_array().map(Vec::len), Some(2));
+    assert!(app.ctx.state.lock().unwrap().sessions.is_empty());
+    let valid: String = CSV
+        .lines()
+        .filter(|l| !l.starts_with("read") && !l.starts_with("later"))
+        .map(|l| format!("{l}\n"))
+        .collect();
+    assert_eq!(import(&app, "?strict=true", &valid).await.json()["imported"], json!([1, 2, 3]));
+}
+
+#[tokio::test]
+async fn imports_are_held_back_like_creates() {
+    let app = TestApp::new();
+    app.post_json("/admin/focus-lock", json!({ "enabled": true })).await;
+    let res = import(&app, "", CSV).await;
+    assert_eq!((res.status, res.error()), (StatusCode::LOCKED, "focus_locked".into()));
+    assert!(app.ctx.state.lock().unwrap().sessions.is_empty());
+}
@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+
+use super::TestApp;
+
+#[tokio::test]
+async fn slow_request_times_out() {
+    let app = TestApp::with(|config| config.request_timeout = Duration::from_millis(20));
+    assert_eq!(app.get("/debug/slow").await.status, StatusCode::REQUEST_TIMEOUT);
+    // The limit is per request; quick ones are untouched.
+    assert_eq!(app.get("/sessions").await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn requests_past_the_limit_are_shed() {
+    let app = std::sync::Arc::new(TestApp::with(|config| config.max_concurrency = Some(2)));
+    let held: Vec<_> = (0..2)
+        .map(|_| {
+            let app = app.clone();
+            tokio::spawn(async move { app.get("/debug/slow").await })
+        })
+        .collect();
+    // Let both take their permit.
+    tokio::task::yield_now().await;
+    // The limit covers the whole API, not each route.
+    for path in ["/debug/slow", "/sessions", "/stats"] {
+        let res = app.get(path).await;
+        assert_eq!(res.status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(res.error(), "overloaded");
+    }
+    // Streams don't count against it.
+    assert_eq!(app.open("/events").await.status(), StatusCode::OK);
+    for slow in held {
+        slow.abort();
+        let _ = slow.await;
+    }
+    assert_eq!(app.get("/sessions").await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn request_ids_are_echoed_or_minted() {
+    let app = TestApp::new();
+    let req = Request::get("/sessions/9999").header("x-request-id", "client-42");
+    let res = app.send(req.body(Body::empty()).unwrap()).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert_eq!(res.header("x-request-id"), Some("client-42"));
+    let minted: Vec<String> = [app.get("/sessions").await, app.get("/sessions").await]
+        .iter()
+        .map(|res| res.header("x-request-id").unwrap().to_string())
+        .collect();
+    for id in &minted {
+        assert!(uuid::Uuid::parse_str(id).is_ok(), "{id}");
+    }
+    assert_ne!(minted[0], minted[1]);
+}
+
+#[tokio::test]
+async fn handler_panics_become_internal_errors() {
+    let app = TestApp::new();
+    let res = app.post("/debug/panic").await;
+    assert_eq!(res.status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(res.json(), serde_json::json!({ "error": "internal" }));
+    // The lock stays poisoned, so nothing builds on the half-changed state.
+    assert!(app.ctx.state.is_poisoned());
+    for res in [
+        app.get("/sessions").await,
+        app.post_json("/sessions", serde_json::json!({ "work_minutes": 25 })).await,
+    ] {
+        assert_eq!(res.status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(res.json(), serde_json::json!({ "error": "internal" }));
+    }
+    // What doesn't need the state still answers.
+    assert_eq!(app.get("/healthz").await.status, StatusCode::OK);
+}
@@ -0,0 +1,74 @@
+use std::{
+    io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::http::StatusCode;
+use serde_json::Value;
+
+use super::{advance, TestApp};
+use crate::{config::LogFormat, logger};
+
+// A log writer whose output the test can read back.
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn transitions_are_logged_as_json() {
+    let captured = Captured::default();
+    let writer = captured.clone();
+    let logger = logger(LogFormat::Json, move || writer.clone());
+    let _logging = tracing::subscriber::set_default(logger);
+    let app = TestApp::new();
+    let id = app.create_minutes(25).await;
+    app.act(id, "start").await;
+    advance(90);
+    app.act(id, "pause").await;
+
+    let output = captured.0.lock().unwrap().clone();
+    let lines: Vec<Value> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .filter(|line: &Value| line["target"] == "transition")
+        .collect();
+    let events: Vec<_> = lines.iter().map(|line| &line["fields"]["event"]).collect();
+    assert_eq!(events, ["started", "paused"]);
+    let paused = &lines[1]["fields"];
+    assert_eq!(paused["session_id"], id);
+    assert_eq!(paused["from"], "Running");
+    assert_eq!(paused["to"], "Paused");
+    assert_eq!(paused["elapsed_secs"], 90);
+    let at = paused["at"].as_str().unwrap();
+    assert!(at.parse::<chrono::DateTime<chrono::Utc>>().is_ok(), "{at}");
+}
+
+#[tokio::test]
+async fn a_zero_request_timeout_falls_back_to_the_default() {
+    let captured = Captured::default();
+    let writer = captured.clone();
+    let logger = logger(LogFormat::Json, move || writer.clone());
+    let _logging = tracing::subscriber::set_default(logger);
+    let app = TestApp::with(|config| config.request_timeout = Duration::ZERO);
+    assert_eq!(app.get("/debug/brief").await.status, StatusCode::OK);
+
+    let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+    let warning = output
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .find(|line| line["level"] == "WARN")
+        .expect(&output);
+    assert_eq!(warning["fields"]["default_secs"], 30);
+}
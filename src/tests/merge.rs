@@ -0,0 +1,46 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use super::{advance, id, Reply, TestApp};
+
+async fn merge(app: &TestApp, into: u64, from: u64) -> Reply {
+    app.post_json(&format!("/sessions/{into}/merge"), json!({ "from": from })).await
+}
+
+#[tokio::test]
+async fn merging_sums_the_time_and_removes_the_source() {
+    let app = TestApp::new();
+    let target = id(&app.create(json!({ "work_minutes": 1, "notes": "outline" })).await);
+    let source = id(&app.create(json!({ "work_minutes": 2, "notes": "draft" })).await);
+    app.act(target, "start").await;
+    app.act(source, "start").await;
+    advance(120);
+    assert_eq!(app.session(target).await["state"], "Finished");
+    assert_eq!(app.session(source).await["state"], "Finished");
+
+    let res = merge(&app, target, source).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    let merged = res.json();
+    assert_eq!(merged["elapsed_secs"], 180);
+    assert_eq!(merged["notes"], "outline\ndraft");
+    assert_eq!(app.get(&format!("/sessions/{source}")).await.status, StatusCode::NOT_FOUND);
+    let history = app.get(&format!("/sessions/{target}/history")).await.json();
+    let events: Vec<&Value> = history.as_array().unwrap().iter().map(|e| &e["event"]).collect();
+    assert_eq!(events.iter().filter(|e| **e == "started").count(), 2);
+    assert_eq!(events.last().unwrap().as_str(), Some("merged"));
+}
+
+#[tokio::test]
+async fn running_sessions_dont_merge() {
+    let app = TestApp::new();
+    let running = app.create_minutes(25).await;
+    let idle = app.create_minutes(25).await;
+    app.act(running, "start").await;
+    for (into, from) in [(running, idle), (idle, running)] {
+        let res = merge(&app, into, from).await;
+        assert_eq!(res.status, StatusCode::CONFLICT);
+        assert_eq!(res.error(), "session_running");
+    }
+    assert_eq!(merge(&app, idle, idle).await.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(app.session(idle).await["state"], "Idle");
+}
@@ -0,0 +1,46 @@
+use axum::http::StatusCode;
+use serde_json::Value;
+
+use super::TestApp;
+use crate::{PomodoroState, NOTIFY_PROFILES};
+
+// Exhaustive, so a new state doesn't compile until it gets the next number,
+// and then the test fails until `/meta` lists it.
+fn ordinal(state: PomodoroState) -> usize {
+    match state {
+        PomodoroState::Idle => 0,
+        PomodoroState::Running => 1,
+        PomodoroState::Break => 2,
+        PomodoroState::Paused => 3,
+        PomodoroState::Interrupted => 4,
+        PomodoroState::Finished => 5,
+        PomodoroState::Cancelled => 6,
+    }
+}
+
+const STATE_COUNT: usize = 7;
+
+#[tokio::test]
+async fn meta_lists_every_state() {
+    let app = TestApp::new();
+    let res = app.get("/meta").await;
+    assert_eq!(res.status, StatusCode::OK);
+    let meta = res.json();
+    let states: Vec<PomodoroState> = meta["states"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| {
+            assert!(s["description"].as_str().is_some_and(|d| !d.is_empty()), "{s}");
+            serde_json::from_value(s["value"].clone()).unwrap()
+        })
+        .collect();
+    let mut ordinals: Vec<usize> = states.into_iter().map(ordinal).collect();
+    ordinals.sort_unstable();
+    assert_eq!(ordinals, (0..STATE_COUNT).collect::<Vec<_>>());
+
+    let phases = meta["phases"].as_array().unwrap();
+    let phases: Vec<&Value> = phases.iter().map(|p| &p["value"]).collect();
+    assert_eq!(phases, ["work", "break"]);
+    assert_eq!(meta["notify_profiles"], serde_json::json!(NOTIFY_PROFILES));
+}
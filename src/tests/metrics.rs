@@ -0,0 +1,35 @@
+use serde_json::json;
+
+use super::{advance, id, TestApp};
+
+fn sample<'a>(text: &'a str, series: &str) -> &'a str {
+    let line = text.lines().find(|l| l.starts_with(series)).unwrap();
+    line[series.len()..].trim()
+}
+
+#[tokio::test]
+async fn finished_durations_feed_the_histogram() {
+    let app = TestApp::new();
+    for minutes in [1, 10, 45] {
+        let body = json!({ "work_minutes": minutes, "break_minutes": 0 });
+        let id = id(&app.create(body).await);
+        app.act(id, "start").await;
+        advance(minutes * 60);
+        assert_eq!(app.session(id).await["state"], "Finished");
+    }
+    let stopped = app.create_minutes(25).await;
+    app.act(stopped, "start").await;
+    advance(120);
+    app.act(stopped, "cancel").await;
+
+    let text = app.get("/metrics").await.text();
+    let histogram = "pomodoro_session_duration_seconds";
+    assert_eq!(sample(&text, &format!("{histogram}_bucket{{le=\"60\"}}")), "1");
+    assert_eq!(sample(&text, &format!("{histogram}_bucket{{le=\"600\"}}")), "2");
+    assert_eq!(sample(&text, &format!("{histogram}_bucket{{le=\"2700\"}}")), "3");
+    assert_eq!(sample(&text, &format!("{histogram}_sum")), "3360");
+    assert_eq!(sample(&text, &format!("{histogram}_count")), "3");
+    // The cancelled one is counted apart.
+    assert_eq!(sample(&text, "pomodoro_session_early_stop_seconds_sum"), "120");
+    assert_eq!(sample(&text, "pomodoro_session_early_stop_seconds_count"), "1");
+}
@@ -0,0 +1,296 @@
+// Drives the router in-process with `oneshot`, one fresh state per test. Time
+// only moves when a test calls `advance`, and the ticker only runs on `tick`,
+// so nothing here sleeps. `#[tokio::test]` keeps handlers on the test's own
+// thread, where `advance` applies.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::State,
+    http::{header, HeaderMap, Method, Request, StatusCode},
+    response::Response,
+    routing::{get, post},
+    Router,
+};
+use chrono::{Local, NaiveTime, TimeDelta};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+use tower::ServiceExt;
+
+use crate::{
+    app, clock, config::Config, hooks::TransitionHook, hub::Hub, AppContext, ListSnapshot,
+    SharedState, Ticker,
+};
+
+mod adjust;
+mod admin;
+mod completed;
+mod control;
+mod counters;
+mod deps;
+mod envelope;
+mod errors;
+mod events;
+mod export;
+mod hooks;
+mod import;
+mod layers;
+mod logging;
+mod merge;
+mod meta;
+mod metrics;
+mod notify;
+mod persist;
+mod phases;
+mod quiet;
+mod routes;
+mod sessions;
+mod split;
+mod stale;
+mod stats;
+mod summary;
+mod supervise;
+mod sweep;
+mod sync;
+mod templates;
+mod ticker;
+mod timestamps;
+mod timing;
+#[cfg(unix)]
+mod uds;
+mod webhook;
+
+// Routes that only exist in test builds, merged into the API by `app`.
+pub fn debug_router() -> Router<AppContext> {
+    Router::new()
+        .route("/debug/slow", get(slow))
+        .route("/debug/brief", get(brief))
+        .route("/debug/panic", post(panic_holding_lock))
+}
+
+// Outlasts any timeout a test configures.
+async fn slow() -> &'static str {
+    tokio::time::sleep(Duration::from_secs(3600)).await;
+    "done"
+}
+
+// Long enough for a zero timeout to fire, well inside the default one.
+async fn brief() -> &'static str {
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    "done"
+}
+
+// Panics with the state lock held, poisoning it, after leaving a mark on the state.
+async fn panic_holding_lock(State(state): State<SharedState>) {
+    let mut guard = state.lock().unwrap();
+    guard.next_id += 1;
+    panic!("debug panic");
+}
+
+pub struct TestApp {
+    pub ctx: AppContext,
+    pub router: Router,
+    ticker: Ticker,
+}
+
+pub struct Reply {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+impl Reply {
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body)
+            .unwrap_or_else(|err| panic!("{} is not JSON ({err}): {}", self.status, self.text()))
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|v| v.to_str().ok())
+    }
+
+    // The `error` code of an error body.
+    pub fn error(&self) -> String {
+        self.json()["error"].as_str().unwrap_or_default().to_string()
+    }
+}
+
+impl TestApp {
+    pub fn new() -> Self {
+        Self::with(|_| {})
+    }
+
+    pub fn with(configure: impl FnOnce(&mut Config)) -> Self {
+        let mut config = Config::default();
+        configure(&mut config);
+        let hub = Hub::new(&config);
+        Self::serving(config, hub)
+    }
+
+    // The default config, with `hooks` in place of the ones it would install.
+    pub fn with_hooks(hooks: Vec<Arc<dyn TransitionHook>>) -> Self {
+        let config = Config::default();
+        let hub = Hub::with_hooks(&config, hooks);
+        Self::serving(config, hub)
+    }
+
+    fn serving(config: Config, hub: Hub) -> Self {
+        let hub = Arc::new(hub);
+        let state = SharedState::default();
+        {
+            let mut guard = state.lock().unwrap();
+            guard.adopt(hub.clone());
+            guard.next_id = config.id_seed;
+        }
+        let ctx = AppContext {
+            state,
+            config: Arc::new(config),
+            snapshot: ListSnapshot::default(),
+            hub,
+        };
+        let ticker = Ticker::new(&ctx);
+        Self {
+            router: app(ctx.clone()),
+            ctx,
+            ticker,
+        }
+    }
+
+    // Waits for the webhook deliveries this app started.
+    pub async fn drain_webhooks(&self) {
+        self.ctx.hub.deliveries.drain(Duration::from_secs(5)).await;
+    }
+
+    // A GET whose response comes back unread, for streams a test holds open.
+    pub async fn open(&self, path: &str) -> Response {
+        let req = Request::get(path).body(Body::empty()).unwrap();
+        self.router.clone().oneshot(req).await.unwrap()
+    }
+
+    pub async fn send(&self, req: Request<Body>) -> Reply {
+        let res = self.router.clone().oneshot(req).await.unwrap();
+        let status = res.status();
+        let headers = res.headers().clone();
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap().to_vec();
+        Reply {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    pub async fn call(&self, method: Method, path: &str, body: Option<&Value>) -> Reply {
+        let req = Request::builder().method(method).uri(path);
+        let req = match body {
+            Some(body) => req
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => req.body(Body::empty()),
+        };
+        self.send(req.unwrap()).await
+    }
+
+    pub async fn get(&self, path: &str) -> Reply {
+        self.call(Method::GET, path, None).await
+    }
+
+    pub async fn post(&self, path: &str) -> Reply {
+        self.call(Method::POST, path, None).await
+    }
+
+    pub async fn post_json(&self, path: &str, body: Value) -> Reply {
+        self.call(Method::POST, path, Some(&body)).await
+    }
+
+    pub async fn put_json(&self, path: &str, body: Value) -> Reply {
+        self.call(Method::PUT, path, Some(&body)).await
+    }
+
+    pub async fn patch_json(&self, path: &str, body: Value) -> Reply {
+        self.call(Method::PATCH, path, Some(&body)).await
+    }
+
+    pub async fn delete(&self, path: &str) -> Reply {
+        self.call(Method::DELETE, path, None).await
+    }
+
+    // Creates a session from `body` and returns it, failing the test on anything but 201.
+    pub async fn create(&self, body: Value) -> Value {
+        let res = self.post_json("/sessions", body).await;
+        assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+        res.json()
+    }
+
+    // A session of `work_minutes` with no break; returns its id.
+    pub async fn create_minutes(&self, work_minutes: u64) -> u64 {
+        id(&self.create(json!({ "work_minutes": work_minutes })).await)
+    }
+
+    pub async fn session(&self, id: u64) -> Value {
+        let res = self.get(&format!("/sessions/{id}")).await;
+        assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+        res.json()
+    }
+
+    // Runs a control action (`start`, `pause`, ...) that is expected to succeed.
+    pub async fn act(&self, id: u64, action: &str) -> Value {
+        let res = self.post(&format!("/sessions/{id}/{action}")).await;
+        assert_eq!(res.status, StatusCode::OK, "{action}: {}", res.text());
+        res.json()
+    }
+
+    // One pass of the ticker, as the running server makes every second.
+    pub fn tick(&mut self) {
+        self.ticker.tick().unwrap();
+    }
+}
+
+pub fn id(session: &Value) -> u64 {
+    session["id"].as_u64().unwrap_or_else(|| panic!("no id in {session}"))
+}
+
+pub fn advance(secs: u64) {
+    clock::advance(Duration::from_secs(secs));
+}
+
+// Moves the clock on to `hour:minute` tomorrow, local time, so a test's day is its own.
+pub fn tomorrow_at(hour: u32, minute: u32) {
+    let now = clock::local();
+    let at = NaiveTime::from_hms_opt(hour, minute, 0).unwrap();
+    let then = (now.date_naive() + TimeDelta::days(1)).and_time(at);
+    let then = then.and_local_timezone(Local).earliest().unwrap();
+    clock::advance((then - now).to_std().unwrap());
+}
+
+// Everything posted to a local receiver, in arrival order.
+pub type Received = Arc<Mutex<Vec<Vec<u8>>>>;
+
+// A local webhook endpoint that answers 500 to its first `failures` deliveries.
+pub async fn receiver(failures: usize) -> (String, Received) {
+    let received = Received::default();
+    let seen = received.clone();
+    let app = Router::new().route(
+        "/hook",
+        post(move |body: axum::body::Bytes| async move {
+            let mut seen = seen.lock().unwrap();
+            seen.push(body.to_vec());
+            if seen.len() <= failures {
+                StatusCode::INTERNAL_SERVER_ERROR
+            } else {
+                StatusCode::NO_CONTENT
+            }
+        }),
+    );
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    (url, received)
+}
@@ -0,0 +1,39 @@
+use std::sync::{Arc, Mutex};
+
+use super::{advance, TestApp};
+use crate::{
+    events::EventKind,
+    id::SessionId,
+    notify::{Notifier, SessionEvent},
+};
+
+// Keeps every event it is handed.
+#[derive(Default)]
+struct Recorder(Mutex<Vec<SessionEvent>>);
+
+impl Notifier for Recorder {
+    fn notify(&self, event: SessionEvent) {
+        self.0.lock().unwrap().push(event);
+    }
+}
+
+#[tokio::test]
+async fn every_notifier_hears_of_a_finish() {
+    let mut app = TestApp::new();
+    let (first, second) = (Arc::new(Recorder::default()), Arc::new(Recorder::default()));
+    app.ticker.notifiers = Arc::new([first.clone() as Arc<dyn Notifier>, second.clone()]);
+    let id = app.create_minutes(1).await;
+    app.act(id, "start").await;
+    app.tick();
+    assert!(first.0.lock().unwrap().is_empty());
+
+    advance(60);
+    app.tick();
+    app.tick();
+    for recorder in [first, second] {
+        let events = recorder.0.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0].kind, EventKind::Finished));
+        assert_eq!(events[0].session.id, SessionId(id));
+    }
+}
@@ -0,0 +1,155 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{json, Value};
+
+use super::{advance, TestApp};
+use crate::{config::StateFormat, persist, to_response, AppState};
+
+fn state_file(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("pomodoro-{}-{name}.json", std::process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+#[tokio::test]
+async fn rapid_mutations_coalesce_into_one_save() {
+    let app = TestApp::new();
+    let path = state_file("coalesce");
+    for _ in 0..20 {
+        let id = app.create_minutes(25).await;
+        app.act(id, "start").await;
+        app.act(id, "pause").await;
+    }
+    persist::flush(&app.ctx.state, &path, StateFormat::Json).unwrap();
+    let saved = persist::load(&path).unwrap().unwrap();
+    assert_eq!(saved.sessions.len(), 20);
+    // Nothing changed since, so the next tick of the flusher writes nothing.
+    fs::remove_file(&path).unwrap();
+    persist::flush(&app.ctx.state, &path, StateFormat::Json).unwrap();
+    assert!(!path.exists());
+    app.create_minutes(5).await;
+    persist::flush(&app.ctx.state, &path, StateFormat::Json).unwrap();
+    assert_eq!(persist::load(&path).unwrap().unwrap().sessions.len(), 21);
+    fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn a_failed_save_is_retried_by_the_next_flush() {
+    let app = TestApp::new();
+    let dir = std::env::temp_dir().join(format!("pomodoro-{}-retry", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let path = dir.join("state.json");
+    app.create_minutes(25).await;
+    // The directory isn't there yet, so the write fails.
+    assert!(persist::flush(&app.ctx.state, &path, StateFormat::Json).is_err());
+    assert!(app.ctx.state.lock().unwrap().dirty);
+    fs::create_dir(&dir).unwrap();
+    persist::flush(&app.ctx.state, &path, StateFormat::Json).unwrap();
+    assert_eq!(persist::load(&path).unwrap().unwrap().sessions.len(), 1);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+// Less `paused_for_secs`: like every Instant, when a pause began is not saved.
+fn responses(state: &AppState) -> Vec<Value> {
+    let response = |s| {
+        let mut response = serde_json::to_value(to_response(s)).unwrap();
+        response.as_object_mut().unwrap().remove("paused_for_secs");
+        response
+    };
+    state.sessions.values().map(response).collect()
+}
+
+#[tokio::test]
+async fn binary_state_round_trips() {
+    let app = TestApp::new();
+    let path = state_file("binary");
+    let body = json!({ "work_minutes": 25, "title": "t", "tags": ["a"], "metadata": { "k": "v" } });
+    let running = super::id(&app.create(body).await);
+    app.act(running, "start").await;
+    let paused = app.create_minutes(10).await;
+    app.act(paused, "start").await;
+    advance(90);
+    app.act(paused, "pause").await;
+    app.create_minutes(5).await;
+    persist::flush(&app.ctx.state, &path, StateFormat::Binary).unwrap();
+    assert!(fs::read(&path).unwrap().starts_with(b"POMO"));
+    let saved = persist::load(&path).unwrap().unwrap();
+    let state = app.ctx.state.lock().unwrap();
+    assert_eq!(responses(&saved), responses(&state));
+    assert_eq!((saved.next_id, saved.version), (state.next_id, state.version));
+    fs::remove_file(&path).unwrap();
+}
+
+// The files `path` was moved aside to.
+fn backups(path: &Path) -> Vec<PathBuf> {
+    let name = path.file_name().unwrap().to_str().unwrap().to_string();
+    fs::read_dir(path.parent().unwrap())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|p| p.file_name().unwrap().to_str().unwrap().starts_with(&format!("{name}.")))
+        .collect()
+}
+
+#[tokio::test]
+async fn a_binary_file_from_another_version_is_set_aside() {
+    let path = state_file("version");
+    let mut bytes = b"POMO".to_vec();
+    bytes.extend_from_slice(&u16::MAX.to_le_bytes());
+    bytes.extend_from_slice(b"whatever came after");
+    fs::write(&path, &bytes).unwrap();
+    assert!(persist::load(&path).unwrap().is_none());
+    assert!(!path.exists());
+    // The old bytes are kept next to it, untouched.
+    let backups = backups(&path);
+    assert_eq!(backups.len(), 1);
+    assert_eq!(fs::read(&backups[0]).unwrap(), bytes);
+    fs::remove_file(&backups[0]).unwrap();
+}
+
+#[tokio::test]
+async fn loading_keeps_new_ids_clear_of_saved_ones() {
+    let app = TestApp::new();
+    let path = state_file("next-id");
+    for _ in 0..3 {
+        app.create_minutes(25).await;
+    }
+    // A deleted session's id isn't handed out again either.
+    app.delete("/sessions/3").await;
+    persist::flush(&app.ctx.state, &path, StateFormat::Json).unwrap();
+    assert_eq!(persist::load(&path).unwrap().unwrap().next_id, 3);
+
+    // An older file without a usable counter is fixed up from its sessions.
+    let mut saved: Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+    saved["next_id"] = json!(0);
+    fs::write(&path, saved.to_string()).unwrap();
+    assert_eq!(persist::load(&path).unwrap().unwrap().next_id, 2);
+    fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn ids_can_be_seeded() {
+    let app = TestApp::with(|c| c.id_seed = 1000);
+    assert_eq!(app.create_minutes(25).await, 1001);
+}
+
+#[tokio::test]
+async fn a_truncated_state_file_is_set_aside_and_startup_goes_on_empty() {
+    let app = TestApp::new();
+    let path = state_file("truncated");
+    app.create_minutes(25).await;
+    persist::flush(&app.ctx.state, &path, StateFormat::Json).unwrap();
+    let whole = fs::read(&path).unwrap();
+    fs::write(&path, &whole[..whole.len() / 2]).unwrap();
+
+    assert!(persist::load(&path).unwrap().is_none());
+    let backups = backups(&path);
+    assert_eq!(backups.len(), 1);
+    assert!(backups[0].to_str().unwrap().ends_with(".bak"));
+    assert_eq!(fs::read(&backups[0]).unwrap(), whole[..whole.len() / 2]);
+    // Nothing is left in the way of the next save.
+    assert!(!path.exists());
+    fs::remove_file(&backups[0]).unwrap();
+}
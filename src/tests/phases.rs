@@ -0,0 +1,162 @@
+use axum::http::StatusCode;
+use chrono::DateTime;
+use serde_json::{json, Value};
+
+use super::{advance, id, TestApp};
+use crate::{id::SessionId, PomodoroState};
+
+#[tokio::test]
+async fn zero_break_goes_straight_to_finished() {
+    let app = TestApp::new();
+    let id = id(&app.create(json!({ "work_minutes": 1, "break_minutes": 0 })).await);
+    app.act(id, "start").await;
+    advance(59);
+    assert_eq!(app.session(id).await["state"], "Running");
+    advance(1);
+    let s = app.session(id).await;
+    assert_eq!(s["state"], "Finished");
+    assert_eq!(s["phase_remaining_secs"], 0);
+}
+
+#[tokio::test]
+async fn break_follows_the_work_phase() {
+    let app = TestApp::new();
+    let id = id(&app.create(json!({ "work_minutes": 1, "break_minutes": 5 })).await);
+    app.act(id, "start").await;
+    advance(60);
+    let s = app.session(id).await;
+    assert_eq!((s["state"].as_str(), s["phase"].as_str()), (Some("Break"), Some("break")));
+    assert_eq!(s["phase_remaining_secs"], 300);
+    advance(299);
+    assert_eq!(app.session(id).await["state"], "Break");
+    advance(1);
+    assert_eq!(app.session(id).await["state"], "Finished");
+}
+
+#[tokio::test]
+async fn each_phase_reports_its_own_countdown() {
+    let app = TestApp::new();
+    let id = id(&app.create(json!({ "work_minutes": 10, "break_minutes": 5 })).await);
+    let countdowns = |s: Value| {
+        let phase = s["phase"].clone();
+        (phase, s["phase_remaining_secs"].clone(), s["remaining_secs"].clone())
+    };
+    assert_eq!(countdowns(app.act(id, "start").await), (json!("work"), json!(600), json!(600)));
+    advance(240);
+    assert_eq!(countdowns(app.session(id).await), (json!("work"), json!(360), json!(360)));
+    // In the break `remaining_secs`, which is work only, stays at zero.
+    advance(360 + 120);
+    assert_eq!(countdowns(app.session(id).await), (json!("break"), json!(180), json!(0)));
+}
+
+#[tokio::test]
+async fn a_long_unpolled_session_settles_on_the_next_read() {
+    let app = TestApp::new();
+    let id = id(&app.create(json!({ "work_minutes": 25, "break_minutes": 5 })).await);
+    // Created and started at the same instant: the clock only moves on `advance`.
+    let started = app.act(id, "start").await["created_at"].clone();
+    // No ticker and no reads for ten hours: work, break and finish in one step.
+    advance(10 * 3600);
+    let s = app.session(id).await;
+    assert_eq!((&s["state"], &s["elapsed_secs"]), (&json!("Finished"), &json!(1500)));
+    assert_eq!(s["rounds_completed"], 1);
+    // Backdated to when the break ran out, not when it was noticed.
+    let at = |v: &Value| DateTime::parse_from_rfc3339(v.as_str().unwrap()).unwrap();
+    assert_eq!((at(&s["finished_at"]) - at(&started)).num_seconds(), 30 * 60);
+    let state = app.ctx.state.lock().unwrap();
+    assert_eq!(state.sessions[&SessionId(id)].state, PomodoroState::Finished);
+}
+
+async fn ten_minutes(app: &TestApp) -> u64 {
+    let id = id(&app.create(json!({ "work_minutes": 10, "break_minutes": 0 })).await);
+    app.act(id, "start").await;
+    id
+}
+
+#[tokio::test]
+async fn a_clean_run_scores_full_marks() {
+    let app = TestApp::new();
+    let id = ten_minutes(&app).await;
+    advance(300);
+    assert_eq!(app.session(id).await["focus_score"], Value::Null);
+    advance(300);
+    assert_eq!(app.session(id).await["focus_score"], 100);
+}
+
+#[tokio::test]
+async fn interruptions_and_suspended_time_lower_the_score() {
+    let app = TestApp::new();
+    let id = ten_minutes(&app).await;
+    advance(300);
+    app.act(id, "interrupt").await;
+    advance(600);
+    app.act(id, "resume").await;
+    advance(300);
+    // 15 for the interruption, 20 for spending half the round suspended.
+    assert_eq!(app.session(id).await["focus_score"], 65);
+}
+
+#[tokio::test]
+async fn a_forced_early_finish_scores_by_the_work_done() {
+    let app = TestApp::new();
+    let id = ten_minutes(&app).await;
+    advance(300);
+    app.post("/admin/finish-all").await;
+    let s = app.session(id).await;
+    assert_eq!((s["state"].as_str(), s["focus_score"].as_u64()), (Some("Finished"), Some(50)));
+}
+
+#[tokio::test]
+async fn changing_work_minutes_keeps_the_elapsed_time() {
+    let app = TestApp::new();
+    let id = app.create_minutes(25).await;
+    let path = format!("/sessions/{id}");
+    app.act(id, "start").await;
+    advance(10 * 60);
+    // Zero would finish it on the spot; it's refused like any out-of-range length.
+    for minutes in [0, 1441] {
+        let res = app.patch_json(&path, json!({ "work_minutes": minutes })).await;
+        assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY, "{minutes}: {}", res.text());
+        assert!(res.json()["fields"]["work_minutes"].is_array());
+    }
+    assert_eq!(app.session(id).await["state"], "Running");
+    let s = app.patch_json(&path, json!({ "work_minutes": 40 })).await.json();
+    assert_eq!((&s["state"], &s["elapsed_secs"]), (&json!("Running"), &json!(600)));
+    assert_eq!(s["remaining_secs"], 30 * 60);
+    advance(60);
+    // Shrunk below what's done, it finishes on the spot, backdated by the overshoot.
+    let s = app.patch_json(&path, json!({ "work_minutes": 5 })).await.json();
+    assert_eq!((&s["state"], &s["elapsed_secs"]), (&json!("Finished"), &json!(300)));
+    assert_eq!(s["remaining_secs"], 0);
+    let at = |v: &Value| DateTime::parse_from_rfc3339(v.as_str().unwrap()).unwrap();
+    assert_eq!((at(&s["finished_at"]) - at(&s["created_at"])).num_seconds(), 5 * 60);
+}
+
+#[tokio::test]
+async fn the_suggestion_follows_the_phase() {
+    let app = TestApp::new();
+    let session = id(&app.create(json!({ "work_minutes": 1, "break_minutes": 5 })).await);
+    let suggest = |id: u64| {
+        let app = &app;
+        async move { app.get(&format!("/sessions/{id}/suggest")).await.json()["action"].clone() }
+    };
+    assert_eq!(suggest(session).await, "start");
+    app.act(session, "start").await;
+    assert_eq!(suggest(session).await, "keep_working");
+    // Work ran out with nobody looking: the suggestion sees it, the state is left alone.
+    advance(60);
+    assert_eq!(suggest(session).await, "take_break");
+    let state = app.ctx.state.lock().unwrap().sessions[&SessionId(session)].state;
+    assert_eq!(state, PomodoroState::Running);
+    assert_eq!(app.session(session).await["state"], "Break");
+    assert_eq!(suggest(session).await, "take_break");
+    advance(300);
+    assert_eq!(app.session(session).await["state"], "Finished");
+    assert_eq!(suggest(session).await, "start_next");
+    // With no break to take, finished work still calls for one.
+    let no_break = id(&app.create(json!({ "work_minutes": 1, "break_minutes": 0 })).await);
+    app.act(no_break, "start").await;
+    advance(60);
+    assert_eq!(app.session(no_break).await["state"], "Finished");
+    assert_eq!(suggest(no_break).await, "take_break");
+}
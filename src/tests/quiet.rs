@@ -0,0 +1,61 @@
+use chrono::NaiveTime;
+
+use super::{advance, receiver, tomorrow_at, Received, TestApp};
+use crate::config::QuietHours;
+
+// Overnight quiet hours, 22:00 to 07:00, with finish webhooks going to a local receiver.
+async fn overnight(queue: bool) -> (TestApp, Received) {
+    let (url, received) = receiver(0).await;
+    let app = TestApp::with(|c| {
+        c.webhook_url = Some(url);
+        c.quiet_hours = Some(QuietHours {
+            start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+            queue,
+        });
+    });
+    (app, received)
+}
+
+// A 25 minute session started at 23:00 and finished inside the window.
+async fn finish_overnight(app: &mut TestApp) -> u64 {
+    tomorrow_at(23, 0);
+    let id = app.create_minutes(25).await;
+    app.act(id, "start").await;
+    advance(25 * 60);
+    app.tick();
+    app.drain_webhooks().await;
+    id
+}
+
+#[tokio::test]
+async fn finishes_in_quiet_hours_send_no_webhook() {
+    let (mut app, received) = overnight(false).await;
+    let id = finish_overnight(&mut app).await;
+    assert_eq!(app.session(id).await["state"], "Finished");
+    assert!(received.lock().unwrap().is_empty());
+    // Dropped, not held: nothing follows once the window ends.
+    advance(8 * 3600);
+    app.tick();
+    app.drain_webhooks().await;
+    assert!(received.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn queued_finishes_go_out_when_quiet_hours_end() {
+    let (mut app, received) = overnight(true).await;
+    let id = finish_overnight(&mut app).await;
+    assert!(received.lock().unwrap().is_empty());
+    // Still held at 06:59; out at 07:00.
+    advance(7 * 3600 + 34 * 60);
+    app.tick();
+    app.drain_webhooks().await;
+    assert!(received.lock().unwrap().is_empty());
+    advance(60);
+    app.tick();
+    app.drain_webhooks().await;
+    let sent = received.lock().unwrap();
+    assert_eq!(sent.len(), 1);
+    let body: serde_json::Value = serde_json::from_slice(&sent[0]).unwrap();
+    assert_eq!((body["id"].as_u64(), &body["state"]), (Some(id), &"Finished".into()));
+}
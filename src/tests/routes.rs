@@ -0,0 +1,21 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::{app, config::Config, hub::Hub, AppContext, ListSnapshot, SharedState};
+
+// axum panics while building a router that registers a method twice on one
+// path, so a bad route merge fails here rather than at startup. The optional
+// layers are switched on so they get built too.
+#[test]
+fn the_full_router_builds() {
+    let config = Config {
+        max_concurrency: Some(4),
+        inactivity_pause: Some(Duration::from_secs(300)),
+        ..Config::default()
+    };
+    let _ = app(AppContext {
+        state: SharedState::default(),
+        hub: Arc::new(Hub::new(&config)),
+        config: Arc::new(config),
+        snapshot: ListSnapshot::default(),
+    });
+}
@@ -0,0 +1,639 @@
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode},
+};
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use super::{advance, id, receiver, Reply, TestApp};
+use crate::{
+    clock,
+    config::{Config, Rounding},
+    id::SessionId,
+    to_response, PomodoroSession, PomodoroState, MAX_METADATA_BYTES, MAX_METADATA_KEYS, MAX_TAGS,
+};
+
+// Handlers run on whichever worker picks them up, so events and metrics have
+// to reach this app's hub from every thread, and no other test's.
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_creates_get_unique_gapless_ids() {
+    let app = std::sync::Arc::new(TestApp::new());
+    let creates = (0..64).map(|_| {
+        let app = app.clone();
+        tokio::spawn(async move {
+            let body = json!({ "work_minutes": 25, "start_immediately": true });
+            let res = app.post_json("/sessions", body).await;
+            assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+            let id = id(&res.json());
+            let res = app.post(&format!("/sessions/{id}/cancel")).await;
+            assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+            id
+        })
+    });
+    let mut ids = Vec::new();
+    for create in creates.collect::<Vec<_>>() {
+        ids.push(create.await.unwrap());
+    }
+    ids.sort_unstable();
+    assert_eq!(ids, (1..=64).collect::<Vec<_>>());
+    assert_eq!(app.ctx.state.lock().unwrap().next_id, 64);
+
+    let metrics = app.get("/metrics").await.text();
+    assert!(metrics.contains("pomodoro_session_early_stop_seconds_count 64\n"), "{metrics}");
+    assert!(metrics.contains("pomodoro_session_duration_seconds_count 0\n"), "{metrics}");
+    let req = Request::get("/events").header("last-event-id", "0").body(Body::empty());
+    let res = app.router.clone().oneshot(req.unwrap()).await.unwrap();
+    let mut feed = res.into_body().into_data_stream();
+    let mut text = String::new();
+    while text.matches("\n\n").count() < 3 * 64 {
+        let chunk = tokio::time::timeout(Duration::from_secs(5), feed.next()).await;
+        text.push_str(std::str::from_utf8(&chunk.unwrap().unwrap().unwrap()).unwrap());
+    }
+    for kind in ["created", "started", "cancelled"] {
+        assert_eq!(text.matches(&format!("event: {kind}\n")).count(), 64, "{text}");
+    }
+}
+
+#[tokio::test]
+async fn get_is_built_from_the_stored_session_in_place() {
+    let app = TestApp::new();
+    let id = app.create_minutes(25).await;
+    app.act(id, "start").await;
+    advance(90);
+    let body = app.session(id).await;
+    let guard = app.ctx.state.lock().unwrap();
+    let stored: &PomodoroSession = &guard.sessions[&SessionId(id)];
+    assert_eq!(body, serde_json::to_value(to_response(stored)).unwrap());
+    assert_eq!(body["elapsed_secs"], 90);
+}
+
+#[test]
+fn states_display_for_humans() {
+    let shown = [
+        (PomodoroState::Idle, "idle"),
+        (PomodoroState::Running, "running"),
+        (PomodoroState::Break, "on break"),
+        (PomodoroState::Paused, "paused"),
+        (PomodoroState::Interrupted, "interrupted"),
+        (PomodoroState::Finished, "finished"),
+        (PomodoroState::Cancelled, "cancelled"),
+    ];
+    for (state, text) in shown {
+        assert_eq!(state.to_string(), text);
+    }
+    assert_eq!(format!("{:?}", PomodoroState::Break), "Break");
+}
+
+#[tokio::test]
+async fn start_immediately_creates_a_running_session() {
+    let app = TestApp::new();
+    let s = app.create(json!({ "work_minutes": 25, "start_immediately": true })).await;
+    assert_eq!(s["state"], "Running");
+    assert_eq!(s["remaining_secs"], 25 * 60);
+    advance(10);
+    assert_eq!(app.session(id(&s)).await["remaining_secs"], 25 * 60 - 10);
+    let idle = app.create(json!({ "work_minutes": 25 })).await;
+    assert_eq!(idle["state"], "Idle");
+}
+
+#[tokio::test]
+async fn get_or_create_returns_the_existing_session() {
+    let app = TestApp::new();
+    let body = json!({ "work_minutes": 25, "external_id": "task-7" });
+    let res = app.post_json("/sessions?get_or_create=true", body.clone()).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+    let first = id(&res.json());
+    let res = app.post_json("/sessions?get_or_create=true", body.clone()).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(id(&res.json()), first);
+    // Without the flag the same external id is a conflict.
+    let res = app.post_json("/sessions", body).await;
+    assert_eq!((res.status, res.error()), (StatusCode::CONFLICT, "duplicate_external_id".into()));
+    assert_eq!(app.ctx.state.lock().unwrap().sessions.len(), 1);
+}
+
+#[tokio::test]
+async fn metadata_is_set_and_merged() {
+    let app = TestApp::new();
+    let body = json!({ "work_minutes": 25, "metadata": { "project": "p1", "color": "red" } });
+    let s = app.create(body).await;
+    assert_eq!(s["metadata"], json!({ "project": "p1", "color": "red" }));
+    let patch = json!({ "metadata": { "project": "p2", "color": null, "lane": "a" } });
+    let res = app.patch_json(&format!("/sessions/{}", id(&s)), patch).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    assert_eq!(res.json()["metadata"], json!({ "project": "p2", "lane": "a" }));
+    assert_eq!(app.session(id(&s)).await["metadata"], json!({ "project": "p2", "lane": "a" }));
+}
+
+#[tokio::test]
+async fn metadata_is_capped() {
+    let app = TestApp::new();
+    let many: serde_json::Map<_, _> =
+        (0..=MAX_METADATA_KEYS).map(|n| (format!("k{n}"), json!("v"))).collect();
+    let res = app.post_json("/sessions", json!({ "work_minutes": 25, "metadata": many })).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", res.text());
+    let big = "x".repeat(MAX_METADATA_BYTES);
+    let body = json!({ "work_minutes": 25, "metadata": { "k": big } });
+    let res = app.post_json("/sessions", body).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", res.text());
+    // A merge that would cross the cap is refused and changes nothing.
+    let id = id(&app.create(json!({ "work_minutes": 25, "metadata": { "k": "v" } })).await);
+    let half = "x".repeat(MAX_METADATA_BYTES / 2);
+    let patch = json!({ "metadata": { "a": half, "b": half } });
+    let res = app.patch_json(&format!("/sessions/{id}"), patch).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", res.text());
+    assert_eq!(app.session(id).await["metadata"], json!({ "k": "v" }));
+}
+
+// PATCH holds fields to the rules create does and reports them the same way.
+#[tokio::test]
+async fn updates_are_validated_like_creates() {
+    let app = TestApp::new();
+    let id = app.create_minutes(25).await;
+    let path = format!("/sessions/{id}");
+    let tags: Vec<String> = (0..=MAX_TAGS).map(|n| format!("t{n}")).collect();
+    let bad = json!({
+        "title": "",
+        "user": "u".repeat(65),
+        "color": "red",
+        "notify_profile": "siren",
+        "tags": tags,
+    });
+    let res = app.patch_json(&path, bad.clone()).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", res.text());
+    let body = res.json();
+    assert_eq!(body["error"], "validation");
+    let mut fields: Vec<&String> = body["fields"].as_object().unwrap().keys().collect();
+    fields.sort();
+    assert_eq!(fields, ["color", "notify_profile", "tags", "title", "user"]);
+    let mut create = bad;
+    create["work_minutes"] = json!(25);
+    assert_eq!(app.post_json("/sessions", create).await.json()["fields"], body["fields"]);
+    // Nulls clear rather than fail, and tags are normalized as on create.
+    let patch = json!({ "title": null, "color": null, "tags": [" b ", "a", "b", ""] });
+    let res = app.patch_json(&path, patch).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    assert_eq!(res.json()["tags"], json!(["a", "b"]));
+    // A merge that crosses the metadata cap names the field too.
+    let half = "x".repeat(MAX_METADATA_BYTES / 2 + 1);
+    let res = app.patch_json(&path, json!({ "metadata": { "a": half, "b": half } })).await;
+    assert_eq!(res.json()["fields"]["metadata"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn malformed_ids_are_400_and_missing_ones_404() {
+    let app = TestApp::new();
+    for path in ["/sessions/abc", "/sessions/-1", "/sessions/1.5"] {
+        let res = app.get(path).await;
+        assert_eq!(res.status, StatusCode::BAD_REQUEST, "{path}");
+        assert_eq!(res.json(), json!({ "error": "invalid_id" }), "{path}");
+    }
+    let res = app.post("/sessions/abc/start").await;
+    assert_eq!((res.status, res.error()), (StatusCode::BAD_REQUEST, "invalid_id".into()));
+    let res = app.get("/sessions/9999").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert_eq!(res.json(), json!({ "error": "not_found" }));
+}
+
+#[tokio::test]
+async fn color_is_validated_and_can_be_cleared() {
+    let app = TestApp::new();
+    let s = app.create(json!({ "work_minutes": 25, "color": "#1A2b3C" })).await;
+    assert_eq!(s["color"], "#1A2b3C");
+    let path = format!("/sessions/{}", id(&s));
+    for bad in ["red", "#12345", "#1234567", "#GGGGGG", "123456"] {
+        let res = app.post_json("/sessions", json!({ "work_minutes": 25, "color": bad })).await;
+        assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY, "{bad}: {}", res.text());
+        let res = app.patch_json(&path, json!({ "color": bad })).await;
+        assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY, "{bad}: {}", res.text());
+    }
+    assert_eq!(app.session(id(&s)).await["color"], "#1A2b3C");
+    // Absent leaves it alone; null clears it.
+    assert_eq!(app.patch_json(&path, json!({ "title": "t" })).await.json()["color"], "#1A2b3C");
+    assert_eq!(app.patch_json(&path, json!({ "color": null })).await.json()["color"], Value::Null);
+}
+
+#[tokio::test]
+async fn index_serves_the_web_ui() {
+    let res = TestApp::new().get("/").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert!(res.header("content-type").is_some_and(|t| t.starts_with("text/html")));
+    let page = res.text();
+    assert!(page.starts_with("<!doctype html>") || page.starts_with("<!DOCTYPE html>"));
+    // It works off the JSON API, with the event stream for live countdowns.
+    assert!(page.contains("/sessions") && page.contains("EventSource"));
+}
+
+#[tokio::test]
+async fn continue_starts_a_copy_with_its_own_stats() {
+    let app = TestApp::new();
+    let body = json!({
+        "work_minutes": 10, "break_minutes": 2, "title": "write", "tags": ["deep"],
+        "color": "#112233", "metadata": { "project": "p1" }, "external_id": "task-1",
+    });
+    let source = id(&app.create(body).await);
+    app.act(source, "start").await;
+    app.act(source, "pause").await;
+    let res = app.post(&format!("/sessions/{source}/continue")).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+    let copy = res.json();
+    assert_ne!(id(&copy), source);
+    assert_eq!(copy["state"], "Running");
+    for field in ["work_minutes", "break_minutes", "title", "tags", "color", "metadata"] {
+        assert_eq!(copy[field], app.session(source).await[field], "{field}");
+    }
+    assert_eq!((&copy["external_id"], &copy["pauses"]), (&Value::Null, &json!(0)));
+    advance(60);
+    assert_eq!(app.session(id(&copy)).await["elapsed_secs"], 60);
+    let source = app.session(source).await;
+    assert_eq!((&source["state"], &source["elapsed_secs"]), (&json!("Paused"), &json!(0)));
+    assert_eq!(app.post("/sessions/9999/continue").await.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn every_invalid_field_is_reported_at_once() {
+    let app = TestApp::new();
+    let body = json!({
+        "work_minutes": 2000, "title": "", "color": "red", "tags": vec!["t"; 33],
+        "user": "u".repeat(65),
+    });
+    let res = app.post_json("/sessions", body).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", res.text());
+    assert_eq!(res.error(), "validation");
+    let fields = res.json()["fields"].as_object().unwrap().clone();
+    let mut names: Vec<_> = fields.keys().map(String::as_str).collect();
+    names.sort_unstable();
+    assert_eq!(names, ["color", "tags", "title", "user", "work_minutes"]);
+    assert_eq!(fields["work_minutes"], json!(["work_minutes may be at most 1440"]));
+    assert_eq!(fields["title"], json!(["title must be 1 to 200 bytes"]));
+    assert!(app.ctx.state.lock().unwrap().sessions.is_empty());
+}
+
+#[tokio::test]
+async fn listings_come_back_in_the_same_order_every_time() {
+    let app = TestApp::new();
+    for minutes in (1..=30).rev() {
+        app.create_minutes(minutes).await;
+    }
+    for id in [3, 17, 29] {
+        assert_eq!(app.delete(&format!("/sessions/{id}")).await.status, StatusCode::NO_CONTENT);
+    }
+    let ids = |list: Value| -> Vec<u64> { list.as_array().unwrap().iter().map(id).collect() };
+    let first = ids(app.get("/sessions").await.json());
+    let second = ids(app.get("/sessions").await.json());
+    assert_eq!(first, second);
+    let mut sorted = first.clone();
+    sorted.sort_unstable();
+    assert_eq!((first.len(), first), (27, sorted));
+    let page = |list: Value| ids(list["items"].clone());
+    let filtered = page(app.get("/sessions?state=Idle&limit=10").await.json());
+    assert_eq!(filtered, [1, 2, 4, 5, 6, 7, 8, 9, 10, 11]);
+}
+
+#[tokio::test]
+async fn notify_profile_is_kept_and_sent_with_the_finish() {
+    let (url, received) = receiver(0).await;
+    let mut app = TestApp::with(|config| config.webhook_url = Some(url));
+    let body = json!({ "work_minutes": 1, "break_minutes": 0, "notify_profile": "chime" });
+    let s = app.create(body).await;
+    assert_eq!(s["notify_profile"], "chime");
+    app.act(id(&s), "start").await;
+    advance(60);
+    app.tick();
+    app.drain_webhooks().await;
+    let sent: Value = serde_json::from_slice(&received.lock().unwrap()[0]).unwrap();
+    assert_eq!((&sent["state"], &sent["notify_profile"]), (&json!("Finished"), &json!("chime")));
+
+    let body = json!({ "work_minutes": 25, "notify_profile": "kazoo" });
+    let res = app.post_json("/sessions", body).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", res.text());
+    assert_eq!(
+        res.json()["fields"]["notify_profile"],
+        json!(["notify_profile must be one of bell, chime, silent"])
+    );
+}
+
+#[tokio::test]
+async fn one_listing_reads_every_session_at_the_same_instant() {
+    let app = TestApp::new();
+    let mut ids = Vec::new();
+    for _ in 0..5 {
+        let id = app.create_minutes(25).await;
+        app.act(id, "start").await;
+        ids.push(id);
+        advance(60);
+    }
+    advance(7);
+    let list = app.get("/sessions").await.json();
+    let list = list.as_array().unwrap();
+    // Started a minute apart, they are exactly a minute apart in the listing.
+    let elapsed: Vec<u64> = list.iter().map(|s| s["elapsed_secs"].as_u64().unwrap()).collect();
+    assert_eq!(elapsed, [307, 247, 187, 127, 67]);
+    let remaining = list.iter().map(|s| s["remaining_secs"].as_u64().unwrap());
+    assert!(elapsed.iter().zip(remaining).all(|(e, r)| e + r == 1500));
+}
+
+#[tokio::test]
+async fn qr_codes_are_png() {
+    let app = TestApp::new();
+    let id = app.create_minutes(25).await;
+    let res = app.get(&format!("/sessions/{id}/qr")).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.header("content-type"), Some("image/png"));
+    assert!(res.body.starts_with(b"\x89PNG\r\n\x1a\n"), "{} bytes", res.body.len());
+    assert_eq!(app.get("/sessions/9999/qr").await.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn clients_can_bring_their_own_uuid() {
+    let app = TestApp::new();
+    let uuid = "6f1c2b4e-3d5a-4e6f-8a9b-0c1d2e3f4a5b";
+    let s = app.create(json!({ "work_minutes": 25, "id": uuid })).await;
+    assert_eq!(s["client_id"], uuid);
+    let found = app.get(&format!("/sessions/by-client-id/{uuid}")).await;
+    assert_eq!((found.status, id(&found.json())), (StatusCode::OK, id(&s)));
+    let res = app.post_json("/sessions", json!({ "work_minutes": 5, "id": uuid })).await;
+    assert_eq!((res.status, res.error()), (StatusCode::CONFLICT, "duplicate_id".into()));
+    let res = app.post_json("/sessions", json!({ "work_minutes": 5, "id": "not-a-uuid" })).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(app.ctx.state.lock().unwrap().sessions.len(), 1);
+    // Deleting the session frees the UUID.
+    app.delete(&format!("/sessions/{}", id(&s))).await;
+    assert_eq!(app.create(json!({ "work_minutes": 5, "id": uuid })).await["client_id"], uuid);
+}
+
+#[tokio::test]
+async fn search_matches_titles_and_notes_case_insensitively() {
+    let app = TestApp::new();
+    let titled = json!({ "work_minutes": 25, "title": "Quarterly REPORT", "tags": ["work"] });
+    let titled = id(&app.create(titled).await);
+    let noted = json!({ "work_minutes": 25, "notes": "draft the report outline" });
+    let noted = id(&app.create(noted).await);
+    app.create(json!({ "work_minutes": 25, "title": "Inbox", "notes": "replies" })).await;
+    app.act(noted, "start").await;
+    let ids = |page: Value| -> Vec<u64> {
+        page["items"].as_array().unwrap().iter().map(id).collect()
+    };
+
+    let page = app.get("/sessions?q=report").await.json();
+    assert_eq!(page["total"], 2);
+    assert_eq!(ids(page), [titled, noted]);
+    assert_eq!(ids(app.get("/sessions?q=OUTLINE").await.json()), [noted]);
+    assert_eq!(ids(app.get("/sessions?q=report&state=Running").await.json()), [noted]);
+    assert_eq!(ids(app.get("/sessions?q=report&tag=work").await.json()), [titled]);
+    assert_eq!(ids(app.get("/sessions?q=report&offset=1&limit=1").await.json()), [noted]);
+
+    let none = app.get("/sessions?q=nothing-like-this").await;
+    assert_eq!(none.status, StatusCode::OK);
+    assert_eq!(none.json()["items"], json!([]));
+    assert_eq!(none.json()["total"], 0);
+}
+
+#[tokio::test]
+async fn end_at_sets_the_work_length_when_the_session_starts() {
+    let app = TestApp::new();
+    let stamp = |secs| {
+        let at = clock::utc() + chrono::TimeDelta::seconds(secs);
+        at.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true)
+    };
+    let end_at = stamp(30 * 60);
+    let s = app.create(json!({ "end_at": end_at })).await;
+    assert_eq!((&s["end_at"], &s["work_minutes"]), (&json!(end_at), &json!(30)));
+    // Started ten minutes late, it still ends on time.
+    advance(10 * 60);
+    let started = app.act(id(&s), "start").await;
+    assert_eq!(started["remaining_secs"], 20 * 60);
+    advance(20 * 60);
+    assert_eq!(app.session(id(&s)).await["state"], "Finished");
+
+    for body in [
+        json!({ "end_at": stamp(600), "work_minutes": 25 }),
+        json!({ "end_at": stamp(-60) }),
+        json!({ "end_at": stamp(25 * 3600) }),
+    ] {
+        let res = app.post_json("/sessions", body).await;
+        assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", res.text());
+    }
+}
+
+#[tokio::test]
+async fn a_repeated_delete_succeeds_within_the_tombstone_window() {
+    let app = TestApp::with(|c| c.delete_tombstone_ttl = Some(Duration::from_secs(60)));
+    let id = app.create_minutes(25).await;
+    let path = format!("/sessions/{id}");
+    assert_eq!(app.delete(&path).await.status, StatusCode::NO_CONTENT);
+    advance(59);
+    assert_eq!(app.delete(&path).await.status, StatusCode::NO_CONTENT);
+    // Still gone for reads; the tombstone only answers deletes.
+    assert_eq!(app.get(&path).await.status, StatusCode::NOT_FOUND);
+    advance(1);
+    assert_eq!(app.delete(&path).await.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn without_a_window_a_repeated_delete_is_not_found() {
+    let app = TestApp::new();
+    let id = app.create_minutes(25).await;
+    let path = format!("/sessions/{id}");
+    assert_eq!(app.delete(&path).await.status, StatusCode::NO_CONTENT);
+    assert_eq!(app.delete(&path).await.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn list_filters_combine() {
+    let app = TestApp::new();
+    let ids = |page: Value| -> Vec<u64> {
+        page["items"].as_array().unwrap().iter().map(id).collect()
+    };
+    let early = id(&app.create(json!({ "work_minutes": 25, "tags": ["work"] })).await);
+    advance(60);
+    let since = clock::utc().timestamp_millis();
+    let report = json!({ "work_minutes": 25, "tags": ["work"], "title": "report" });
+    let running = id(&app.create(report.clone()).await);
+    let idle = id(&app.create(report).await);
+    app.create(json!({ "work_minutes": 25, "tags": ["home"], "title": "report" })).await;
+    app.act(running, "start").await;
+
+    let page = app.get(&format!("/sessions?tag=work&created_after={since}&q=REPORT")).await;
+    assert_eq!(ids(page.json()), [running, idle]);
+    let page = app.get(&format!("/sessions?tag=work&created_after={since}&state=Idle")).await;
+    assert_eq!(ids(page.json()), [idle]);
+    let page = app.get(&format!("/sessions?tag=work&created_before={since}")).await;
+    assert_eq!(ids(page.json()), [early]);
+}
+
+#[tokio::test]
+async fn every_bad_list_param_is_reported_at_once() {
+    let app = TestApp::new();
+    let res = app.get("/sessions?state=Asleep&limit=-1&created_after=yesterday&format=xml").await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    let body = res.json();
+    assert_eq!(body["error"], "invalid_query");
+    let mut fields: Vec<&str> = body["fields"].as_object().unwrap().keys().map(|k| &**k).collect();
+    fields.sort_unstable();
+    assert_eq!(fields, ["created_after", "format", "limit", "state"]);
+    let limit = &body["fields"]["limit"];
+    assert_eq!(limit, &json!(["limit must be a non-negative integer"]));
+}
+
+#[tokio::test]
+async fn validate_checks_a_session_without_creating_it() {
+    let app = TestApp::new();
+    let valid = json!({ "work_minutes": 25, "title": "t" });
+    let res = app.post_json("/sessions/validate", valid).await;
+    assert_eq!((res.status, res.json()), (StatusCode::OK, json!({ "valid": true })));
+
+    let body = json!({ "work_minutes": 2000, "title": "", "color": "red" });
+    let invalid = app.post_json("/sessions/validate", body.clone()).await;
+    assert_eq!(invalid.status, StatusCode::UNPROCESSABLE_ENTITY);
+    // The same errors create would give.
+    assert_eq!(invalid.json(), app.post_json("/sessions", body).await.json());
+    assert_eq!(invalid.json()["fields"].as_object().unwrap().len(), 3);
+
+    // Nothing was stored and no id was used up.
+    assert!(app.ctx.state.lock().unwrap().sessions.is_empty());
+    assert_eq!(app.create_minutes(25).await, 1);
+}
+
+// `elapsed_secs` and `remaining_secs` 1.7s into a minute, 58.3s left. The
+// mode is fixed once per process (per test thread here), so one test each.
+async fn reported(mode: Rounding) -> (Value, Value) {
+    let app = TestApp::with(|c| c.rounding = mode);
+    let id = app.create_minutes(1).await;
+    app.act(id, "start").await;
+    clock::advance(Duration::from_millis(1700));
+    let s = app.session(id).await;
+    (s["elapsed_secs"].clone(), s["remaining_secs"].clone())
+}
+
+#[tokio::test]
+async fn reported_seconds_round_down_by_default() {
+    assert!(matches!(Config::default().rounding, Rounding::Floor));
+    assert_eq!(reported(Rounding::Floor).await, (json!(1), json!(58)));
+}
+
+#[tokio::test]
+async fn reported_seconds_can_round_up() {
+    assert_eq!(reported(Rounding::Ceil).await, (json!(2), json!(59)));
+}
+
+#[tokio::test]
+async fn reported_seconds_can_round_to_nearest() {
+    assert_eq!(reported(Rounding::Round).await, (json!(2), json!(58)));
+}
+
+// The ids of the sessions linked to calendar `event`.
+async fn linked(app: &TestApp, event: &str) -> Vec<u64> {
+    let page = app.get(&format!("/sessions?calendar_event_id={event}")).await.json();
+    page["items"].as_array().unwrap().iter().map(id).collect()
+}
+
+#[tokio::test]
+async fn sessions_can_be_found_by_calendar_event() {
+    let app = TestApp::new();
+    let standup = json!({ "work_minutes": 25, "calendar_event_id": "evt-standup" });
+    let first = app.create(standup.clone()).await;
+    assert_eq!(first["calendar_event_id"], "evt-standup");
+    let (first, second) = (id(&first), id(&app.create(standup).await));
+    let other = app.create_minutes(25).await;
+    assert_eq!(linked(&app, "evt-standup").await, [first, second]);
+
+    // Moving a session to another event, or unlinking it, updates the lookup.
+    let review = json!({ "calendar_event_id": "evt-review" });
+    let res = app.patch_json(&format!("/sessions/{second}"), review.clone()).await;
+    assert_eq!(res.json()["calendar_event_id"], "evt-review");
+    app.patch_json(&format!("/sessions/{other}"), review).await;
+    assert_eq!(linked(&app, "evt-standup").await, [first]);
+    assert_eq!(linked(&app, "evt-review").await, [second, other]);
+    let unlink = json!({ "calendar_event_id": null });
+    app.patch_json(&format!("/sessions/{other}"), unlink).await;
+    app.delete(&format!("/sessions/{first}")).await;
+    assert_eq!(linked(&app, "evt-review").await, [second]);
+    assert!(linked(&app, "evt-standup").await.is_empty());
+}
+
+#[test]
+fn session_ids_are_bare_numbers_outside_the_code() {
+    assert_eq!(serde_json::to_value(SessionId(7)).unwrap(), json!(7));
+    assert_eq!(serde_json::from_value::<SessionId>(json!(7)).unwrap(), SessionId(7));
+    assert!(serde_json::from_value::<SessionId>(json!("7")).is_err());
+    assert_eq!("42".parse(), Ok(SessionId(42)));
+    assert!("4x".parse::<SessionId>().is_err());
+    assert_eq!(SessionId(42).to_string(), "42");
+}
+
+#[tokio::test]
+async fn ids_in_bodies_and_paths_agree() {
+    let app = TestApp::new();
+    let first = app.create_minutes(25).await;
+    let body = json!({ "work_minutes": 25, "depends_on": first });
+    let second = app.create(body).await;
+    assert_eq!(second["depends_on"], first);
+    let path = format!("/sessions/{}", second["id"]);
+    assert_eq!(app.get(&path).await.json()["id"], second["id"]);
+    let res = app.post_json("/sessions", json!({ "work_minutes": 25, "depends_on": "1" })).await;
+    assert_eq!((res.status, res.error()), (StatusCode::BAD_REQUEST, "malformed".into()));
+}
+
+// The (session, event) pairs on the activity feed, in its order.
+async fn activity(app: &TestApp, limit: usize) -> Vec<(u64, String)> {
+    let feed = app.get(&format!("/activity?limit={limit}")).await.json();
+    let entry = |e: &Value| (id(e), e["event"].as_str().unwrap().to_string());
+    feed.as_array().unwrap().iter().map(entry).collect()
+}
+
+#[tokio::test]
+async fn activity_merges_every_history_newest_first() {
+    let app = TestApp::new();
+    let (a, b) = (app.create_minutes(25).await, app.create_minutes(25).await);
+    let steps = [(a, "start"), (b, "start"), (a, "pause"), (b, "pause"), (a, "resume")];
+    for (id, action) in steps {
+        advance(10);
+        app.act(id, action).await;
+    }
+    let newest = [(a, "resumed"), (b, "paused"), (a, "paused"), (b, "started"), (a, "started")];
+    let newest: Vec<_> = newest.iter().map(|&(id, event)| (id, event.to_string())).collect();
+    assert_eq!(activity(&app, 20).await, newest);
+    assert_eq!(activity(&app, 2).await, newest[..2]);
+    assert_eq!(app.get("/activity?limit=201").await.status, StatusCode::BAD_REQUEST);
+}
+
+// The ids of the sessions listed under `tag`.
+async fn tagged(app: &TestApp, tag: &str) -> Vec<u64> {
+    let page = app.get(&format!("/sessions?tag={tag}")).await.json();
+    page["items"].as_array().unwrap().iter().map(id).collect()
+}
+
+async fn untag(app: &TestApp, path: &str, tag: &str) -> Reply {
+    app.call(Method::DELETE, path, Some(&json!({ "tag": tag }))).await
+}
+
+#[tokio::test]
+async fn tags_can_be_replaced_added_and_removed() {
+    let app = TestApp::new();
+    let session = id(&app.create(json!({ "work_minutes": 25, "tags": ["old"] })).await);
+    let other = id(&app.create(json!({ "work_minutes": 25, "tags": ["b"] })).await);
+    let path = format!("/sessions/{session}/tags");
+
+    let res = app.put_json(&path, json!({ "tags": [" b", "a", "b", ""] })).await;
+    assert_eq!(res.json()["tags"], json!(["a", "b"]));
+    assert!(tagged(&app, "old").await.is_empty());
+    assert_eq!(tagged(&app, "b").await, [session, other]);
+
+    let res = app.post_json(&path, json!({ "tag": " c " })).await;
+    assert_eq!(res.json()["tags"], json!(["a", "b", "c"]));
+    let again = app.post_json(&path, json!({ "tag": "c" })).await;
+    assert_eq!(again.json()["tags"], json!(["a", "b", "c"]));
+    assert_eq!(tagged(&app, "c").await, [session]);
+
+    assert_eq!(untag(&app, &path, "b").await.json()["tags"], json!(["a", "c"]));
+    assert_eq!(tagged(&app, "b").await, [other]);
+    // Removing a tag that isn't there changes nothing.
+    let res = untag(&app, &path, "zzz").await;
+    assert_eq!((res.status, &res.json()["tags"]), (StatusCode::OK, &json!(["a", "c"])));
+    let blank = app.post_json(&path, json!({ "tag": "  " })).await;
+    assert_eq!(blank.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
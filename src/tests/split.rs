@@ -0,0 +1,65 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use super::{id, TestApp};
+
+async fn split(app: &TestApp, id: u64, parts: u64) -> Vec<Value> {
+    let res = app.post_json(&format!("/sessions/{id}/split"), json!({ "parts": parts })).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+    res.json().as_array().unwrap().clone()
+}
+
+#[tokio::test]
+async fn splits_into_equal_chained_parts() {
+    let app = TestApp::new();
+    let source = app.create(json!({ "work_minutes": 90, "title": "deep work" })).await;
+    let parts = split(&app, id(&source), 3).await;
+    let minutes: Vec<_> = parts.iter().map(|p| p["work_minutes"].as_u64().unwrap()).collect();
+    assert_eq!(minutes, [30, 30, 30]);
+    let ids: Vec<_> = parts.iter().map(id).collect();
+    assert_eq!(parts[0]["next_session"], ids[1]);
+    assert_eq!(parts[1]["next_session"], ids[2]);
+    assert_eq!(parts[2]["next_session"], Value::Null);
+    assert!(parts.iter().all(|p| p["split_from"] == id(&source) && p["title"] == "deep work"));
+    let gone = app.get(&format!("/sessions/{}", id(&source))).await;
+    assert_eq!(gone.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn the_last_part_takes_the_remainder() {
+    let app = TestApp::new();
+    let id = app.create_minutes(50).await;
+    let minutes: Vec<_> =
+        split(&app, id, 3).await.iter().map(|p| p["work_minutes"].as_u64().unwrap()).collect();
+    assert_eq!(minutes, [16, 16, 18]);
+}
+
+#[tokio::test]
+async fn only_idle_sessions_split() {
+    let app = TestApp::new();
+    let id = app.create_minutes(90).await;
+    app.act(id, "start").await;
+    let res = app.post_json(&format!("/sessions/{id}/split"), json!({ "parts": 3 })).await;
+    assert_eq!((res.status, res.error()), (StatusCode::CONFLICT, "not_idle".into()));
+}
+
+#[tokio::test]
+async fn dependents_wait_on_the_last_part() {
+    let app = TestApp::new();
+    let source = app.create_minutes(60).await;
+    let after = id(&app.create(json!({ "work_minutes": 25, "depends_on": source })).await);
+    let parts = split(&app, source, 2).await;
+    assert_eq!(app.session(after).await["depends_on"], id(&parts[1]));
+}
+
+#[tokio::test]
+async fn nothing_splits_during_maintenance() {
+    let app = TestApp::new();
+    let id = app.create_minutes(90).await;
+    app.post_json("/admin/maintenance", json!({ "enabled": true })).await;
+    let res = app.post_json(&format!("/sessions/{id}/split"), json!({ "parts": 3 })).await;
+    assert_eq!((res.status, res.error()), (StatusCode::SERVICE_UNAVAILABLE, "maintenance".into()));
+    assert_eq!(app.session(id).await["work_minutes"], 90);
+    app.post_json("/admin/maintenance", json!({ "enabled": false })).await;
+    assert_eq!(split(&app, id, 3).await.len(), 3);
+}
@@ -0,0 +1,154 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use axum::http::StatusCode;
+
+use super::{advance, id, TestApp};
+
+// Holds the state lock on another thread until the returned sender is dropped.
+fn contend(app: &TestApp) -> (mpsc::Sender<()>, thread::JoinHandle<()>) {
+    let (locked, is_locked) = mpsc::channel();
+    let (release, released) = mpsc::channel::<()>();
+    let state = app.ctx.state.clone();
+    let holder = thread::spawn(move || {
+        let _guard = state.lock().unwrap();
+        locked.send(()).unwrap();
+        let _ = released.recv();
+    });
+    is_locked.recv().unwrap();
+    (release, holder)
+}
+
+#[tokio::test]
+async fn a_busy_lock_serves_the_last_list_marked_stale() {
+    let app = TestApp::with(|c| c.stale_read_bound = Some(Duration::from_secs(5)));
+    let id = app.create_minutes(25).await;
+    app.act(id, "start").await;
+    // A locked read refreshes the snapshot.
+    assert_eq!(app.get("/sessions").await.header("x-stale"), None);
+    advance(3);
+
+    let (release, holder) = contend(&app);
+    let list = app.get("/sessions").await;
+    assert_eq!((list.status, list.header("x-stale")), (StatusCode::OK, Some("true")));
+    assert_eq!(list.json()[0]["elapsed_secs"], 0);
+    let one = app.get(&format!("/sessions/{id}")).await;
+    assert_eq!(one.header("x-stale"), Some("true"));
+    assert_eq!(one.json()["elapsed_secs"], 0);
+    drop(release);
+    holder.join().unwrap();
+
+    let list = app.get("/sessions").await;
+    assert_eq!(list.header("x-stale"), None);
+    assert_eq!(list.json()[0]["elapsed_secs"], 3);
+}
+
+#[tokio::test]
+async fn a_snapshot_past_the_bound_waits_for_the_lock() {
+    let app = TestApp::with(|c| c.stale_read_bound = Some(Duration::from_secs(5)));
+    let id = app.create_minutes(25).await;
+    app.act(id, "start").await;
+    app.get("/sessions").await;
+    advance(6);
+
+    let (release, holder) = contend(&app);
+    let releaser = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        drop(release);
+    });
+    let list = app.get("/sessions").await;
+    assert_eq!(list.header("x-stale"), None);
+    assert_eq!(list.json()[0]["elapsed_secs"], 6);
+    releaser.join().unwrap();
+    holder.join().unwrap();
+}
+
+// (id, state, elapsed_secs) for each listed session.
+async fn listed(app: &TestApp) -> Vec<(u64, String, u64)> {
+    let list = app.get("/sessions").await.json();
+    list.as_array()
+        .unwrap()
+        .iter()
+        .map(|s| {
+            let state = s["state"].as_str().unwrap().to_string();
+            (id(s), state, s["elapsed_secs"].as_u64().unwrap())
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn the_list_snapshot_trails_changes_by_one_tick() {
+    let mut app = TestApp::with(|c| c.list_snapshot = true);
+    let a = app.create_minutes(25).await;
+    // Before the first tick there's nothing to serve, so the lock is taken.
+    assert_eq!(listed(&app).await, [(a, "Idle".into(), 0)]);
+    app.tick();
+
+    app.act(a, "start").await;
+    let b = app.create_minutes(25).await;
+    assert_eq!(listed(&app).await, [(a, "Idle".into(), 0)]);
+    app.tick();
+    assert_eq!(listed(&app).await, [(a, "Running".into(), 0), (b, "Idle".into(), 0)]);
+
+    advance(30);
+    assert_eq!(listed(&app).await[0].2, 0);
+    app.tick();
+    assert_eq!(listed(&app).await[0].2, 30);
+
+    app.delete(&format!("/sessions/{b}")).await;
+    assert_eq!(listed(&app).await.len(), 2);
+    app.tick();
+    assert_eq!(listed(&app).await, [(a, "Running".into(), 30)]);
+
+    // Served without touching the lock, however long it's held.
+    let (release, holder) = contend(&app);
+    assert_eq!(listed(&app).await, [(a, "Running".into(), 30)]);
+    drop(release);
+    holder.join().unwrap();
+}
+
+// Lists `rounds` times while another thread keeps taking the lock for a
+// millisecond at a time, as a busy writer would.
+async fn list_under_writes(app: &TestApp, rounds: u32) -> Duration {
+    let stop = Arc::new(AtomicBool::new(false));
+    let writer = {
+        let (state, stop) = (app.ctx.state.clone(), stop.clone());
+        thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                let _guard = state.lock().unwrap();
+                thread::sleep(Duration::from_millis(1));
+            }
+        })
+    };
+    let started = Instant::now();
+    for _ in 0..rounds {
+        assert_eq!(app.get("/sessions").await.status, StatusCode::OK);
+    }
+    let took = started.elapsed();
+    stop.store(true, Ordering::Relaxed);
+    writer.join().unwrap();
+    took
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "a timing measurement; run with --ignored --nocapture"]
+async fn listing_under_contention() {
+    let mut timings = Vec::new();
+    for list_snapshot in [false, true] {
+        let mut app = TestApp::with(|c| c.list_snapshot = list_snapshot);
+        for _ in 0..200 {
+            app.create_minutes(25).await;
+        }
+        app.tick();
+        let took = list_under_writes(&app, 500).await;
+        println!("list_snapshot={list_snapshot}: 500 listings of 200 sessions in {took:?}");
+        timings.push(took);
+    }
+    assert!(timings[1] < timings[0], "{timings:?}");
+}
@@ -0,0 +1,283 @@
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use chrono::Timelike;
+use serde_json::json;
+
+use super::{advance, id, TestApp};
+use crate::{clock, id::SessionId};
+
+#[tokio::test]
+async fn remaining_sums_active_sessions_without_touching_them() {
+    let app = TestApp::new();
+    let long = id(&app.create(json!({ "work_minutes": 10, "break_minutes": 5 })).await);
+    app.act(long, "start").await;
+    advance(120);
+    let short = id(&app.create(json!({ "work_minutes": 2, "break_minutes": 0 })).await);
+    app.act(short, "start").await;
+    // Neither paused nor never-started sessions count.
+    let paused = app.create_minutes(25).await;
+    app.act(paused, "start").await;
+    app.act(paused, "pause").await;
+    app.create_minutes(25).await;
+    advance(60);
+
+    let stored = || app.ctx.state.lock().unwrap().sessions[&SessionId(long)].elapsed;
+    let before = stored();
+    let stats = app.get("/stats/remaining").await.json();
+    assert_eq!(
+        stats,
+        json!({
+            "active_sessions": 2,
+            "total_remaining_secs": (15 * 60 - 180) + (2 * 60 - 60),
+            "min_remaining_secs": 60,
+        })
+    );
+    assert_eq!(stored(), before);
+}
+
+#[tokio::test]
+async fn remaining_with_nothing_active() {
+    let app = TestApp::new();
+    app.create_minutes(25).await;
+    let stats = app.get("/stats/remaining").await.json();
+    assert_eq!(
+        stats,
+        json!({ "active_sessions": 0, "total_remaining_secs": 0, "min_remaining_secs": null })
+    );
+}
+
+#[tokio::test]
+async fn a_long_pause_counts_as_abandoned() {
+    let app = TestApp::with(|config| config.abandon_after = Duration::from_secs(600));
+    let id = app.create_minutes(25).await;
+    app.act(id, "start").await;
+    app.act(id, "pause").await;
+    let resumed = app.create_minutes(25).await;
+    app.act(resumed, "start").await;
+    app.act(resumed, "pause").await;
+    advance(599);
+    assert_eq!(app.get("/stats").await.json()["abandoned"], 0);
+    app.act(resumed, "resume").await;
+    advance(1);
+    let stats = app.get("/stats").await.json();
+    assert_eq!(stats["abandoned"], 1);
+    // Still paused as far as the session goes; abandonment is only a classification.
+    assert_eq!(stats["by_state"]["Paused"], 1);
+    app.act(id, "resume").await;
+    assert_eq!(app.get("/stats").await.json()["abandoned"], 0);
+}
+
+#[tokio::test]
+async fn compare_diffs_two_finished_sessions() {
+    let app = TestApp::new();
+    let a = app.create_minutes(10).await;
+    app.act(a, "start").await;
+    advance(60);
+    app.act(a, "pause").await;
+    advance(60);
+    app.act(a, "resume").await;
+    advance(9 * 60);
+    app.session(a).await;
+    let b = app.create_minutes(25).await;
+    app.act(b, "start").await;
+    advance(25 * 60);
+    app.session(b).await;
+    let version = app.ctx.state.lock().unwrap().version;
+
+    let res = app.get(&format!("/sessions/compare?a={a}&b={b}")).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    let body = res.json();
+    assert_eq!([&body["a"]["state"], &body["b"]["state"]], ["Finished", "Finished"]);
+    let (a_score, b_score) = (&body["a"]["focus_score"], &body["b"]["focus_score"]);
+    let score_diff = b_score.as_i64().unwrap() - a_score.as_i64().unwrap();
+    assert_eq!(
+        body["diff"],
+        json!({
+            "work_minutes": 15, "break_minutes": 0, "elapsed_secs": 15 * 60,
+            "focus_score": score_diff, "pauses": -1, "interruptions": 0,
+        })
+    );
+    assert_eq!(app.ctx.state.lock().unwrap().version, version);
+
+    let res = app.get(&format!("/sessions/compare?a={a}&b=9999")).await;
+    assert_eq!((res.status, res.error()), (StatusCode::NOT_FOUND, "not_found".into()));
+}
+
+// Finishes one-minute sessions side by side and returns the UTC day they finished on.
+async fn finish_together(app: &TestApp, count: usize) -> String {
+    let mut ids = Vec::new();
+    for _ in 0..count {
+        let id = app.create_minutes(1).await;
+        app.act(id, "start").await;
+        ids.push(id);
+    }
+    advance(60);
+    for id in ids {
+        assert_eq!(app.session(id).await["state"], "Finished");
+    }
+    clock::utc().date_naive().to_string()
+}
+
+#[tokio::test]
+async fn daily_buckets_every_day_including_empty_ones() {
+    let app = TestApp::new();
+    let first = finish_together(&app, 1).await;
+    advance(2 * 86_400);
+    let last = finish_together(&app, 2).await;
+    let running = app.create_minutes(25).await;
+    app.act(running, "start").await;
+
+    let res = app.get("/stats/daily?days=4").await;
+    assert_eq!(res.status, StatusCode::OK);
+    let buckets = res.json();
+    let buckets = buckets.as_array().unwrap();
+    assert_eq!(buckets.len(), 4);
+    assert_eq!(buckets[3]["date"], last);
+    for bucket in buckets {
+        let date = bucket["date"].as_str().unwrap();
+        let completed = usize::from(date == first) + 2 * usize::from(date == last);
+        assert_eq!(bucket["sessions_completed"], completed, "{date}");
+        assert_eq!(bucket["focus_secs"], 60 * completed, "{date}");
+    }
+    let total: u64 = buckets.iter().map(|b| b["sessions_completed"].as_u64().unwrap()).sum();
+    assert_eq!(total, 3);
+}
+
+#[tokio::test]
+async fn daily_counts_a_session_that_ran_out_since_the_last_tick() {
+    let app = TestApp::new();
+    let id = app.create_minutes(1).await;
+    app.act(id, "start").await;
+    advance(90);
+    let day = (clock::utc() - chrono::TimeDelta::seconds(30)).date_naive().to_string();
+    let buckets = app.get("/stats/daily?days=2").await.json();
+    let bucket = buckets.as_array().unwrap().iter().find(|b| b["date"] == day).unwrap();
+    assert_eq!((&bucket["sessions_completed"], &bucket["focus_secs"]), (&json!(1), &json!(60)));
+}
+
+#[tokio::test]
+async fn daily_days_is_bounded() {
+    let app = TestApp::new();
+    for days in [0, 367] {
+        let res = app.get(&format!("/stats/daily?days={days}")).await;
+        assert_eq!(res.status, StatusCode::BAD_REQUEST);
+        assert_eq!(res.error(), "invalid_query");
+    }
+    assert_eq!(app.get("/stats/daily?days=366").await.json().as_array().unwrap().len(), 366);
+}
+
+#[tokio::test]
+async fn busy_until_is_the_furthest_projected_finish() {
+    let app = TestApp::new();
+    assert_eq!(app.get("/stats/busy-until").await.json(), json!({ "busy_until": null }));
+    // Ends first on work, but its break carries it past the other.
+    let with_break = id(&app.create(json!({ "work_minutes": 10, "break_minutes": 10 })).await);
+    app.act(with_break, "start").await;
+    advance(60);
+    let longer_work = app.create_minutes(15).await;
+    app.act(longer_work, "start").await;
+    let paused = app.create_minutes(60).await;
+    app.act(paused, "start").await;
+    app.act(paused, "pause").await;
+
+    let stored = || app.ctx.state.lock().unwrap().sessions[&SessionId(with_break)].elapsed;
+    let before = stored();
+    let expected = clock::utc() + chrono::TimeDelta::seconds(20 * 60 - 60);
+    let busy = app.get("/stats/busy-until").await.json();
+    let busy: chrono::DateTime<chrono::Utc> = busy["busy_until"].as_str().unwrap().parse().unwrap();
+    assert!((busy - expected).num_seconds().abs() <= 1, "{busy} vs {expected}");
+    assert_eq!(stored(), before);
+}
+
+// Finishes a session of `minutes` for `user`.
+async fn finish_for(app: &TestApp, user: Option<&str>, minutes: u64) {
+    let s = app.create(json!({ "work_minutes": minutes, "user": user })).await;
+    app.act(id(&s), "start").await;
+    advance(minutes * 60);
+    assert_eq!(app.session(id(&s)).await["state"], "Finished");
+}
+
+// The users on a leaderboard, checking their ranks run 1, 2, ... as listed.
+async fn ranked(app: &TestApp, query: &str) -> Vec<String> {
+    let res = app.get(&format!("/leaderboard?{query}")).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    let board = res.json();
+    let entries = board["entries"].as_array().unwrap();
+    assert!(entries.iter().zip(1..).all(|(e, rank)| e["rank"] == rank), "{board}");
+    entries.iter().map(|e| e["user"].as_str().unwrap().to_string()).collect()
+}
+
+#[tokio::test]
+async fn leaderboard_ranks_users_within_the_period() {
+    let app = TestApp::new();
+    // Noon UTC two days on, so nothing below straddles midnight.
+    let now = clock::utc();
+    let noon = (now.date_naive() + chrono::Days::new(2)).and_hms_opt(12, 0, 0).unwrap();
+    advance((noon.and_utc() - now).num_seconds() as u64);
+    finish_for(&app, Some("ana"), 10).await;
+    advance(86_400);
+    finish_for(&app, Some("bo"), 1).await;
+    finish_for(&app, Some("bo"), 2).await;
+    finish_for(&app, None, 30).await;
+    let unfinished = app.create(json!({ "work_minutes": 25, "user": "cy" })).await;
+    app.act(id(&unfinished), "start").await;
+
+    assert_eq!(ranked(&app, "period=all").await, ["bo", "ana"]);
+    assert_eq!(ranked(&app, "period=all&by=focus_minutes").await, ["ana", "bo"]);
+    assert_eq!(ranked(&app, "period=today").await, ["bo"]);
+
+    let res = app.get("/leaderboard?period=month").await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(res.error(), "invalid_query");
+}
+
+// The hours with anything credited to them, as (hour, focus_secs, sessions_completed).
+async fn busy_hours(app: &TestApp, query: &str) -> Vec<(u64, u64, u64)> {
+    let res = app.get(&format!("/stats/hourly{query}")).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    let buckets = res.json();
+    let buckets = buckets.as_array().unwrap();
+    assert_eq!(buckets.len(), 24);
+    let field = |b: &serde_json::Value, name: &str| b[name].as_u64().unwrap();
+    buckets
+        .iter()
+        .filter(|b| field(b, "sessions_completed") > 0)
+        .map(|b| (field(b, "hour"), field(b, "focus_secs"), field(b, "sessions_completed")))
+        .collect()
+}
+
+#[tokio::test]
+async fn hourly_stats_credit_the_finishing_hour_in_the_requested_offset() {
+    let app = TestApp::new();
+    // 09:40 UTC two days on.
+    let now = clock::utc();
+    let start = (now.date_naive() + chrono::Days::new(2)).and_hms_opt(9, 40, 0).unwrap();
+    advance((start.and_utc() - now).num_seconds() as u64);
+    finish_for(&app, None, 10).await;
+    finish_for(&app, None, 5).await;
+    advance(13 * 3600 + 25 * 60);
+    finish_for(&app, None, 20).await;
+    let unfinished = app.create_minutes(25).await;
+    app.act(unfinished, "start").await;
+
+    let utc = [(9, 900, 2), (23, 1200, 1)];
+    assert_eq!(busy_hours(&app, "").await, utc);
+    assert_eq!(busy_hours(&app, "?tz=UTC").await, utc);
+    assert_eq!(busy_hours(&app, "?tz=%2B02:00").await, [(1, 1200, 1), (11, 900, 2)]);
+    assert_eq!(busy_hours(&app, "?tz=-05:30").await, [(4, 900, 2), (18, 1200, 1)]);
+
+    let res = app.get("/stats/hourly?tz=Mars").await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(res.error(), "invalid_query");
+}
+
+#[tokio::test]
+async fn hourly_counts_a_session_that_ran_out_since_the_last_tick() {
+    let app = TestApp::new();
+    let id = app.create_minutes(1).await;
+    app.act(id, "start").await;
+    advance(90);
+    let hour = u64::from((clock::utc() - chrono::TimeDelta::seconds(30)).hour());
+    assert_eq!(busy_hours(&app, "").await, [(hour, 60, 1)]);
+}
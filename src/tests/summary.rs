@@ -0,0 +1,64 @@
+use chrono::NaiveTime;
+use serde_json::{json, Value};
+
+use super::{advance, id, receiver, tomorrow_at, Received, TestApp};
+use crate::{clock, config::Config, summary::SummaryPush, webhook};
+
+async fn push(app: &TestApp, skip_empty: bool) -> (SummaryPush, Received) {
+    let (url, received) = receiver(0).await;
+    let webhook = webhook::Webhook::new(&Config::default(), &app.ctx.hub.deliveries, url);
+    let at = NaiveTime::from_hms_opt(18, 0, 0).unwrap();
+    (SummaryPush::new(app.ctx.state.clone(), webhook, at, skip_empty), received)
+}
+
+fn delivered(received: &Received) -> Vec<Value> {
+    let bodies = received.lock().unwrap();
+    bodies.iter().map(|body| serde_json::from_slice(body).unwrap()).collect()
+}
+
+#[tokio::test]
+async fn the_days_summary_goes_out_at_the_configured_time() {
+    tomorrow_at(9, 0);
+    let app = TestApp::new();
+    let (mut push, received) = push(&app, false).await;
+    for minutes in [10, 15] {
+        let id = id(&app.create(json!({ "work_minutes": minutes, "break_minutes": 0 })).await);
+        app.act(id, "start").await;
+        advance(minutes * 60);
+    }
+    let unfinished = app.create_minutes(25).await;
+    app.act(unfinished, "start").await;
+    app.act(unfinished, "pause").await;
+
+    // 09:25 now; one second short of 18:00 nothing is due.
+    advance(8 * 3600 + 35 * 60 - 1);
+    push.push_due().unwrap();
+    app.drain_webhooks().await;
+    assert!(delivered(&received).is_empty());
+    advance(1);
+    push.push_due().unwrap();
+    app.drain_webhooks().await;
+    let date = clock::local().date_naive().to_string();
+    assert_eq!(
+        delivered(&received),
+        [json!({ "date": date, "sessions_completed": 2, "focus_minutes": 25 })]
+    );
+    // Once a day: the next one is tomorrow's.
+    push.push_due().unwrap();
+    app.drain_webhooks().await;
+    assert_eq!(delivered(&received).len(), 1);
+}
+
+#[tokio::test]
+async fn empty_days_are_skipped_when_configured() {
+    tomorrow_at(9, 0);
+    let app = TestApp::new();
+    let (mut skipping, skipped) = push(&app, true).await;
+    let (mut sending, sent) = push(&app, false).await;
+    advance(9 * 3600);
+    skipping.push_due().unwrap();
+    sending.push_due().unwrap();
+    app.drain_webhooks().await;
+    assert!(delivered(&skipped).is_empty());
+    assert_eq!(delivered(&sent)[0]["sessions_completed"], 0);
+}
@@ -0,0 +1,83 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use super::{advance, TestApp};
+use crate::{id::SessionId, run_ticker, supervise, PomodoroState, Ticker};
+
+#[tokio::test]
+async fn a_ticker_that_panics_is_restarted() {
+    let app = TestApp::new();
+    let id = app.create_minutes(1).await;
+    app.act(id, "start").await;
+    advance(60);
+    let stored = || app.ctx.state.lock().unwrap().sessions[&SessionId(id)].state;
+    assert_eq!(stored(), PomodoroState::Running);
+
+    // The first run dies before it gets to tick.
+    let runs = Arc::new(AtomicUsize::new(0));
+    let ctx = app.ctx.clone();
+    supervise::spawn(ctx.hub.tasks.clone(), "ticker", move || {
+        let ticker = Ticker::new(&ctx);
+        let run = runs.fetch_add(1, Ordering::SeqCst);
+        async move {
+            if run == 0 {
+                panic!("tick failed");
+            }
+            run_ticker(ticker).await
+        }
+    });
+    let settled = async {
+        while stored() != PomodoroState::Finished {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(5), settled).await.unwrap();
+
+    let res = app.get("/healthz").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(
+        res.json()["tasks"]["ticker"],
+        json!({ "running": true, "restarts": 1, "last_error": "tick failed" })
+    );
+    assert_eq!(app.session(id).await["state"], "Finished");
+}
+
+// Restarts don't unpoison the state lock; the ticker keeps stopping at it,
+// and `/healthz` keeps counting.
+#[tokio::test]
+async fn a_ticker_that_poisons_the_state_stays_down() {
+    let app = TestApp::new();
+    let runs = Arc::new(AtomicUsize::new(0));
+    let ctx = app.ctx.clone();
+    supervise::spawn(ctx.hub.tasks.clone(), "ticker", move || {
+        let ticker = Ticker::new(&ctx);
+        let (state, run) = (ctx.state.clone(), runs.fetch_add(1, Ordering::SeqCst));
+        async move {
+            if run == 0 {
+                let _guard = state.lock().unwrap();
+                panic!("tick failed");
+            }
+            run_ticker(ticker).await
+        }
+    });
+    let restarted = async {
+        loop {
+            let ticker = app.get("/healthz").await.json()["tasks"]["ticker"].clone();
+            if ticker["restarts"].as_u64() >= Some(2) {
+                return ticker;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    };
+    let ticker = tokio::time::timeout(Duration::from_secs(5), restarted).await.unwrap();
+    assert_eq!(ticker["last_error"], "task exited");
+    assert!(app.ctx.state.is_poisoned());
+}
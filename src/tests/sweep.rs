@@ -0,0 +1,147 @@
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use super::{advance, id, TestApp};
+
+#[tokio::test]
+async fn only_never_started_idle_sessions_expire() {
+    let mut app = TestApp::with(|config| config.idle_ttl = Some(Duration::from_secs(3600)));
+    let untouched = app.create_minutes(25).await;
+    // Back to Idle through the auto reset, having run once.
+    let body = json!({ "work_minutes": 1, "break_minutes": 0, "auto_reset_after": 1 });
+    let reset = id(&app.create(body).await);
+    app.act(reset, "start").await;
+    advance(60);
+    app.tick();
+    advance(1);
+    app.tick();
+    assert_eq!(app.session(reset).await["state"], "Idle");
+
+    advance(3600 - 61 - 1);
+    assert_eq!(app.post("/admin/sweep").await.json()["swept"], json!([]));
+    advance(1);
+    assert_eq!(app.post("/admin/sweep").await.json()["swept"], json!([untouched]));
+    let gone = app.get(&format!("/sessions/{untouched}")).await;
+    assert_eq!(gone.status, StatusCode::NOT_FOUND);
+    assert_eq!(app.session(reset).await["state"], "Idle");
+}
+
+#[tokio::test]
+async fn idle_sessions_stay_without_a_ttl() {
+    let app = TestApp::new();
+    let id = app.create_minutes(25).await;
+    advance(365 * 86_400);
+    assert_eq!(app.post("/admin/sweep").await.json()["swept"], json!([]));
+    assert_eq!(app.session(id).await["state"], "Idle");
+}
+
+#[tokio::test]
+async fn pinned_sessions_survive_the_sweep() {
+    let mut app = TestApp::with(|config| config.finished_ttl = Some(Duration::from_secs(600)));
+    let one_minute = json!({ "work_minutes": 1, "break_minutes": 0 });
+    let unpinned = id(&app.create(one_minute.clone()).await);
+    let mut body = one_minute;
+    body["pinned"] = json!(true);
+    let pinned = id(&app.create(body).await);
+    // Pinning later, through PATCH, counts the same.
+    let patched = id(&app.create(json!({ "work_minutes": 1, "break_minutes": 0 })).await);
+    let res = app.patch_json(&format!("/sessions/{patched}"), json!({ "pinned": true })).await;
+    assert_eq!(res.json()["pinned"], true);
+    for id in [unpinned, pinned, patched] {
+        app.act(id, "start").await;
+    }
+    advance(60);
+    app.tick();
+    advance(600);
+    assert_eq!(app.post("/admin/sweep").await.json()["swept"], json!([unpinned]));
+    for id in [pinned, patched] {
+        assert_eq!(app.session(id).await["state"], "Finished");
+    }
+}
+
+#[tokio::test]
+async fn swept_finished_sessions_are_served_from_the_archive() {
+    let mut app = TestApp::with(|config| {
+        config.finished_ttl = Some(Duration::from_secs(600));
+        config.archive_capacity = 2;
+        config.archive_ttl = Duration::from_secs(300);
+    });
+    let mut ids = Vec::new();
+    for _ in 0..3 {
+        let id = id(&app.create(json!({ "work_minutes": 1, "break_minutes": 0 })).await);
+        app.act(id, "start").await;
+        ids.push(id);
+    }
+    advance(60);
+    app.tick();
+    advance(600);
+    assert_eq!(app.post("/admin/sweep").await.json()["swept"], json!(ids));
+    // Only the last two fit.
+    assert_eq!(app.get(&format!("/sessions/{}", ids[0])).await.status, StatusCode::NOT_FOUND);
+    let s = app.session(ids[1]).await;
+    assert_eq!((&s["state"], &s["elapsed_secs"]), (&json!("Finished"), &json!(60)));
+    assert_eq!(s["archived"], true);
+    assert!(app.ctx.state.lock().unwrap().sessions.is_empty());
+    advance(300);
+    assert_eq!(app.get(&format!("/sessions/{}", ids[2])).await.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn the_sweep_compacts_long_histories_into_counts() {
+    let app = TestApp::with(|config| config.history_max_entries = Some(4));
+    let id = app.create_minutes(25).await;
+    app.act(id, "start").await;
+    for _ in 0..10 {
+        advance(10);
+        app.act(id, "pause").await;
+        app.act(id, "resume").await;
+    }
+    let path = format!("/sessions/{id}/history");
+    assert_eq!(app.get(&path).await.json().as_array().map(Vec::len), Some(21));
+    app.post("/admin/sweep").await;
+    let history = app.get(&path).await.json();
+    let kinds: Vec<_> = history.as_array().unwrap().iter().map(|e| e["event"].clone()).collect();
+    assert_eq!(kinds, ["paused", "resumed", "paused", "resumed"]);
+    // What was dropped is still counted.
+    let s = app.session(id).await;
+    assert_eq!(s["history_compacted"], json!({ "started": 1, "paused": 8, "resumed": 8 }));
+    assert_eq!(s["pauses"], 10);
+}
+
+#[tokio::test]
+async fn entries_past_the_max_age_are_compacted() {
+    let app = TestApp::with(|config| config.history_max_age = Some(Duration::from_secs(3600)));
+    let id = app.create_minutes(600).await;
+    app.act(id, "start").await;
+    advance(3600);
+    app.act(id, "pause").await;
+    // Exactly an hour old is still within it.
+    app.post("/admin/sweep").await;
+    assert_eq!(app.get(&format!("/sessions/{id}/history")).await.json()[0]["event"], "started");
+    advance(1);
+    app.post("/admin/sweep").await;
+    let history = app.get(&format!("/sessions/{id}/history")).await.json();
+    assert_eq!(history.as_array().unwrap().len(), 1);
+    assert_eq!(history[0]["event"], "paused");
+    assert_eq!(app.session(id).await["history_compacted"], json!({ "started": 1 }));
+}
+
+#[tokio::test]
+async fn a_dry_run_sweep_names_what_it_would_remove() {
+    let app = TestApp::with(|config| config.idle_ttl = Some(Duration::from_secs(3600)));
+    let first = app.create_minutes(25).await;
+    let started = app.create_minutes(25).await;
+    app.act(started, "start").await;
+    let last = app.create_minutes(25).await;
+    advance(3600);
+    let before = app.get("/sessions").await.json();
+
+    let dry = app.post("/admin/sweep?dry_run=true").await.json();
+    assert_eq!(dry, json!({ "swept": [first, last], "dry_run": true }));
+    assert_eq!(app.get("/sessions").await.json(), before);
+    let real = app.post("/admin/sweep").await.json();
+    assert_eq!(real, json!({ "swept": [first, last], "dry_run": false }));
+    assert_eq!(app.get("/sessions").await.json().as_array().unwrap().len(), 1);
+}
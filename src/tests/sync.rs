@@ -0,0 +1,73 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use super::{id, TestApp};
+
+async fn changes(app: &TestApp, query: &str) -> Value {
+    let res = app.get(&format!("/sessions?{query}")).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.text());
+    res.json()
+}
+
+fn ids(changes: &Value) -> Vec<u64> {
+    changes["items"].as_array().unwrap().iter().map(id).collect()
+}
+
+#[tokio::test]
+async fn only_sessions_changed_since_the_cursor_come_back() {
+    let app = TestApp::new();
+    let mut created = Vec::new();
+    for _ in 0..5 {
+        created.push(app.create_minutes(25).await);
+    }
+    let cursor = changes(&app, "changed_since=0").await["cursor"].clone();
+    assert_eq!(ids(&changes(&app, "changed_since=0").await), created);
+
+    app.act(created[2], "start").await;
+    let batch = changes(&app, &format!("changed_since={cursor}")).await;
+    assert_eq!(ids(&batch), [created[2]]);
+    assert_eq!(batch["cursor"], batch["items"][0]["version"]);
+    // Caught up: nothing more until the next change.
+    let cursor = batch["cursor"].clone();
+    let batch = changes(&app, &format!("changed_since={cursor}")).await;
+    assert_eq!(batch, json!({ "items": [], "cursor": cursor }));
+}
+
+#[tokio::test]
+async fn a_limited_batch_leaves_the_rest_for_the_next_call() {
+    let app = TestApp::new();
+    let mut created = Vec::new();
+    for _ in 0..5 {
+        created.push(app.create_minutes(25).await);
+    }
+    let first = changes(&app, "changed_since=0&limit=3").await;
+    assert_eq!(ids(&first), created[..3]);
+    let rest = changes(&app, &format!("changed_since={}&limit=3", first["cursor"])).await;
+    assert_eq!(ids(&rest), created[3..]);
+}
+
+#[tokio::test]
+async fn changed_since_must_be_a_version() {
+    let res = TestApp::new().get("/sessions?changed_since=yesterday").await;
+    assert_eq!((res.status, res.error()), (StatusCode::BAD_REQUEST, "invalid_query".into()));
+}
+
+// Requests that leave the session as it was don't hand out a version.
+#[tokio::test]
+async fn no_op_actions_leave_no_change_behind() {
+    let app = TestApp::new();
+    let id = app.create_minutes(25).await;
+    app.act(id, "start").await;
+    app.post(&format!("/sessions/{id}/pause?action_id=a")).await;
+    let cursor = changes(&app, "changed_since=0").await["cursor"].clone();
+    let paused = app.session(id).await;
+    app.ctx.state.lock().unwrap().dirty = false;
+    for path in ["pause?action_id=a", "pause", "resume?action_id=a"] {
+        let res = app.post(&format!("/sessions/{id}/{path}")).await;
+        assert_eq!(res.status, StatusCode::OK, "{path}: {}", res.text());
+        assert_eq!(res.json()["version"], paused["version"], "{path}");
+    }
+    let batch = changes(&app, &format!("changed_since={cursor}")).await;
+    assert_eq!(batch, json!({ "items": [], "cursor": cursor }));
+    assert!(!app.ctx.state.lock().unwrap().dirty);
+}
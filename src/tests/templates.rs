@@ -0,0 +1,123 @@
+use std::fs;
+
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use super::{advance, id, TestApp};
+use crate::{config::StateFormat, persist};
+
+fn template() -> Value {
+    json!({
+        "name": "deep work",
+        "work_minutes": 50,
+        "break_minutes": 10,
+        "pinned": true,
+        "metadata": { "project": "p1" },
+        "color": "#336699",
+    })
+}
+
+#[tokio::test]
+async fn template_crud() {
+    let app = TestApp::new();
+    let res = app.post_json("/templates", template()).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+    let tid = id(&res.json());
+    assert_eq!(app.get(&format!("/templates/{tid}")).await.json()["name"], "deep work");
+    let mut renamed = template();
+    renamed["name"] = json!("shallow work");
+    renamed["work_minutes"] = json!(15);
+    let res = app.put_json(&format!("/templates/{tid}"), renamed).await;
+    assert_eq!(res.json()["work_minutes"], 15);
+    let listed = app.get("/templates").await.json();
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    assert_eq!(listed[0]["name"], "shallow work");
+    assert_eq!(app.delete(&format!("/templates/{tid}")).await.status, StatusCode::NO_CONTENT);
+    assert_eq!(app.get(&format!("/templates/{tid}")).await.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn instantiated_sessions_take_the_template_config() {
+    let app = TestApp::new();
+    let tid = id(&app.post_json("/templates", template()).await.json());
+    let res = app.post(&format!("/templates/{tid}/instantiate")).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.text());
+    let s = res.json();
+    assert_eq!(s["state"], "Idle");
+    for field in ["work_minutes", "break_minutes", "pinned", "metadata", "color"] {
+        assert_eq!(s[field], template()[field], "{field}");
+    }
+    let again = app.post(&format!("/templates/{tid}/instantiate")).await.json();
+    assert_ne!(id(&again), id(&s));
+}
+
+#[tokio::test]
+async fn templates_are_checked_like_creates() {
+    let app = TestApp::new();
+    let mut too_long = template();
+    too_long["work_minutes"] = json!(2000);
+    let res = app.post_json("/templates", too_long).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", res.text());
+    let mut unnamed = template();
+    unnamed["name"] = json!("  ");
+    assert_eq!(app.post_json("/templates", unnamed).await.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(app.post("/templates/9/instantiate").await.status, StatusCode::NOT_FOUND);
+    let res = app.post_json("/templates", json!({ "name": "empty" })).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(res.json()["message"], "work_minutes, pattern or end_at is required");
+}
+
+#[tokio::test]
+async fn templates_persist_with_the_state() {
+    let app = TestApp::new();
+    app.post_json("/templates", template()).await;
+    let path = std::env::temp_dir().join(format!("pomodoro-{}-templates", std::process::id()));
+    for format in [StateFormat::Json, StateFormat::Binary] {
+        app.ctx.state.lock().unwrap().dirty = true;
+        persist::flush(&app.ctx.state, &path, format).unwrap();
+        let loaded = persist::load(&path).unwrap().unwrap();
+        assert_eq!(loaded.templates.len(), 1);
+        assert_eq!(loaded.next_template_id, 1);
+    }
+    fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn patterned_templates_cycle_through_their_rounds() {
+    let mut app = TestApp::new();
+    let short = json!({ "work_minutes": 2, "break_minutes": 1 });
+    let long = json!({ "work_minutes": 2, "break_minutes": 4 });
+    let body = json!({
+        "name": "four and a long break", "auto_reset_after": 1,
+        "pattern": [short, short, short, long],
+    });
+    let tid = id(&app.post_json("/templates", body).await.json());
+    let s = app.post(&format!("/templates/{tid}/instantiate")).await.json();
+    let id = id(&s);
+    let mut rounds = Vec::new();
+    for _ in 0..5 {
+        let s = app.act(id, "start").await;
+        let minutes = |field: &str| s[field].as_u64().unwrap();
+        rounds.push((minutes("work_minutes"), minutes("break_minutes")));
+        advance((minutes("work_minutes") + minutes("break_minutes")) * 60);
+        app.tick();
+        assert_eq!(app.session(id).await["state"], "Finished");
+        advance(1);
+        app.tick();
+    }
+    assert_eq!(rounds, [(2, 1), (2, 1), (2, 1), (2, 4), (2, 1)]);
+    let s = app.session(id).await;
+    assert_eq!((&s["rounds_completed"], &s["pattern_index"]), (&json!(5), &json!(1)));
+}
+
+#[tokio::test]
+async fn instantiating_is_refused_during_maintenance() {
+    let app = TestApp::new();
+    let tid = id(&app.post_json("/templates", template()).await.json());
+    app.post_json("/admin/maintenance", json!({ "enabled": true })).await;
+    let res = app.post(&format!("/templates/{tid}/instantiate")).await;
+    assert_eq!((res.status, res.error()), (StatusCode::SERVICE_UNAVAILABLE, "maintenance".into()));
+    app.post_json("/admin/maintenance", json!({ "enabled": false })).await;
+    let res = app.post(&format!("/templates/{tid}/instantiate")).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+}
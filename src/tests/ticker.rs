@@ -0,0 +1,72 @@
+use serde_json::json;
+
+use super::{advance, id, TestApp};
+use crate::{id::SessionId, PomodoroState};
+
+#[tokio::test]
+async fn finished_session_resets_to_idle_after_the_grace() {
+    let mut app = TestApp::new();
+    let body = json!({ "work_minutes": 1, "break_minutes": 0, "auto_reset_after": 30 });
+    let id = id(&app.create(body).await);
+    app.act(id, "start").await;
+    advance(60);
+    app.tick();
+    assert_eq!(app.session(id).await["state"], "Finished");
+    advance(29);
+    app.tick();
+    assert_eq!(app.session(id).await["state"], "Finished");
+    advance(1);
+    app.tick();
+    let s = app.session(id).await;
+    assert_eq!(s["state"], "Idle");
+    assert_eq!(s["elapsed_secs"], 0);
+    assert_eq!(s["rounds_completed"], 1);
+}
+
+#[tokio::test]
+async fn finished_session_without_a_grace_stays_finished() {
+    let mut app = TestApp::new();
+    let id = id(&app.create(json!({ "work_minutes": 1, "break_minutes": 0 })).await);
+    app.act(id, "start").await;
+    advance(60);
+    app.tick();
+    advance(86_400);
+    app.tick();
+    assert_eq!(app.session(id).await["state"], "Finished");
+}
+
+fn inactivity() -> TestApp {
+    TestApp::with(|c| c.inactivity_pause = Some(std::time::Duration::from_secs(300)))
+}
+
+#[tokio::test]
+async fn an_untouched_running_session_is_paused() {
+    let mut app = inactivity();
+    let id = app.create_minutes(25).await;
+    app.act(id, "start").await;
+    advance(299);
+    app.tick();
+    let stored = |app: &TestApp| app.ctx.state.lock().unwrap().sessions[&SessionId(id)].state;
+    assert_eq!(stored(&app), PomodoroState::Running);
+    advance(1);
+    app.tick();
+    assert_eq!(stored(&app), PomodoroState::Paused);
+    // Paused where the window ran out.
+    assert_eq!(app.session(id).await["elapsed_secs"], 300);
+}
+
+#[tokio::test]
+async fn any_request_for_the_session_keeps_it_running() {
+    let mut app = inactivity();
+    let id = app.create_minutes(25).await;
+    app.act(id, "start").await;
+    for _ in 0..3 {
+        advance(200);
+        app.tick();
+        // Even one that fails counts.
+        app.post_json(&format!("/sessions/{id}/merge"), json!({ "from": id })).await;
+    }
+    advance(299);
+    app.tick();
+    assert_eq!(app.session(id).await["state"], "Running");
+}
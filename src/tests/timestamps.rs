@@ -0,0 +1,72 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{advance, TestApp};
+use crate::{clock, config::TimestampFormat};
+
+#[derive(Deserialize)]
+struct Stamp(#[serde(with = "crate::timestamp")] DateTime<Utc>);
+
+fn read(value: serde_json::Value) -> DateTime<Utc> {
+    serde_json::from_value::<Stamp>(value).unwrap().0
+}
+
+fn to_millis(at: DateTime<Utc>) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(at.timestamp_millis()).unwrap()
+}
+
+#[tokio::test]
+async fn timestamps_are_rfc3339_by_default() {
+    let app = TestApp::new();
+    let created = clock::utc();
+    let s = app.create(json!({ "work_minutes": 1, "start_immediately": true })).await;
+    let text = s["created_at"].as_str().unwrap();
+    assert!(text.ends_with('Z'), "{text}");
+    assert_eq!(read(s["created_at"].clone()), created);
+    advance(60);
+    let s = app.session(super::id(&s)).await;
+    assert_eq!(read(s["finished_at"].clone()), created + chrono::TimeDelta::seconds(60));
+}
+
+#[tokio::test]
+async fn timestamps_are_epoch_millis_when_configured() {
+    let app = TestApp::with(|c| c.timestamp_format = TimestampFormat::EpochMillis);
+    let created = clock::utc();
+    let s = app.create(json!({ "work_minutes": 1, "start_immediately": true })).await;
+    assert_eq!(s["created_at"], created.timestamp_millis());
+    assert_eq!(read(s["created_at"].clone()), to_millis(created));
+    advance(60);
+    let s = app.session(super::id(&s)).await;
+    assert_eq!(s["finished_at"], created.timestamp_millis() + 60_000);
+    // Queries take the same form back.
+    let from = created.timestamp_millis();
+    for (from, total) in [(from, 1), (from + 61_000, 0)] {
+        let res = app.get(&format!("/sessions/completed?from={from}")).await;
+        assert_eq!(res.json()["total"], total, "{}", res.text());
+    }
+}
+
+#[test]
+fn either_form_parses() {
+    let at = to_millis(clock::utc());
+    assert_eq!(read(json!(at.timestamp_millis())), at);
+    assert_eq!(read(json!(at.to_rfc3339())), at);
+    assert_eq!(read(json!(at.timestamp_millis().to_string())), at);
+    assert!(serde_json::from_value::<Stamp>(json!("yesterday")).is_err());
+}
+
+// Responses follow the setting; what gets saved doesn't, so changing it never
+// changes a state file.
+#[tokio::test]
+async fn stored_times_stay_rfc3339_when_responses_use_millis() {
+    let app = TestApp::with(|c| c.timestamp_format = TimestampFormat::EpochMillis);
+    let at = clock::utc();
+    let id = super::id(&app.create(json!({ "work_minutes": 1, "start_immediately": true })).await);
+    let history = app.get(&format!("/sessions/{id}/history")).await.json();
+    assert_eq!(history[0]["at"], at.timestamp_millis());
+    let saved = serde_json::to_value(&*app.ctx.state.lock().unwrap()).unwrap();
+    let stored = &saved["sessions"][id.to_string()]["history"][0]["at"];
+    assert!(stored.is_string(), "{stored}");
+    assert_eq!(read(stored.clone()), at);
+}
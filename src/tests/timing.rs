@@ -0,0 +1,34 @@
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde_json::json;
+
+use super::{advance, id, TestApp};
+use crate::clock::{self, Clock};
+
+#[test]
+fn one_reading_converts_both_ways() {
+    let wall = clock::utc();
+    let reading = Clock::now();
+    assert_eq!(reading.now_system(), wall);
+    let minute = Duration::from_secs(60);
+    let later = reading.now_instant() + minute;
+    let earlier = reading.now_instant() - minute;
+    assert_eq!(reading.system_at(later), wall + TimeDelta::seconds(60));
+    assert_eq!(reading.system_at(earlier), wall - TimeDelta::seconds(60));
+    assert_eq!(reading.project(minute), Some(wall + TimeDelta::seconds(60)));
+}
+
+// A finish noticed long after the fact is still dated when the time ran out.
+#[tokio::test]
+async fn finished_at_is_when_the_time_ran_out() {
+    let app = TestApp::new();
+    let id = id(&app.create(json!({ "work_minutes": 1, "break_minutes": 1 })).await);
+    let start = clock::utc();
+    app.act(id, "start").await;
+    advance(3600);
+    let s = app.session(id).await;
+    assert_eq!(s["state"], "Finished");
+    let finished: DateTime<Utc> = s["finished_at"].as_str().unwrap().parse().unwrap();
+    assert_eq!(finished, start + TimeDelta::minutes(2));
+}
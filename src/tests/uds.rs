@@ -0,0 +1,53 @@
+use std::fs;
+
+use serde_json::Value;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::UnixStream,
+    sync::oneshot,
+};
+
+use super::TestApp;
+use crate::uds;
+
+// One request over its own connection, read until the server closes it.
+async fn request(stream: &mut UnixStream, head: &str, body: &str) -> (u16, Value) {
+    let req = format!(
+        "{head} HTTP/1.1\r\nhost: localhost\r\ncontent-type: application/json\r\n\
+         content-length: {}\r\nconnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(req.as_bytes()).await.unwrap();
+    let mut res = String::new();
+    stream.read_to_string(&mut res).await.unwrap();
+    let status = res[9..12].parse().unwrap();
+    let (_, body) = res.split_once("\r\n\r\n").unwrap();
+    (status, serde_json::from_str(body).unwrap())
+}
+
+#[tokio::test]
+async fn serves_over_a_unix_socket() {
+    let app = TestApp::new();
+    let path = std::env::temp_dir().join(format!("pomodoro-{}.sock", std::process::id()));
+    // Left over from an earlier run; serving replaces it.
+    fs::write(&path, "stale").unwrap();
+    let (stop, stopped) = oneshot::channel::<()>();
+    let shutdown = async move {
+        let _ = stopped.await;
+    };
+    // `join!` polls the server first, so the socket is bound before the client connects.
+    let client = async {
+        let mut stream = UnixStream::connect(&path).await.unwrap();
+        let (status, created) =
+            request(&mut stream, "POST /sessions", r#"{"work_minutes":25}"#).await;
+        assert_eq!(status, 201);
+        let mut stream = UnixStream::connect(&path).await.unwrap();
+        let get = format!("GET /sessions/{}", created["id"]);
+        let (status, got) = request(&mut stream, &get, "").await;
+        assert_eq!((status, got["work_minutes"].as_u64()), (200, Some(25)));
+        stop.send(()).unwrap();
+    };
+    let (served, ()) = tokio::join!(uds::serve(&path, app.router.clone(), shutdown), client);
+    served.unwrap();
+    assert!(!path.exists());
+}
@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use super::{advance, receiver, TestApp};
+use crate::{config::Config, serve, webhook};
+
+use webhook::{Deliveries, Webhook};
+
+fn config(attempts: u32) -> Config {
+    Config {
+        webhook_attempts: attempts,
+        webhook_backoff: Duration::from_millis(10),
+        ..Config::default()
+    }
+}
+
+#[tokio::test]
+async fn failed_deliveries_are_retried_until_one_succeeds() {
+    let (url, seen) = receiver(2).await;
+    let deliveries = Deliveries::default();
+    Webhook::new(&config(5), &deliveries, url).spawn(b"{}".to_vec());
+    deliveries.drain(Duration::from_secs(5)).await;
+    assert_eq!(seen.lock().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn delivery_gives_up_after_the_last_attempt() {
+    let (url, seen) = receiver(usize::MAX).await;
+    let deliveries = Deliveries::default();
+    Webhook::new(&config(3), &deliveries, url).spawn(b"{}".to_vec());
+    deliveries.drain(Duration::from_secs(5)).await;
+    assert_eq!(seen.lock().unwrap().len(), 3);
+}
+
+// Served over a socket so the test needn't take the real port. The first
+// delivery fails, so the finish is only reported after a retry.
+#[cfg(unix)]
+#[tokio::test]
+async fn shutdown_waits_for_a_finish_to_be_delivered() {
+    let (url, seen) = receiver(1).await;
+    let path = std::env::temp_dir().join(format!("pomodoro-{}-drain.sock", std::process::id()));
+    let mut app = TestApp::with(|c| {
+        c.uds = Some(path);
+        c.webhook_url = Some(url);
+        c.webhook_attempts = 2;
+        c.webhook_backoff = Duration::from_millis(200);
+    });
+    let id = app.create_minutes(1).await;
+    app.act(id, "start").await;
+    advance(60);
+    app.tick();
+
+    serve(&app.ctx, app.router.clone(), async {}).await.unwrap();
+    assert_eq!(seen.lock().unwrap().len(), 2);
+}
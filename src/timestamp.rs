@@ -0,0 +1,106 @@
+use std::fmt;
+
+use chrono::{DateTime, SecondsFormat, TimeZone, Utc};
+use serde::{de, Deserializer, Serialize, Serializer};
+
+use crate::config::TimestampFormat;
+
+// A time as responses show it, in the format the server is configured with.
+// Stored times keep their own encoding (see `serialize`); this is only built
+// on the way out.
+#[derive(Debug, Clone, Copy)]
+pub struct Stamp {
+    at: DateTime<Utc>,
+    format: TimestampFormat,
+}
+
+impl Stamp {
+    pub fn new(at: DateTime<Utc>, format: TimestampFormat) -> Self {
+        Self { at, format }
+    }
+
+    pub fn render(&self) -> String {
+        match self.format {
+            TimestampFormat::Rfc3339 => rfc3339(&self.at),
+            TimestampFormat::EpochMillis => self.at.timestamp_millis().to_string(),
+        }
+    }
+}
+
+impl Serialize for Stamp {
+    fn serialize<S: Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        match self.format {
+            TimestampFormat::Rfc3339 => ser.serialize_str(&rfc3339(&self.at)),
+            TimestampFormat::EpochMillis => ser.serialize_i64(self.at.timestamp_millis()),
+        }
+    }
+}
+
+fn rfc3339(at: &DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+// For stored times: RFC 3339 in the JSON state file, and chrono's own encoding
+// in binary formats (the postcard state file), whatever the output setting, so
+// switching it never changes what a saved file holds.
+pub fn serialize<S: Serializer>(at: &DateTime<Utc>, ser: S) -> Result<S::Ok, S::Error> {
+    if !ser.is_human_readable() {
+        return serde::Serialize::serialize(at, ser);
+    }
+    ser.serialize_str(&rfc3339(at))
+}
+
+// Accepts either form regardless of the configured output format, so state
+// files written when stored times followed it still load.
+pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<DateTime<Utc>, D::Error> {
+    if !de.is_human_readable() {
+        return serde::Deserialize::deserialize(de);
+    }
+    de.deserialize_any(TimestampVisitor)
+}
+
+// Either form, as text: RFC 3339 or epoch milliseconds.
+pub fn parse(v: &str) -> Option<DateTime<Utc>> {
+    if let Ok(millis) = v.parse::<i64>() {
+        return Utc.timestamp_millis_opt(millis).single();
+    }
+    DateTime::parse_from_rfc3339(v).ok().map(|at| at.with_timezone(&Utc))
+}
+
+struct TimestampVisitor;
+
+impl de::Visitor<'_> for TimestampVisitor {
+    type Value = DateTime<Utc>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an RFC 3339 timestamp or epoch milliseconds")
+    }
+
+    fn visit_i64<E: de::Error>(self, millis: i64) -> Result<Self::Value, E> {
+        Utc.timestamp_millis_opt(millis)
+            .single()
+            .ok_or_else(|| E::custom("timestamp out of range"))
+    }
+
+    fn visit_u64<E: de::Error>(self, millis: u64) -> Result<Self::Value, E> {
+        let millis = i64::try_from(millis).map_err(|_| E::custom("timestamp out of range"))?;
+        self.visit_i64(millis)
+    }
+
+    // Query strings deliver numbers as text too.
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        parse(v).ok_or_else(|| E::custom(format!("invalid timestamp {v:?}")))
+    }
+}
+
+pub mod option {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer};
+
+    #[derive(Deserialize)]
+    struct Wrapped(#[serde(with = "super")] DateTime<Utc>);
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<Option<DateTime<Utc>>, D::Error> {
+        Ok(Option::<Wrapped>::deserialize(de)?.map(|Wrapped(at)| at))
+    }
+}
@@ -0,0 +1,44 @@
+use std::{fs, future::Future, io, path::Path};
+
+use axum::Router;
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::{conn::auto::Builder, graceful::GracefulShutdown},
+    service::TowerToHyperService,
+};
+use tokio::net::UnixListener;
+
+// axum::serve only speaks TCP, so Unix socket connections are driven by hyper
+// directly. On shutdown, like axum::serve, it stops accepting and waits for the
+// open connections to finish their in-flight requests.
+pub async fn serve(path: &Path, app: Router, shutdown: impl Future<Output = ()>) -> io::Result<()> {
+    remove_stale(path)?;
+    let listener = UnixListener::bind(path)?;
+    let builder = Builder::new(TokioExecutor::new());
+    let graceful = GracefulShutdown::new();
+    tokio::pin!(shutdown);
+    loop {
+        let (socket, _) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            () = &mut shutdown => break,
+        };
+        let service = TowerToHyperService::new(app.clone());
+        let conn = builder.serve_connection_with_upgrades(TokioIo::new(socket), service);
+        let conn = graceful.watch(conn.into_owned());
+        tokio::spawn(async move {
+            if let Err(err) = conn.await {
+                tracing::warn!(error = %err, "failed to serve unix socket connection");
+            }
+        });
+    }
+    drop(listener);
+    graceful.shutdown().await;
+    remove_stale(path)
+}
+
+fn remove_stale(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
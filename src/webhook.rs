@@ -0,0 +1,159 @@
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use axum::http::header;
+use tokio::sync::watch;
+
+use crate::config::Config;
+
+#[derive(Clone)]
+pub struct Webhook {
+    client: reqwest::Client,
+    deliveries: Deliveries,
+    url: String,
+    attempts: u32,
+    backoff: Duration,
+}
+
+impl Webhook {
+    pub fn from_config(config: &Config, deliveries: &Deliveries) -> Option<Arc<Self>> {
+        Some(Self::new(config, deliveries, config.webhook_url.clone()?))
+    }
+
+    // Same retry policy as the main webhook, different destination.
+    pub fn new(config: &Config, deliveries: &Deliveries, url: String) -> Arc<Self> {
+        Arc::new(Self {
+            client: reqwest::Client::new(),
+            deliveries: deliveries.clone(),
+            url,
+            attempts: config.webhook_attempts.max(1),
+            backoff: config.webhook_backoff,
+        })
+    }
+
+    // Delivers in the background so the caller (the ticker) never waits on the network.
+    pub fn spawn(&self, body: Vec<u8>) {
+        let webhook = self.clone();
+        let in_flight = InFlight::start(&self.deliveries, &self.url);
+        tokio::spawn(async move {
+            webhook.deliver(body).await;
+            drop(in_flight);
+        });
+    }
+
+    // Retries failures with exponential backoff (backoff, 2x, 4x, ...) plus jitter.
+    async fn deliver(&self, body: Vec<u8>) {
+        let mut delay = self.backoff;
+        for attempt in 1..=self.attempts {
+            let sent = self
+                .client
+                .post(&self.url)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(body.clone())
+                .send()
+                .await
+                .and_then(|res| res.error_for_status());
+            match sent {
+                Ok(_) => return,
+                Err(err) if attempt == self.attempts => {
+                    tracing::warn!(
+                        url = redact(&self.url),
+                        attempts = attempt,
+                        error = %err.without_url(),
+                        "giving up on webhook"
+                    );
+                }
+                Err(_) => {
+                    tokio::time::sleep(delay + jitter(delay / 4)).await;
+                    delay *= 2;
+                }
+            }
+        }
+    }
+}
+
+// Deliveries still running, counted per URL. The guard is dropped when the
+// task ends, including when the runtime cancels it.
+#[derive(Clone)]
+pub struct Deliveries(Arc<watch::Sender<BTreeMap<String, usize>>>);
+
+impl Default for Deliveries {
+    fn default() -> Self {
+        Self(Arc::new(watch::Sender::new(BTreeMap::new())))
+    }
+}
+
+impl Deliveries {
+    // Waits up to `grace` for every spawned delivery to finish, then reports the
+    // ones that are still going; they are abandoned when the runtime shuts down.
+    pub async fn drain(&self, grace: Duration) {
+        let mut rx = self.0.subscribe();
+        if tokio::time::timeout(grace, rx.wait_for(BTreeMap::is_empty)).await.is_ok() {
+            return;
+        }
+        for (url, count) in self.0.borrow().iter() {
+            tracing::warn!(
+                url = redact(url),
+                count,
+                "shutting down with webhook deliveries unfinished"
+            );
+        }
+    }
+}
+
+struct InFlight {
+    deliveries: Deliveries,
+    url: String,
+}
+
+impl InFlight {
+    fn start(deliveries: &Deliveries, url: &str) -> Self {
+        deliveries.0.send_modify(|urls| *urls.entry(url.to_string()).or_default() += 1);
+        Self {
+            deliveries: deliveries.clone(),
+            url: url.to_string(),
+        }
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.deliveries.0.send_modify(|urls| {
+            if let Some(count) = urls.get_mut(&self.url) {
+                *count -= 1;
+                if *count == 0 {
+                    urls.remove(&self.url);
+                }
+            }
+        });
+    }
+}
+
+// Webhook URLs often carry credentials in the userinfo, path or query, so logs
+// only name the scheme, host and port.
+fn redact(url: &str) -> String {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return "<unparseable url>".to_string();
+    };
+    match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{}://{host}:{port}", url.scheme()),
+        (Some(host), None) => format!("{}://{host}", url.scheme()),
+        (None, _) => format!("{}:", url.scheme()),
+    }
+}
+
+// Cheap jitter from the clock's sub-second noise; good enough to spread retries.
+fn jitter(max: Duration) -> Duration {
+    let max_nanos = max.as_nanos() as u64;
+    if max_nanos == 0 {
+        return Duration::ZERO;
+    }
+    let noise = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or_default();
+    Duration::from_nanos(noise % max_nanos)
+}